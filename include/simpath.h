@@ -0,0 +1,53 @@
+#ifndef SIMPATH_H
+#define SIMPATH_H
+
+/* This file is generated by cbindgen from src/ffi.rs. Do not edit it. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// `Simpath` is the struct returned when you create a new on using a named environment variable
+// which you then use to interact with the `Simpath`
+typedef struct Simpath Simpath;
+
+// Create a new `Simpath`, loading its entries from the environment variable `var_name`, as
+// `Simpath::new()` does. Returns NULL if `var_name` is NULL or not valid UTF-8.
+//
+// # Safety
+// `var_name` must be NULL or a valid nul-terminated string. The `Simpath` returned must be
+// freed with `simpath_free()`.
+Simpath *simpath_new(const char *var_name);
+
+// Free a `Simpath` created with `simpath_new()`. Does nothing if `simpath` is NULL.
+//
+// # Safety
+// `simpath` must be NULL or a `Simpath` returned by `simpath_new()` that has not been freed.
+void simpath_free(Simpath *simpath);
+
+// Add an entry (a directory or, with the "urls" feature, a Url) to a `Simpath`, as
+// `Simpath::add()` does. Returns 0 on success, or -1 if `simpath` or `entry` is NULL or
+// `entry` is not valid UTF-8.
+//
+// # Safety
+// `simpath` must be NULL or a valid `Simpath` returned by `simpath_new()`, and `entry` must be
+// NULL or a valid nul-terminated string.
+int simpath_add(Simpath *simpath, const char *entry);
+
+// Find a file, directory or resource called `name` in a `Simpath`, as `Simpath::find()` does.
+// Returns the path (or Url) of the entry found, which must be freed with
+// `simpath_string_free()`, or NULL if it was not found or an argument is not valid.
+//
+// # Safety
+// `simpath` must be NULL or a valid `Simpath` returned by `simpath_new()`, and `name` must be
+// NULL or a valid nul-terminated string.
+char *simpath_find(const Simpath *simpath, const char *name);
+
+// Free a string returned by one of these functions. Does nothing if `string` is NULL.
+//
+// # Safety
+// `string` must be NULL or a string returned by one of these functions that has not been freed.
+void simpath_string_free(char *string);
+
+#endif  /* SIMPATH_H */
@@ -0,0 +1,202 @@
+//! `simpath` is a command line tool that exposes the functionality of the `simpath` crate, to
+//! find files in, list, and validate search paths defined in environment variables.
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use simpath::{FileType, FindReport, FoundType, Simpath};
+
+#[derive(Parser)]
+#[command(name = "simpath", version, about = "Find files on search paths defined in environment variables")]
+struct Options {
+    /// Print output as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Separator character used to parse the environment variable
+    #[arg(long, global = true)]
+    separator: Option<char>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Find a file or directory in the search path
+    Find {
+        /// The name of the file or directory to find
+        name: String,
+        /// The environment variable that defines the search path
+        #[arg(long, default_value = "PATH")]
+        var: String,
+        /// The type of entry to find
+        #[arg(long = "type", value_enum, default_value = "any")]
+        file_type: Type,
+    },
+    /// List the entries in the search path
+    List {
+        /// The environment variable that defines the search path
+        #[arg(long, default_value = "PATH")]
+        var: String,
+        /// List all the files in the search path instead of its entries
+        #[arg(long)]
+        files: bool,
+    },
+    /// Validate that all the directories in the search path exist and can be read
+    Validate {
+        /// The environment variable that defines the search path
+        #[arg(long, default_value = "PATH")]
+        var: String,
+    },
+    /// Find executables in the search path, as the `which` command does
+    Which {
+        /// The name of the executable to find
+        name: String,
+        /// The environment variable that defines the search path
+        #[arg(long, default_value = "PATH")]
+        var: String,
+        /// Find all matching executables, not just the first
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Type {
+    Any,
+    File,
+    Directory,
+}
+
+impl From<Type> for FileType {
+    fn from(file_type: Type) -> Self {
+        match file_type {
+            Type::Any => FileType::Any,
+            Type::File => FileType::File,
+            Type::Directory => FileType::Directory,
+        }
+    }
+}
+
+fn main() {
+    let options = Options::parse();
+
+    let success = match &options.command {
+        Command::Find { name, var, file_type } => {
+            let search_path = simpath(var, &options);
+            if options.json {
+                print_report(&search_path.find_report(name, (*file_type).into()))
+            } else {
+                print_found(search_path.find_type(name, (*file_type).into()).map(|found| vec!(found)))
+            }
+        }
+        Command::List { var, files } => list(&simpath(var, &options), *files, options.json),
+        Command::Validate { var } => validate(&simpath(var, &options), options.json),
+        Command::Which { name, var, all } => {
+            let search_path = simpath(var, &options);
+            let found = if *all {
+                search_path.which_all(name)
+            } else {
+                search_path.which(name).map(|path| vec!(path))
+            };
+            if options.json {
+                print_report(&which_report(&search_path, name, found))
+            } else {
+                print_found(found.map(|paths| paths.into_iter().map(FoundType::File).collect()))
+            }
+        }
+    };
+
+    if !success {
+        exit(1);
+    }
+}
+
+// Create the `Simpath` from the environment variable, using the separator if one was specified
+fn simpath(var: &str, options: &Options) -> Simpath {
+    match options.separator {
+        Some(separator) => Simpath::new_with_separator(var, separator),
+        None => Simpath::new(var),
+    }
+}
+
+// Describe the executables found by `which` or `which_all` as a `FindReport`
+fn which_report(search_path: &Simpath, name: &str, found: Result<Vec<PathBuf>, std::io::Error>) -> FindReport {
+    let report = FindReport {
+        search_path: search_path.name().to_string(),
+        name: name.to_string(),
+        file_type: Some(FileType::File),
+        ..FindReport::default()
+    };
+    match found {
+        Ok(paths) => FindReport { matched: paths.iter().map(|path| path.display().to_string()).collect(), ..report },
+        Err(e) => FindReport { error: Some(e.to_string()), ..report },
+    }
+}
+
+fn print_found(result: Result<Vec<FoundType>, std::io::Error>) -> bool {
+    match result {
+        Ok(found) => {
+            for found in found {
+                println!("{}", found);
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            false
+        }
+    }
+}
+
+fn print_report(report: &FindReport) -> bool {
+    println!("{}", serde_json::to_string(report).unwrap_or_default());
+    !report.matched.is_empty()
+}
+
+fn list(search_path: &Simpath, files: bool, json: bool) -> bool {
+    let mut entries: Vec<String> = vec!();
+    let mut success = true;
+
+    if files {
+        for file in search_path.walk().unique(true) {
+            match file {
+                Ok(path) => entries.push(path.display().to_string()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    success = false;
+                }
+            }
+        }
+    } else {
+        entries.extend(search_path.entries().iter().filter(|entry| entry.is_enabled()).map(|entry| entry.to_string()));
+    }
+
+    if json {
+        println!("{}", json!({ "name": search_path.name(), "entries": entries }));
+    } else {
+        for entry in entries {
+            println!("{}", entry);
+        }
+    }
+
+    success
+}
+
+fn validate(search_path: &Simpath, json: bool) -> bool {
+    let report = search_path.validate_report();
+
+    if json {
+        println!("{}", serde_json::to_string(&report).unwrap_or_default());
+    } else if report.invalid.is_empty() {
+        println!("All directories in '{}' are valid", search_path.name());
+    } else {
+        for invalid in &report.invalid {
+            println!("'{}': {}", invalid.entry, invalid.reason);
+        }
+    }
+
+    report.invalid.is_empty()
+}
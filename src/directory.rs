@@ -0,0 +1,2104 @@
+//! The entries of a `Simpath`, and its directory entries: adding and managing them, filtering
+//! the files in them, and searching them for files.
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "urls")]
+use url::Url;
+
+use super::{FileType, FoundType, GlobName, NameMatcher, PathError, PathName, Simpath, SkippedEntry};
+use super::search::{SearchContext, SearchEntry, Stat};
+#[cfg(feature = "urls")]
+use super::url::UrlEntry;
+
+// The rules for excluding files from discovery-style searches
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Exclusions {
+    hidden: bool,
+    patterns: Vec<GlobName>,
+    #[cfg(feature = "ignore")]
+    ignore_files: bool,
+}
+
+// The exclusion rules specific to one directory of the search path (and its sub-directories)
+#[derive(Default)]
+pub(crate) struct DirExclusions {
+    filter: Option<EntryFilter>,
+    #[cfg(feature = "ignore")]
+    ignore: Option<ignore::gitignore::Gitignore>,
+}
+
+/// `EntrySource` describes where an entry in a `Simpath` came from
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum EntrySource {
+    /// The entry was read from the environment variable with this name
+    EnvVar(String),
+    /// The entry was read from this configuration file
+    ConfigFile(PathBuf),
+    /// The entry is a default, used as the environment variable was not set
+    Default,
+    /// The entry was added programmatically, e.g. using `add_directory()`
+    Programmatic,
+}
+
+impl fmt::Display for EntrySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntrySource::EnvVar(var_name) => write!(f, "environment variable '{}'", var_name),
+            EntrySource::ConfigFile(path) => write!(f, "config file '{}'", path.display()),
+            EntrySource::Default => write!(f, "defaults"),
+            EntrySource::Programmatic => write!(f, "added programmatically"),
+        }
+    }
+}
+
+/// `EntryDecision` is what was decided about an entry parsed from an environment variable, as
+/// passed to the handler set with `Simpath::on_entry()`
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntryDecision {
+    /// The entry was added to the search path
+    Added,
+    /// The entry was ignored, for this reason (e.g. it is empty or already in the search path)
+    Ignored(String),
+    /// The entry was rejected, for this reason (see `Simpath::rejected_entries()`)
+    Rejected(String),
+}
+
+impl fmt::Display for EntryDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryDecision::Added => write!(f, "added"),
+            EntryDecision::Ignored(reason) => write!(f, "ignored: {}", reason),
+            EntryDecision::Rejected(reason) => write!(f, "rejected: {}", reason),
+        }
+    }
+}
+
+// A callback that is passed each entry parsed from an environment variable, with where it came
+// from and what was decided about it
+type EntryCallback = dyn Fn(&str, &EntrySource, &EntryDecision) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct EntryHandler(Arc<EntryCallback>);
+
+impl fmt::Debug for EntryHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EntryHandler")
+    }
+}
+
+/// `Entry` is an entry in a `Simpath`: a directory or, if the "urls" feature is enabled, a base
+/// Url. The entries of a `Simpath` are kept in one list, in the order they are searched.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// A directory, which is searched for files
+    Directory(DirectoryEntry),
+    #[cfg(feature = "urls")]
+    /// A base Url, which is searched for resources
+    Url(UrlEntry),
+}
+
+impl Entry {
+    // Get the information kept about the entry, whatever kind of entry it is
+    pub(crate) fn info(&self) -> &EntryInfo {
+        match self {
+            Entry::Directory(directory) => &directory.info,
+            #[cfg(feature = "urls")]
+            Entry::Url(url) => &url.info,
+        }
+    }
+
+    // Get the information kept about the entry, to change it
+    pub(crate) fn info_mut(&mut self) -> &mut EntryInfo {
+        match self {
+            Entry::Directory(directory) => &mut directory.info,
+            #[cfg(feature = "urls")]
+            Entry::Url(url) => &mut url.info,
+        }
+    }
+
+    /// Get the `DirectoryEntry` if the entry is a directory
+    pub fn as_directory(&self) -> Option<&DirectoryEntry> {
+        match self {
+            Entry::Directory(directory) => Some(directory),
+            #[cfg(feature = "urls")]
+            Entry::Url(_) => None,
+        }
+    }
+
+    // Get the `DirectoryEntry` if the entry is a directory, to change it
+    pub(crate) fn as_directory_mut(&mut self) -> Option<&mut DirectoryEntry> {
+        match self {
+            Entry::Directory(directory) => Some(directory),
+            #[cfg(feature = "urls")]
+            Entry::Url(_) => None,
+        }
+    }
+
+    #[cfg(feature = "urls")]
+    /// Get the `UrlEntry` if the entry is a Url
+    pub fn as_url(&self) -> Option<&UrlEntry> {
+        match self {
+            Entry::Directory(_) => None,
+            Entry::Url(url) => Some(url),
+        }
+    }
+
+    /// Get the path of the directory, if the entry is a directory
+    pub fn path(&self) -> Option<&Path> {
+        self.as_directory().map(DirectoryEntry::path)
+    }
+
+    #[cfg(feature = "urls")]
+    /// Get the Url, if the entry is a Url
+    pub fn url(&self) -> Option<&Url> {
+        self.as_url().map(UrlEntry::url)
+    }
+
+    /// Get where the entry came from
+    pub fn source(&self) -> &EntrySource {
+        &self.info().source
+    }
+
+    /// Get the priority of the entry, used by `Simpath::sort_by_priority()`. By default it is 0.
+    pub fn priority(&self) -> i32 {
+        self.info().priority
+    }
+
+    /// Get whether the entry is enabled. Disabled entries are not searched.
+    pub fn is_enabled(&self) -> bool {
+        self.info().enabled
+    }
+
+    /// Get the `EntryFilter` applied to the files in the entry, if it is a directory that one
+    /// has been set for
+    pub fn filter(&self) -> Option<&EntryFilter> {
+        self.as_directory().and_then(DirectoryEntry::filter)
+    }
+
+    /// Get the annotations attached to the entry with `Simpath::annotate_entry()`
+    pub fn annotations(&self) -> &HashMap<String, String> {
+        &self.info().annotations
+    }
+
+    /// Get the value of the annotation `key` attached to the entry, if it has one
+    pub fn annotation(&self, key: &str) -> Option<&str> {
+        self.info().annotations.get(key).map(String::as_str)
+    }
+
+    /// Get the directory of the wildcard entry (such as `plugins/*`) that this entry was expanded
+    /// from, if it is a directory that was
+    pub fn expanded_from(&self) -> Option<&Path> {
+        self.as_directory().and_then(DirectoryEntry::expanded_from)
+    }
+}
+
+// Displays the path of a directory entry, or a Url entry, as it is shown in `to_env_string()`
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Directory(directory) => write!(f, "{}", directory.path.display()),
+            #[cfg(feature = "urls")]
+            Entry::Url(url) => write!(f, "{}", url.url),
+        }
+    }
+}
+
+// The information kept about every entry of a `Simpath`, whatever kind of entry it is
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct EntryInfo {
+    pub(crate) source: EntrySource,
+    pub(crate) priority: i32,
+    pub(crate) enabled: bool,
+    pub(crate) annotations: HashMap<String, String>,
+}
+
+impl EntryInfo {
+    pub(crate) fn new(source: EntrySource) -> Self {
+        EntryInfo { source, priority: 0, enabled: true, annotations: HashMap::new() }
+    }
+}
+
+/// `DirectoryEntry` is a directory in a `Simpath`, along with information about it
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectoryEntry {
+    pub(crate) path: Arc<Path>,
+    // The form of `path` compared with other directories, so that checking for duplicates does
+    // not canonicalize every entry again
+    comparable: PathBuf,
+    filter: Option<EntryFilter>,
+    wildcard: Option<Arc<Path>>,
+    pub(crate) info: EntryInfo,
+}
+
+impl DirectoryEntry {
+    /// Get the path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the `EntryFilter` applied to the files in the directory, if one has been set
+    pub fn filter(&self) -> Option<&EntryFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Get the directory of the wildcard entry (such as `plugins/*`) that this entry was expanded
+    /// from, if it was
+    pub fn expanded_from(&self) -> Option<&Path> {
+        self.wildcard.as_deref()
+    }
+}
+
+// A wildcard entry that was expanded to the subdirectories of `base` (`plugins/*`), or if it is
+// recursive to `base` and all the directories below it (`/opt/tools/**`)
+#[derive(Clone, Debug)]
+pub(crate) struct Wildcard {
+    base: Arc<Path>,
+    pub(crate) source: EntrySource,
+    recursive: bool,
+    // Where the directories it expands to are in the list of entries, kept so that they are put
+    // back there when it is refreshed even if it expanded to none
+    position: usize,
+}
+
+// The number of levels of subdirectories a recursive wildcard entry is expanded to, unless
+// another limit is set
+pub(crate) const DEFAULT_MAX_WILDCARD_DEPTH: usize = 8;
+
+// A directory is searched by reading the entries in it, or for a relative path with several
+// components (e.g. `sys/types.h`) by checking the path joined to it
+impl SearchEntry for Arc<Path> {
+    fn location(&self) -> String {
+        self.display().to_string()
+    }
+
+    fn search(&self, context: &mut SearchContext<'_>, file_name: &str, file_type: &FileType)
+        -> Result<(), Error> {
+        if *file_type == FileType::Resource {
+            return Ok(());
+        }
+
+        let simpath = context.simpath;
+        let search_dir = self;
+        let relative_path = Simpath::relative_path(file_name)?;
+        if let Some(ref relative_path) = relative_path {
+            if !simpath.may_contain(search_dir, relative_path) {
+                trace_event!(debug, directory = %search_dir.display(), "skipped directory using its bloom filter");
+                return Ok(());
+            }
+        }
+
+        simpath.check_cancelled(file_name)?;
+        trace_event!(debug, directory = %search_dir.display(), "searching directory");
+        simpath.count(Stat::Directory);
+        context.searched(search_dir.display());
+        let found_before = context.found.len();
+
+        if let Some(ref relative_path) = relative_path {
+            simpath.count(Stat::File);
+            context.files_scanned += 1;
+            let path = search_dir.join(relative_path);
+            context.compared(path.display());
+            if simpath.has_exclusions()
+                && simpath.excluded_relative(&simpath.dir_exclusions(search_dir), search_dir, relative_path) {
+                if path.exists() {
+                    context.skipped(path.display(), "It is excluded".into());
+                }
+                return Ok(());
+            }
+            match simpath.found_type(path.clone(), file_type) {
+                Ok(Some(entry)) => context.add_found(entry),
+                Ok(None) => {
+                    if path.exists() {
+                        context.skipped(path.display(), format!("It is not of type '{:?}'", file_type));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                    simpath.notify_error(&path, &e);
+                    context.skipped(path.display(), e.to_string());
+                    context.denied(e);
+                }
+                Err(e) => {
+                    context.skipped(path.display(), e.to_string());
+                    simpath.handle_error(&path, e)?;
+                }
+            }
+        } else {
+            let dir_exclusions = simpath.dir_exclusions(search_dir);
+            let entries = match fs::read_dir(search_dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                    simpath.notify_error(search_dir, &e);
+                    context.skipped(search_dir.display(), e.to_string());
+                    context.denied(Error::new(ErrorKind::PermissionDenied,
+                                              format!("Could not search directory '{}' in {}: {}",
+                                                      search_dir.display(), PathName(&simpath.name), e)));
+                    return Ok(());
+                }
+                Err(e) => {
+                    context.skipped(search_dir.display(), e.to_string());
+                    simpath.handle_error(search_dir, e)?;
+                    return Ok(());
+                }
+            };
+
+            for entry in entries {
+                let file = match entry {
+                    Ok(file) => file,
+                    Err(e) => {
+                        simpath.handle_error(search_dir, e)?;
+                        continue;
+                    }
+                };
+                simpath.count(Stat::File);
+                context.files_scanned += 1;
+                context.compared(file.path().display());
+                if let Some(filename) = file.file_name().to_str() {
+                    if simpath.names_match(filename, file_name) {
+                        if simpath.excluded(&dir_exclusions, &file) {
+                            context.skipped(file.path().display(), "It is excluded".into());
+                            continue;
+                        }
+                        let found_filetype = match file.metadata() {
+                            Ok(metadata) => metadata.file_type(),
+                            Err(e) => {
+                                context.skipped(file.path().display(), e.to_string());
+                                simpath.handle_error(&file.path(), e)?;
+                                continue;
+                            }
+                        };
+                        match Simpath::matching_type(file.path(), found_filetype.into(), file_type) {
+                            Some(entry) => match simpath.check_access(&file.path()) {
+                                Ok(()) => context.add_found(entry),
+                                Err(e) => {
+                                    simpath.notify_error(&file.path(), &e);
+                                    context.skipped(file.path().display(), e.to_string());
+                                    context.denied(e);
+                                }
+                            },
+                            None => context.skipped(file.path().display(),
+                                                    format!("It is not of type '{:?}'", file_type)),
+                        }
+                    }
+                }
+            }
+        }
+
+        if context.found.len() > found_before {
+            simpath.count_hit(search_dir.display().to_string());
+        }
+        simpath.notify_progress(search_dir, context.files_scanned);
+        Ok(())
+    }
+
+    fn search_where(&self, context: &mut SearchContext<'_>, matcher: &dyn NameMatcher, file_type: &FileType)
+        -> Result<(), Error> {
+        if *file_type == FileType::Resource {
+            return Ok(());
+        }
+
+        let simpath = context.simpath;
+        let search_dir = self;
+        simpath.check_cancelled("matching names")?;
+        let dir_exclusions = simpath.dir_exclusions(search_dir);
+        let entries = match fs::read_dir(search_dir) {
+            Ok(entries) => entries,
+            Err(e) => return simpath.handle_error(search_dir, e),
+        };
+
+        for entry in entries {
+            let file = match entry {
+                Ok(file) => file,
+                Err(e) => {
+                    simpath.handle_error(search_dir, e)?;
+                    continue;
+                }
+            };
+            if !file.file_name().to_str().map(|name| matcher.matches(name)).unwrap_or(false)
+                || simpath.excluded(&dir_exclusions, &file) {
+                continue;
+            }
+            let found_filetype = match file.metadata() {
+                Ok(metadata) => metadata.file_type(),
+                Err(e) => {
+                    simpath.handle_error(&file.path(), e)?;
+                    continue;
+                }
+            };
+            if let Some(entry) = Simpath::matching_type(file.path(), found_filetype.into(), file_type) {
+                match simpath.check_access(&file.path()) {
+                    Ok(()) => context.add_found(entry),
+                    Err(e) => context.denied(e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "urls")]
+    fn contains(&self, found: &FoundType) -> bool {
+        match found {
+            FoundType::Resource(_) => false,
+            _ => Path::new(&Simpath::location(found)).parent() == Some(&**self),
+        }
+    }
+
+    fn subdirectories(&self, context: &SearchContext<'_>, follow_symlinks: bool) -> Vec<PathBuf> {
+        let simpath = context.simpath;
+        let dir_exclusions = simpath.dir_exclusions(self);
+        let mut subdirectories: Vec<PathBuf> = match fs::read_dir(self) {
+            Ok(entries) => entries.flatten()
+                .filter(|entry| !simpath.excluded(&dir_exclusions, entry))
+                .filter(|entry| match entry.file_type() {
+                    Ok(file_type) if file_type.is_symlink() => follow_symlinks && entry.path().is_dir(),
+                    Ok(file_type) => file_type.is_dir(),
+                    Err(_) => false,
+                })
+                .map(|entry| entry.path())
+                .collect(),
+            Err(_) => vec!(),
+        };
+        subdirectories.sort();
+        subdirectories
+    }
+}
+
+// The directories shared by the entries of all `Simpath`s
+#[derive(Default)]
+struct Interned {
+    paths: HashSet<Arc<Path>>,
+    // The number of directories at which those no longer in any `Simpath` are dropped
+    prune_at: usize,
+}
+
+// The smallest number of shared directories at which those no longer used are dropped
+const MIN_INTERNED_PRUNE_AT: usize = 64;
+
+// Get the shared copy of the directory `path`, so that the same directory in many `Simpath`s
+// (such as those created for each request from the same base entries) is stored only once.
+// Only used for `Simpath`s that have set `set_share_directories()`
+pub(crate) fn intern(path: &Path) -> Arc<Path> {
+    static INTERNED: OnceLock<Mutex<Interned>> = OnceLock::new();
+    let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(shared) = interned.paths.get(path) {
+        return shared.clone();
+    }
+
+    if interned.paths.len() >= interned.prune_at {
+        interned.paths.retain(|shared| Arc::strong_count(shared) > 1);
+        interned.prune_at = (interned.paths.len() * 2).max(MIN_INTERNED_PRUNE_AT);
+    }
+
+    let shared: Arc<Path> = Arc::from(path);
+    interned.paths.insert(shared.clone());
+    shared
+}
+
+/// `Canonicalize` is the policy a `Simpath` uses for the directories added to it. It decides the
+/// form each directory is stored in, and so which directories are considered to be the same one
+/// when checking if the `Simpath` `contains()` a directory and when ignoring duplicates as they
+/// are added. It is set with `Simpath::set_canonicalize()`, and by default is `Canonicalize::None`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum Canonicalize {
+    /// Directories are made absolute and any symbolic links in them are resolved, so that all the
+    /// ways of referring to the same directory are equal. Directories that do not exist are only
+    /// made absolute.
+    Full,
+    /// Directories are made absolute and `.` and `..` components are removed, without resolving
+    /// symbolic links
+    AbsoluteOnly,
+    /// Directories are stored and compared exactly as they were given
+    None,
+}
+
+/// `EntryFilter` is a set of glob patterns that decide which files in an entry of a `Simpath`
+/// are considered when it is searched. It is attached to an entry using
+/// `Simpath::set_entry_filter()`.
+///
+/// If there are any include patterns then only files whose names match one of them are
+/// considered, and files whose names match any exclude pattern are never considered.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::EntryFilter;
+///
+/// fn main() {
+///     let filter = EntryFilter::new().include("*.plugin").exclude("test-*");
+///     println!("{:?}", filter);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntryFilter {
+    include: Vec<GlobName>,
+    exclude: Vec<GlobName>,
+}
+
+impl EntryFilter {
+    /// Create an `EntryFilter` that considers all files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only consider files whose names match the glob `pattern`, or one of the other include patterns
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(GlobName::new(pattern));
+        self
+    }
+
+    /// Do not consider files or directories whose names match the glob `pattern`
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(GlobName::new(pattern));
+        self
+    }
+}
+
+// The kind of a file found in a directory, which is what is recorded for it in an index
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
+pub(crate) enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    #[cfg(unix)]
+    Fifo,
+    #[cfg(unix)]
+    Socket,
+    #[cfg(unix)]
+    BlockDevice,
+    #[cfg(unix)]
+    CharDevice,
+}
+
+impl From<fs::FileType> for EntryKind {
+    fn from(file_type: fs::FileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            if file_type.is_fifo() {
+                return EntryKind::Fifo;
+            } else if file_type.is_socket() {
+                return EntryKind::Socket;
+            } else if file_type.is_block_device() {
+                return EntryKind::BlockDevice;
+            } else if file_type.is_char_device() {
+                return EntryKind::CharDevice;
+            }
+        }
+
+        if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::File
+        }
+    }
+}
+
+/// `AddReport` describes the directories added to a `Simpath` in bulk, as returned by
+/// `Simpath::add_directories()`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AddReport {
+    /// The name of the `Simpath` the directories were added to
+    pub search_path: String,
+    /// The directories that were added, in the order they were added
+    pub added: Vec<String>,
+    /// The directories that were not added, and why
+    pub rejected: Vec<SkippedEntry>,
+}
+
+impl Simpath {
+    /// Set whether hidden files and directories (those whose names start with `.`) are skipped
+    /// when searching, walking, listing and indexing the path. The default is not to skip them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_skip_hidden(true);
+    ///     assert!(search_path.skip_hidden());
+    /// }
+    /// ```
+    pub fn set_skip_hidden(&mut self, skip_hidden: bool) {
+        self.exclusions.hidden = skip_hidden;
+    }
+
+    /// Get whether hidden files and directories are skipped
+    pub fn skip_hidden(&self) -> bool {
+        self.exclusions.hidden
+    }
+
+    /// Exclude files and directories whose names match the glob `pattern` (e.g. `*~` or `*.bak`)
+    /// when searching, walking, listing and indexing the path
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.add_exclude("*~");
+    ///     search_path.add_exclude(".git");
+    ///     println!("{:?}", search_path.list_with_extension("toml", false));
+    /// }
+    /// ```
+    pub fn add_exclude(&mut self, pattern: &str) {
+        self.exclusions.patterns.push(GlobName::new(pattern));
+    }
+
+    #[cfg(feature = "ignore")]
+    /// Set whether the `.gitignore` and `.ignore` files in each directory of the path are
+    /// respected, excluding the files they ignore (in that directory and its sub-directories)
+    /// when searching, walking, listing and indexing the path. The default is not to respect them.
+    pub fn set_respect_ignore_files(&mut self, respect_ignore_files: bool) {
+        self.exclusions.ignore_files = respect_ignore_files;
+    }
+
+    // Check if any files may be excluded from searches
+    pub(crate) fn has_exclusions(&self) -> bool {
+        #[cfg(feature = "ignore")]
+        if self.exclusions.ignore_files {
+            return true;
+        }
+
+        self.exclusions.hidden || !self.exclusions.patterns.is_empty()
+            || self.directory_entries().any(|entry| entry.filter.is_some())
+    }
+
+    // Get the exclusion rules specific to the directory `dir` in the search path
+    pub(crate) fn dir_exclusions(&self, dir: &Path) -> DirExclusions {
+        let filter = self.directory_entries().find(|entry| *entry.path == *dir)
+            .and_then(|entry| entry.filter.clone());
+
+        #[cfg(feature = "ignore")]
+        let ignore = if self.exclusions.ignore_files {
+            let mut builder = ignore::gitignore::GitignoreBuilder::new(dir);
+            for ignore_file in &[".gitignore", ".ignore"] {
+                let path = dir.join(ignore_file);
+                if path.exists() {
+                    if let Some(e) = builder.add(&path) {
+                        self.notify_error(&path, &Error::new(ErrorKind::InvalidData, e.to_string()));
+                    }
+                }
+            }
+            builder.build().ok()
+        } else {
+            None
+        };
+
+        DirExclusions {
+            filter,
+            #[cfg(feature = "ignore")]
+            ignore,
+        }
+    }
+
+    // Check if the file or directory `entry` is excluded from discovery
+    pub(crate) fn excluded(&self, dir_exclusions: &DirExclusions, entry: &fs::DirEntry) -> bool {
+        self.excluded_name(dir_exclusions, &entry.file_name().to_string_lossy(), &entry.path(),
+                           || entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+    }
+
+    // Check if the file or directory at `relative_path` below `dir`, or any of the directories
+    // on the way to it, is excluded from discovery
+    pub(crate) fn excluded_relative(&self, dir_exclusions: &DirExclusions, dir: &Path, relative_path: &Path) -> bool {
+        let mut path = dir.to_path_buf();
+        let mut components = relative_path.components().peekable();
+        while let Some(component) = components.next() {
+            path.push(component);
+            let last = components.peek().is_none();
+            if self.excluded_name(dir_exclusions, &component.as_os_str().to_string_lossy(), &path,
+                                  || !last || path.is_dir()) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Check if the file or directory called `name` at `path` is excluded from discovery
+    fn excluded_name<F>(&self, dir_exclusions: &DirExclusions, name: &str, _path: &Path, is_dir: F) -> bool
+        where F: Fn() -> bool {
+        if self.exclusions.hidden && name.starts_with('.') {
+            return true;
+        }
+
+        if self.exclusions.patterns.iter().any(|pattern| pattern.matches(name)) {
+            return true;
+        }
+
+        if let Some(filter) = &dir_exclusions.filter {
+            if filter.exclude.iter().any(|pattern| pattern.matches(name)) {
+                return true;
+            }
+            // directories are not subject to the include patterns, so they can still be walked
+            if !filter.include.is_empty() && !filter.include.iter().any(|pattern| pattern.matches(name))
+                && !is_dir() {
+                return true;
+            }
+        }
+
+        #[cfg(feature = "ignore")]
+        if let Some(ignore) = &dir_exclusions.ignore {
+            return ignore.matched_path_or_any_parents(_path, is_dir()).is_ignore();
+        }
+
+        false
+    }
+
+    /// Set the `Canonicalize` policy for the directories in the search path. The directories
+    /// already in it are converted to the form used by the new policy, and any that then refer
+    /// to the same directory as an earlier one are removed. By default this is
+    /// `Canonicalize::None`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{Canonicalize, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPathName");
+    ///     search_path.set_canonicalize(Canonicalize::AbsoluteOnly);
+    ///     search_path.add_directory("/usr/local/../bin");
+    ///     search_path.add_directory("/usr/bin");
+    ///     assert_eq!(search_path.directories().count(), 1);
+    ///     assert!(search_path.contains("/usr/../usr/bin/."));
+    /// }
+    /// ```
+    pub fn set_canonicalize(&mut self, canonicalize: Canonicalize) {
+        self.canonicalize = canonicalize;
+        for mut entry in self.take_entries() {
+            if let Some(directory) = entry.as_directory_mut() {
+                let canonical = self.canonical_form(&directory.path);
+                directory.comparable = Self::comparable_form(&canonical);
+                directory.path = self.stored_path(&canonical);
+                if self.has_comparable(&directory.comparable) {
+                    trace_event!(debug, search_path = %self.name, entry = %directory.path.display(),
+                                 "removed duplicate entry");
+                    continue;
+                }
+            }
+            self.append_entry(entry);
+        }
+    }
+
+    /// Get the `Canonicalize` policy for the directories in the search path
+    pub fn canonicalize(&self) -> Canonicalize {
+        self.canonicalize
+    }
+
+    /// Set whether the directories of the search path are stored only once, and shared with all
+    /// the other `Simpath`s that have also set it and contain the same directories. This saves
+    /// memory when many `Simpath`s are created with the same entries (such as one for each
+    /// request, from a template), at the cost of looking each directory added up in a pool that
+    /// is shared by all threads. The directories already in the search path are shared when it
+    /// is set. By default directories are not shared.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut template = Simpath::new("PATH");
+    ///     template.set_share_directories(true);
+    ///     let mut task = template.clone();
+    ///     task.add_directory("/opt/task/bin");
+    /// }
+    /// ```
+    pub fn set_share_directories(&mut self, share_directories: bool) {
+        self.share_directories = share_directories;
+        if share_directories {
+            for directory in self.entries_mut().iter_mut().filter_map(Entry::as_directory_mut) {
+                directory.path = intern(&directory.path);
+            }
+        }
+    }
+
+    /// Get whether the directories of the search path are shared with other `Simpath`s
+    pub fn share_directories(&self) -> bool {
+        self.share_directories
+    }
+
+    // Get the copy of `dir` to store in an entry, which is the shared one if directories are shared
+    fn stored_path(&self, dir: &Path) -> Arc<Path> {
+        if self.share_directories {
+            intern(dir)
+        } else {
+            Arc::from(dir)
+        }
+    }
+
+    // Get the form a directory is stored in under the canonicalization policy
+    pub(crate) fn canonical_form(&self, dir: &Path) -> PathBuf {
+        match self.canonicalize {
+            // `canonicalize()` is not supported on WASI, so there symbolic links are not resolved
+            #[cfg(not(target_os = "wasi"))]
+            Canonicalize::Full => fs::canonicalize(dir).unwrap_or_else(|_| Self::absolute(dir)),
+            #[cfg(target_os = "wasi")]
+            Canonicalize::Full => Self::absolute(dir),
+            Canonicalize::AbsoluteOnly => Self::absolute(dir),
+            Canonicalize::None => dir.to_path_buf(),
+        }
+    }
+
+    // Get the form of a directory that is compared with others to check if they are the same
+    // directory under the canonicalization policy
+    pub(crate) fn comparable(&self, dir: &Path) -> PathBuf {
+        Self::comparable_form(&self.canonical_form(dir))
+    }
+
+    // Get the form of a directory, already in its canonical form, that is compared with others
+    fn comparable_form(canonical: &Path) -> PathBuf {
+        #[cfg(windows)]
+        return PathBuf::from(canonical.to_string_lossy().replace('/', "\\").to_lowercase());
+        #[cfg(not(windows))]
+        canonical.to_path_buf()
+    }
+
+    // Check if the search path already has an entry for the same directory as `dir`, under the
+    // canonicalization policy
+    pub(crate) fn has_directory(&self, dir: &Path) -> bool {
+        self.has_comparable(&self.comparable(dir))
+    }
+
+    // Check if the search path already has an entry whose comparable form is `comparable`
+    fn has_comparable(&self, comparable: &Path) -> bool {
+        self.comparables.contains(comparable)
+    }
+
+    // Iterate over the directory entries, enabled or not, in search order
+    pub(crate) fn directory_entries(&self) -> impl Iterator<Item = &DirectoryEntry> {
+        self.entries.iter().filter_map(Entry::as_directory)
+    }
+
+    // Make a directory absolute, relative to the current directory, removing any `.` and `..`
+    // components lexically
+    fn absolute(dir: &Path) -> PathBuf {
+        let absolute = env::current_dir().map(|cwd| cwd.join(dir)).unwrap_or_else(|_| dir.to_path_buf());
+        let mut normalized = PathBuf::new();
+        for component in absolute.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => { normalized.pop(); }
+                _ => normalized.push(component),
+            }
+        }
+        normalized
+    }
+
+    // Add a directory parsed from an entry, expanding it to the subdirectories of its parent if
+    // it is a wildcard entry such as `plugins/*`, or to its parent and all the directories below
+    // it if it is a recursive wildcard entry such as `/opt/tools/**`
+    pub(crate) fn push_parsed_directory<P: AsRef<Path>>(&mut self, dir: P, source: EntrySource) {
+        let dir = dir.as_ref();
+        let recursive = match dir.file_name().and_then(OsStr::to_str) {
+            Some("*") => false,
+            Some("**") => true,
+            _ => return match self.strict_rejects(dir) {
+                Some(reason) => self.reject_entry(dir.display().to_string(), reason, source),
+                None => { self.push_directory(dir, source); }
+            },
+        };
+
+        let base = match dir.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.stored_path(parent),
+            _ => self.stored_path(Path::new(".")),
+        };
+        if let Some(reason) = self.strict_rejects(&base) {
+            return self.reject_entry(dir.display().to_string(), reason, source);
+        }
+        if self.wildcards.iter().any(|wildcard| wildcard.base == base) {
+            trace_event!(debug, search_path = %self.name, entry = %dir.display(), "ignored duplicate entry");
+            return;
+        }
+
+        let wildcard = Wildcard { base, source, recursive, position: self.entries.len() };
+        self.push_wildcard(&wildcard);
+        self.wildcards.push(wildcard);
+    }
+
+    // Add the directories a wildcard entry expands to the end of the list, in name order
+    fn push_wildcard(&mut self, wildcard: &Wildcard) {
+        if wildcard.recursive {
+            self.push_entry(wildcard.base.to_path_buf(), wildcard.source.clone(), Some(wildcard.base.clone()));
+            self.push_subdirectories(wildcard, &wildcard.base, self.max_wildcard_depth);
+        } else {
+            self.push_subdirectories(wildcard, &wildcard.base, 1);
+        }
+    }
+
+    // Add the subdirectories of `dir`, and (depth first) those below them up to `depth` levels
+    fn push_subdirectories(&mut self, wildcard: &Wildcard, dir: &Path, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+
+        let mut subdirs: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(Result::ok).map(|entry| entry.path())
+                .filter(|path| path.is_dir()).collect(),
+            Err(e) => {
+                self.notify_error(dir, &e);
+                vec!()
+            }
+        };
+        subdirs.sort();
+
+        for subdir in subdirs {
+            self.push_entry(subdir.clone(), wildcard.source.clone(), Some(wildcard.base.clone()));
+            // Stop expanding the wildcard once the search path has reached its limit of entries
+            if self.len() >= self.limits.max_entries {
+                break;
+            }
+            self.push_subdirectories(wildcard, &subdir, depth - 1);
+        }
+    }
+
+    /// Set a `handler` to be called with every entry parsed from an environment variable (by
+    /// `new()` and its variants, `add_from_env_var()` and `add_from_env_var_with_separator()`),
+    /// with where it came from and whether it was added, ignored or rejected and why, so that an
+    /// application can log a bad configuration, or abort, as it is parsed. To have it called for
+    /// the entries of the variable a `Simpath` is created from use `new_with_entry_handler()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.on_entry(|entry, source, decision| println!("'{}' from {}: {}", entry, source, decision));
+    ///     search_path.add_from_env_var("PATH");
+    /// }
+    /// ```
+    pub fn on_entry<F>(&mut self, handler: F) where F: Fn(&str, &EntrySource, &EntryDecision) + Send + Sync + 'static {
+        self.entry_handler = Some(EntryHandler(Arc::new(handler)));
+    }
+
+    // Pass what was decided about an entry to the entry handler, if one is set
+    pub(crate) fn notify_entry(&self, entry: &OsStr, source: &EntrySource, decision: &EntryDecision) {
+        if let Some(handler) = &self.entry_handler {
+            (handler.0)(&entry.to_string_lossy(), source, decision);
+        }
+    }
+
+    /// Set strict mode for directories: when it is on, each directory entry parsed from an
+    /// environment variable or config file, or added with `add()`, is only added if it exists,
+    /// is a directory and can be read. For wildcard entries (such as `plugins/*`) the directory
+    /// they are expanded from is checked. Entries that are not added can be listed with
+    /// `rejected_entries()`. It is off by default, so that directories created later are found.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.set_strict_directories(true);
+    ///     search_path.add("/no/such/directory");
+    ///     assert!(search_path.is_empty());
+    /// }
+    /// ```
+    pub fn set_strict_directories(&mut self, strict: bool) {
+        self.strict_directories = strict;
+    }
+
+    // Get the reason a directory entry is rejected in strict mode, if it is
+    pub(crate) fn strict_rejects(&self, dir: &Path) -> Option<String> {
+        if !self.strict_directories {
+            None
+        } else if dir.exists() && !dir.is_dir() {
+            Some(format!("'{}' is not a directory", dir.display()))
+        } else {
+            Self::validate_directory(dir).map(|error| error.to_string())
+        }
+    }
+
+    /// Set the number of levels of subdirectories that recursive wildcard entries (such as
+    /// `/opt/tools/**`) are expanded to, when they are added or refreshed. The default is 8.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("TOOLS");
+    ///     search_path.set_max_wildcard_depth(2);
+    ///     search_path.add("/opt/tools/**");
+    ///     println!("Tool directories: {:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn set_max_wildcard_depth(&mut self, max_wildcard_depth: usize) {
+        self.max_wildcard_depth = max_wildcard_depth;
+    }
+
+    /// Get the number of levels of subdirectories that recursive wildcard entries are expanded to
+    pub fn max_wildcard_depth(&self) -> usize {
+        self.max_wildcard_depth
+    }
+
+    /// Expand the wildcard entries (such as `plugins/*` or `/opt/tools/**`) parsed from
+    /// environment variables or added using `add()` again, so that subdirectories created or
+    /// removed since they were expanded are seen. The entries expanded from each wildcard
+    /// replace those it was expanded to before, in the same position in the search path.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PLUGINS");
+    ///     search_path.add("/opt/app/plugins/*");
+    ///     // ... plugins are installed
+    ///     search_path.refresh_wildcards();
+    ///     println!("Plugin directories: {:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn refresh_wildcards(&mut self) {
+        for (index, wildcard) in self.wildcards.clone().into_iter().enumerate() {
+            let expanded_from = |entry: &Entry| entry.expanded_from() == Some(&*wildcard.base);
+            let position = self.entries.iter().position(expanded_from).unwrap_or(wildcard.position);
+            self.retain_entries(|entry| !expanded_from(entry));
+            let position = position.min(self.entries.len());
+            let expanded = self.entries.len();
+            self.push_wildcard(&wildcard);
+            let entries = self.entries_mut();
+            let added: Vec<Entry> = entries.drain(expanded..).collect();
+            entries.splice(position..position, added);
+            self.wildcards[index].position = position;
+        }
+    }
+
+    /// Add a directory to the end of the list of directories to search for files. It is stored
+    /// in the form given by the `Canonicalize` policy, and if the same directory is already in
+    /// the list it is not added again.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.add_directory(".");
+    ///     search_path.add_directory(std::env::temp_dir());
+    ///     println!("Directories in Search Path: {:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn add_directory<P: AsRef<Path>>(&mut self, dir: P) {
+        self.push_directory(dir, EntrySource::Programmatic);
+    }
+
+    /// Add a number of directories to the end of the list of directories to search for files,
+    /// returning an `AddReport` of which were added and which were rejected, and why.
+    /// Directories are only added if they exist, can be read and are not already in the list.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPathName");
+    ///     let report = search_path.add_directories(&["/usr/bin", "/no/such/directory"]);
+    ///     for rejected in report.rejected {
+    ///         println!("'{}' was not added: {}", rejected.entry, rejected.reason);
+    ///     }
+    /// }
+    /// ```
+    pub fn add_directories<I, P>(&mut self, dirs: I) -> AddReport
+        where I: IntoIterator<Item = P>, P: AsRef<Path> {
+        let mut report = AddReport {
+            search_path: self.name.clone(),
+            ..Default::default()
+        };
+
+        for dir in dirs {
+            let dir = dir.as_ref();
+            let entry = dir.display().to_string();
+            if self.has_directory(dir) {
+                trace_event!(debug, search_path = %self.name, entry = %entry, "rejected duplicate entry");
+                report.rejected.push(SkippedEntry { entry, reason: "It is already in the search path".into() });
+            } else if let Some(error) = Self::validate_directory(dir) {
+                trace_event!(debug, search_path = %self.name, entry = %entry, reason = %error, "rejected entry");
+                report.rejected.push(SkippedEntry { entry, reason: error.to_string() });
+            } else if self.push_directory(dir, EntrySource::Programmatic) {
+                report.added.push(entry);
+            } else {
+                report.rejected.push(SkippedEntry { entry, reason: self.entry_limit_error().to_string() });
+            }
+        }
+
+        report
+    }
+
+    // Add a directory to the end of the list, if it is not already in it, recording where it
+    // came from. Returns true if it was added
+    pub(crate) fn push_directory<P: AsRef<Path>>(&mut self, dir: P, source: EntrySource) -> bool {
+        self.push_entry(dir.as_ref().to_path_buf(), source, None)
+    }
+
+    // Add a directory to the end of the list, if it is not already in it, recording where it
+    // came from and the wildcard entry it was expanded from, if any. Returns true if it was
+    // added, and false if it was a duplicate or the limit on the number of entries was reached
+    fn push_entry(&mut self, path: PathBuf, source: EntrySource, wildcard: Option<Arc<Path>>) -> bool {
+        let canonical = self.canonical_form(&path);
+        let comparable = Self::comparable_form(&canonical);
+        let path = self.stored_path(&canonical);
+        if self.has_comparable(&comparable) {
+            trace_event!(debug, search_path = %self.name, entry = %path.display(), "ignored duplicate entry");
+            false
+        } else if self.len() >= self.limits.max_entries {
+            let error = self.entry_limit_error();
+            self.notify_error(&path, &error);
+            self.reject_entry(path.display().to_string(), error.to_string(), source);
+            false
+        } else {
+            self.append_entry(Entry::Directory(DirectoryEntry { path, comparable, filter: None, wildcard,
+                info: EntryInfo::new(source) }));
+            true
+        }
+    }
+
+    /// Set the priority of the entry at `index` in `entries()`. Priorities do not change the
+    /// search order until `sort_by_priority()` is called. If there is no entry at `index` then
+    /// `Err` is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.add_directory("/opt/tools/bin");
+    ///     let index = search_path.entries().len() - 1;
+    ///     search_path.set_priority(index, 10).expect("Could not set priority");
+    ///     search_path.sort_by_priority();
+    ///     assert_eq!(search_path.entries()[0].path(), Some(std::path::Path::new("/opt/tools/bin")));
+    /// }
+    /// ```
+    pub fn set_priority(&mut self, index: usize, priority: i32) -> Result<(), Error> {
+        self.entry_mut(index)?.info_mut().priority = priority;
+        Ok(())
+    }
+
+    /// Sort the entries so that entries with a higher priority are searched first. The sort is
+    /// stable, so entries with the same priority keep their order.
+    pub fn sort_by_priority(&mut self) {
+        self.entries_mut().sort_by_key(|entry| std::cmp::Reverse(entry.priority()));
+    }
+
+    /// Disable the entry at `index` in `entries()`, so that it is not searched, without removing
+    /// it. If there is no entry at `index` then `Err` is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     if let Some(index) = search_path.entries().iter()
+    ///         .position(|entry| entry.path() == Some(std::path::Path::new("/usr/local/bin"))) {
+    ///         search_path.disable_entry(index).expect("Could not disable entry");
+    ///         println!("Without /usr/local/bin 'ls' is found at {:?}", search_path.find("ls"));
+    ///         search_path.enable_entry(index).expect("Could not enable entry");
+    ///     }
+    /// }
+    /// ```
+    pub fn disable_entry(&mut self, index: usize) -> Result<(), Error> {
+        self.entry_mut(index)?.info_mut().enabled = false;
+        Ok(())
+    }
+
+    /// Enable the entry at `index` in `entries()`, so that it is searched again. If there is no
+    /// entry at `index` then `Err` is returned.
+    pub fn enable_entry(&mut self, index: usize) -> Result<(), Error> {
+        self.entry_mut(index)?.info_mut().enabled = true;
+        Ok(())
+    }
+
+    /// Enable the entries for which `predicate` returns true and disable the rest
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_enabled_by(|entry| !entry.path().is_some_and(|path| path.starts_with("/usr/local")));
+    ///     println!("{:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn set_enabled_by<F>(&mut self, mut predicate: F) where F: FnMut(&Entry) -> bool {
+        for entry in self.entries_mut() {
+            entry.info_mut().enabled = predicate(entry);
+        }
+    }
+
+    /// Set the `EntryFilter` that decides which files in the entry at `index` in `entries()` are
+    /// considered when searching, walking, listing and indexing the path. If there is no entry at
+    /// `index`, or it is not a directory, then `Err` is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{EntryFilter, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PLUGIN_PATH");
+    ///     search_path.add_directory("/usr/lib/app/plugins");
+    ///     let index = search_path.len() - 1;
+    ///     search_path.set_entry_filter(index, EntryFilter::new().include("*.plugin"))
+    ///         .expect("Could not set filter");
+    /// }
+    /// ```
+    pub fn set_entry_filter(&mut self, index: usize, filter: EntryFilter) -> Result<(), Error> {
+        if let Some(directory) = self.entry_mut(index)?.as_directory_mut() {
+            directory.filter = Some(filter);
+            return Ok(());
+        }
+
+        Err(Error::new(ErrorKind::InvalidInput,
+                       format!("Entry {} in {} is not a directory, so cannot be filtered", index, PathName(&self.name))))
+    }
+
+    /// Attach the annotation `key` with `value` to the entry at `index` in `entries()`, replacing
+    /// any value it already had, so that applications can tag entries (e.g. with the plugin they
+    /// came from) and act on the tags of the entries that files are found in. Annotations are
+    /// kept by clones of the `Simpath`. If there is no entry at `index` then `Err` is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PLUGIN_PATH");
+    ///     search_path.add_directory("/usr/lib/app/plugins");
+    ///     let index = search_path.len() - 1;
+    ///     search_path.annotate_entry(index, "mirror", "read-only").expect("Could not annotate");
+    ///     if let Ok(found) = search_path.find("plugin.so") {
+    ///         let mirror = search_path.entry_found_in(&found).and_then(|entry| entry.annotation("mirror"));
+    ///         println!("{:?} was found in a {:?} mirror", found, mirror);
+    ///     }
+    /// }
+    /// ```
+    pub fn annotate_entry(&mut self, index: usize, key: &str, value: &str) -> Result<(), Error> {
+        self.entry_mut(index)?.info_mut().annotations.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Get the entry that `found` was found in: the deepest enabled directory entry it is in or,
+    /// for a resource, the Url entry it is. `None` is returned if it is not in one.
+    pub fn entry_found_in(&self, found: &FoundType) -> Option<&Entry> {
+        #[cfg(feature = "urls")]
+        if let FoundType::Resource(url) = found {
+            return self.entries.iter().find(|entry| entry.is_enabled() && entry.url() == Some(url));
+        }
+
+        let location = Simpath::location(found);
+        self.entries.iter()
+            .filter(|entry| entry.is_enabled())
+            .filter_map(|entry| entry.path().map(|path| (entry, path)))
+            .filter(|(_, path)| Path::new(&location).starts_with(path))
+            .max_by_key(|(_, path)| path.components().count())
+            .map(|(entry, _)| entry)
+    }
+
+    /// Keep only the entries for which `predicate` returns true, removing the rest
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.retain(|entry| !entry.path().is_some_and(|path| path.starts_with("/tmp")));
+    ///     println!("{:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn retain<F>(&mut self, predicate: F) where F: FnMut(&Entry) -> bool {
+        self.retain_entries(predicate);
+    }
+
+    /// Sort the entries using the `compare` function. The sort is stable, so entries that
+    /// compare equal keep their relative order.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     // search the deepest directories first
+    ///     let depth = |entry: &simpath::Entry| entry.path().map(|path| path.components().count());
+    ///     search_path.sort_entries_by(|a, b| depth(b).cmp(&depth(a)));
+    ///     println!("{:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn sort_entries_by<F>(&mut self, compare: F)
+        where F: FnMut(&Entry, &Entry) -> std::cmp::Ordering {
+        self.entries_mut().sort_by(compare);
+    }
+
+    /// Replace the path of each directory entry with the path returned by `map`. If more than one
+    /// entry ends up with the same path only the first is kept. The directories of wildcard
+    /// entries (such as `plugins/*`) are mapped too, so that `refresh_wildcards()` expands them
+    /// from their new location.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::path::Path;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.map_entries(|path| Path::new("/chroot").join(path.strip_prefix("/").unwrap_or(path)));
+    ///     println!("{:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn map_entries<F>(&mut self, map: F) where F: FnMut(&Path) -> PathBuf {
+        self.map_directories(map);
+    }
+
+    // Replace the path of each directory entry with the path returned by `map`, as
+    // `map_entries()` does, returning the directories that were changed and kept
+    fn map_directories<F>(&mut self, mut map: F) -> Vec<PathBuf> where F: FnMut(&Path) -> PathBuf {
+        let mut changed = vec!();
+        let mut wildcards = std::mem::take(&mut self.wildcards);
+        for wildcard in &mut wildcards {
+            wildcard.base = self.stored_path(&map(&wildcard.base));
+        }
+        self.wildcards = wildcards;
+
+        for mut entry in self.take_entries() {
+            if let Some(directory) = entry.as_directory_mut() {
+                let path = map(&directory.path);
+                let is_changed = *path != *directory.path;
+                directory.path = self.stored_path(&path);
+                directory.wildcard = directory.wildcard.as_ref().map(|base| self.stored_path(&map(base)));
+                directory.comparable = self.comparable(&directory.path);
+                if self.has_comparable(&directory.comparable) {
+                    continue;
+                }
+                if is_changed {
+                    changed.push(path);
+                }
+            }
+            self.append_entry(entry);
+        }
+        changed
+    }
+
+    /// Rewrite all the entries that begin with `from_prefix` so that they begin with `to_prefix`
+    /// instead, as is needed when searching inside a sysroot or chroot. The rewritten
+    /// directories are validated, as `validate()` does, and a `PathError` is returned for each
+    /// rewritten directory that is not valid. If a rewritten entry is already in the search path
+    /// then only the first is kept, and only the rewritten entries that are kept are validated.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPathName");
+    ///     search_path.add_directory("/usr/include");
+    ///     let errors = search_path.rebase("/usr", "/opt/sysroot/usr");
+    ///     assert_eq!(search_path.directories().next(), Some(std::path::Path::new("/opt/sysroot/usr/include")));
+    ///     for error in errors {
+    ///         println!("Invalid entry after rebase: {}", error);
+    ///     }
+    /// }
+    /// ```
+    pub fn rebase<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from_prefix: P, to_prefix: Q) -> Vec<PathError> {
+        let rewritten = self.map_directories(|path| match path.strip_prefix(&from_prefix) {
+            Ok(rest) => to_prefix.as_ref().join(rest),
+            Err(_) => path.to_path_buf()
+        });
+
+        rewritten.iter().filter_map(|dir| Self::validate_directory(dir)).collect()
+    }
+
+    /// Make `dir` the first entry of the search path, moving it to the front if it is already in
+    /// it or adding it there if not, as an installer putting its tools first would. If the
+    /// search path was parsed from an environment variable, and has not been changed since in
+    /// other ways, the original text is edited in the same way, leaving the rest of it (including
+    /// empty and rejected entries) untouched, so that `to_env_string()` returns the minimally
+    /// changed value once `set_preserve_original()` has been set.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     env::set_var("MyInstallPath", "/usr/bin::/opt/tool/bin");
+    ///     let mut search_path = Simpath::new_with_separator("MyInstallPath", ':');
+    ///     search_path.set_preserve_original(true);
+    ///     search_path.ensure_first("/opt/tool/bin");
+    ///     assert_eq!(search_path.to_env_string(), "/opt/tool/bin:/usr/bin:");
+    /// }
+    /// ```
+    pub fn ensure_first<P: AsRef<Path>>(&mut self, dir: P) {
+        let dir = dir.as_ref();
+        let in_sync = self.original_in_sync();
+        if !self.has_directory(dir) {
+            self.push_directory(dir, EntrySource::Programmatic);
+        }
+        let comparable = self.comparable(dir);
+        let index = match self.entries.iter()
+            .position(|entry| entry.path().is_some_and(|path| self.comparable(path) == comparable)) {
+            Some(index) => index,
+            None => return, // it could not be added, as the search path is full
+        };
+
+        let entries = self.entries_mut();
+        let entry = entries.remove(index);
+        entries.insert(0, entry);
+
+        self.edit_original(in_sync, |search_path, segments| {
+            let first = segments.iter().find(|segment| search_path.is_segment_for(segment, &comparable)).cloned();
+            segments.retain(|segment| !search_path.is_segment_for(segment, &comparable));
+            segments.insert(0, first.unwrap_or_else(|| dir.display().to_string()));
+        });
+    }
+
+    /// Add `dir` to the end of the search path if it is not already in it, returning true if it
+    /// was added. The original text of the environment variable is edited in the same way, as
+    /// `ensure_first()` does.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     if search_path.ensure_present("/opt/tool/bin") {
+    ///         println!("PATH={}", search_path.to_env_string());
+    ///     }
+    /// }
+    /// ```
+    pub fn ensure_present<P: AsRef<Path>>(&mut self, dir: P) -> bool {
+        let dir = dir.as_ref();
+        let in_sync = self.original_in_sync();
+        if self.has_directory(dir) {
+            return false;
+        }
+
+        self.push_directory(dir, EntrySource::Programmatic);
+        if !self.has_directory(dir) {
+            return false;
+        }
+
+        self.edit_original(in_sync, |_, segments| segments.push(dir.display().to_string()));
+        true
+    }
+
+    /// Remove all the directory entries that are `prefix` or are below it, such as those of an
+    /// uninstalled tool, returning the paths of those removed. The entries are compared in the
+    /// form given by the `Canonicalize` policy. The original text of the environment variable is
+    /// edited in the same way, as `ensure_first()` does, removing all the entries in it that
+    /// match, including any that were rejected.
+    ///
+    /// An `InvalidInput` error is returned, and nothing is removed, if `prefix` is empty or is
+    /// not an absolute path, as it could match every entry.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     for removed in search_path.remove_matching("/opt/old-tool").expect("Not an absolute path") {
+    ///         println!("Removed '{}'", removed.display());
+    ///     }
+    /// }
+    /// ```
+    pub fn remove_matching<P: AsRef<Path>>(&mut self, prefix: P) -> Result<Vec<PathBuf>, Error> {
+        let prefix = prefix.as_ref();
+        if !prefix.is_absolute() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Cannot remove the entries of {} below '{}', which is not an absolute path",
+                                          PathName(&self.name), prefix.display())));
+        }
+
+        let in_sync = self.original_in_sync();
+        let prefix = self.comparable(prefix);
+        let removed = self.retain_entries(|entry| !entry.as_directory()
+            .is_some_and(|directory| directory.comparable.starts_with(&prefix)));
+        // Wildcards below the prefix are removed too, so that refreshing them does not add back
+        // the directories they were expanded to
+        let wildcards = std::mem::take(&mut self.wildcards);
+        self.wildcards = wildcards.into_iter()
+            .filter(|wildcard| !self.comparable(&wildcard.base).starts_with(&prefix)).collect();
+
+        if !removed.is_empty() {
+            self.edit_original(in_sync, |search_path, segments| segments.retain(|segment|
+                segment.is_empty() || !search_path.comparable(Path::new(segment)).starts_with(&prefix)));
+        }
+
+        Ok(removed.iter().filter_map(Entry::path).map(Path::to_path_buf).collect())
+    }
+
+    // Check if `segment` of the original text of the environment variable is an entry for the
+    // directory `comparable` (in the form returned by `comparable()`)
+    fn is_segment_for(&self, segment: &str, comparable: &Path) -> bool {
+        !segment.is_empty() && self.comparable(Path::new(segment)) == comparable
+    }
+
+    // Get the list of entries to change it, copying it first if it is shared with a clone. It must
+    // not be used to add or remove directories, which is done with `append_entry()`,
+    // `retain_entries()` and `take_entries()` so that their comparable forms are kept up to date.
+    pub(crate) fn entries_mut(&mut self) -> &mut Vec<Entry> {
+        Arc::make_mut(&mut self.entries)
+    }
+
+    // Add `entry` to the end of the list of entries
+    pub(crate) fn append_entry(&mut self, entry: Entry) {
+        if let Some(directory) = entry.as_directory() {
+            Arc::make_mut(&mut self.comparables).insert(directory.comparable.clone());
+        }
+        self.entries_mut().push(entry);
+    }
+
+    // Keep only the entries accepted by `keep`, returning those removed
+    pub(crate) fn retain_entries<F>(&mut self, mut keep: F) -> Vec<Entry> where F: FnMut(&Entry) -> bool {
+        let mut removed = vec!();
+        self.entries_mut().retain(|entry| keep(entry) || {
+            removed.push(entry.clone());
+            false
+        });
+        if removed.iter().any(|entry| entry.as_directory().is_some()) {
+            let comparables = Arc::make_mut(&mut self.comparables);
+            for directory in removed.iter().filter_map(Entry::as_directory) {
+                comparables.remove(&directory.comparable);
+            }
+        }
+        removed
+    }
+
+    // Remove all the entries, returning them
+    pub(crate) fn take_entries(&mut self) -> Vec<Entry> {
+        self.comparables = Arc::default();
+        Arc::unwrap_or_clone(std::mem::take(&mut self.entries))
+    }
+
+    // Get the entry at `index`, or a `NotFound` error if there is none
+    fn entry_mut(&mut self, index: usize) -> Result<&mut Entry, Error> {
+        let name = &self.name;
+        Arc::make_mut(&mut self.entries).get_mut(index).ok_or_else(|| Error::new(ErrorKind::NotFound,
+                                                            format!("There is no entry {} in {}",
+                                                                    index, PathName(name))))
+    }
+
+    // Check that a directory exists and can be read, returning the `PathError` if not
+    pub(crate) fn validate_directory(dir: &Path) -> Option<PathError> {
+        if !dir.exists() {
+            Some(PathError::DoesNotExist(dir.display().to_string()))
+        } else if fs::read_dir(dir).is_err() {
+            Some(PathError::CannotRead(dir.display().to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{AddReport, Canonicalize, EntryDecision, EntryFilter, EntrySource, FoundType, Limits, PathError, Simpath,
+                SkippedEntry};
+    use crate::test::temp_dir;
+
+    #[test]
+    fn sort_entries_by_priority() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/first");
+        path.add_directory("/second");
+        path.add_directory("/third");
+        path.add_directory("/fourth");
+        path.set_priority(2, 5).unwrap();
+        path.set_priority(3, 5).unwrap();
+        path.set_priority(0, -1).unwrap();
+        assert!(path.set_priority(4, 1).is_err());
+
+        path.sort_by_priority();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/third"), PathBuf::from("/fourth"),
+                                            PathBuf::from("/second"), PathBuf::from("/first")));
+        assert_eq!(path.entries()[0].priority(), 5);
+
+        // entries with equal priority keep their current order
+        path.set_priority(0, 0).unwrap();
+        path.set_priority(1, 0).unwrap();
+        path.sort_by_priority();
+        assert_eq!(path.directories().take(3).collect::<Vec<_>>(), [PathBuf::from("/third"), PathBuf::from("/fourth"),
+                                             PathBuf::from("/second")]);
+    }
+
+    #[test]
+    fn disabled_entries_not_searched() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("testfile")).unwrap();
+        fs::File::create(second_dir.join("testfile")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+
+        path.disable_entry(0).unwrap();
+        assert!(!path.entries()[0].is_enabled());
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(second_dir.clone()));
+        assert_eq!(path.find("testfile").unwrap(), FoundType::File(second_dir.join("testfile")));
+
+        path.enable_entry(0).unwrap();
+        assert_eq!(path.find("testfile").unwrap(), FoundType::File(first_dir.join("testfile")));
+
+        path.set_enabled_by(|entry| entry.path() == Some(&*second_dir));
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(second_dir.clone()));
+        assert!(path.enable_entry(2).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn retain_sort_and_map_entries() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/usr/bin");
+        path.add_directory("/tmp/bin");
+        path.add_directory("/usr/local/bin");
+        path.add_directory("/bin");
+
+        path.retain(|entry| !entry.path().is_some_and(|dir| dir.starts_with("/tmp")));
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/bin"), PathBuf::from("/usr/local/bin"),
+                                            PathBuf::from("/bin")));
+
+        path.sort_entries_by(|a, b| b.path().map(|dir| dir.components().count())
+            .cmp(&a.path().map(|dir| dir.components().count())));
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/local/bin"), PathBuf::from("/usr/bin"),
+                                            PathBuf::from("/bin")));
+
+        // "/usr/bin" becomes "/bin", which is already in the path, so only one is kept
+        path.map_entries(|dir| match dir.strip_prefix("/usr") {
+            Ok(rest) => PathBuf::from("/").join(rest),
+            Err(_) => dir.to_path_buf(),
+        });
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/local/bin"), PathBuf::from("/bin")));
+    }
+
+    #[test]
+    fn rebase_entries() {
+        let sysroot = temp_dir();
+        fs::create_dir_all(sysroot.join("usr/include")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/usr/include");
+        path.add_directory("/usr/lib");
+        path.add_directory("/opt/include");
+
+        let errors = path.rebase("/usr", sysroot.join("usr"));
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(sysroot.join("usr/include"), sysroot.join("usr/lib"),
+                                            PathBuf::from("/opt/include")));
+        assert_eq!(errors, vec!(PathError::DoesNotExist(sysroot.join("usr/lib").display().to_string())));
+
+        // A rewritten entry that duplicates an earlier one is dropped, and not validated
+        let mut path = Simpath::new("MyName");
+        path.add_directory(sysroot.join("usr/missing"));
+        path.add_directory("/usr/missing");
+        assert!(path.rebase("/usr", sysroot.join("usr")).is_empty());
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(sysroot.join("usr/missing")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(sysroot);
+    }
+
+    #[test]
+    fn add_directories_reports_rejected() {
+        let temp_dir = temp_dir();
+        let missing = temp_dir.join("missing");
+        let mut path = Simpath::new("MyName");
+
+        let report = path.add_directories(vec!(&temp_dir, &missing, &temp_dir));
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone()));
+        assert_eq!(report, AddReport {
+            search_path: "MyName".into(),
+            added: vec!(temp_dir.display().to_string()),
+            rejected: vec!(
+                SkippedEntry { entry: missing.display().to_string(),
+                    reason: format!("'{}' does not exist", missing.display()) },
+                SkippedEntry { entry: temp_dir.display().to_string(),
+                    reason: "It is already in the search path".into() }),
+        });
+
+        path.set_limits(Limits { max_entries: 1, ..Default::default() });
+        let report = path.add_directories(vec!(env::temp_dir()));
+        assert!(report.added.is_empty());
+        assert_eq!(report.rejected[0].reason, "search path 'MyName' cannot have more than the limit of 1 entries");
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn hidden_and_excluded_files_are_skipped() {
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join(".git")).unwrap();
+        fs::File::create(temp_dir.join(".git/config.toml")).unwrap();
+        fs::File::create(temp_dir.join("app.toml")).unwrap();
+        fs::File::create(temp_dir.join("app.toml~")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        assert_eq!(path.walk().recursive(true).count(), 3);
+
+        path.set_skip_hidden(true);
+        path.add_exclude("*~");
+        assert_eq!(path.walk().recursive(true).collect::<Result<Vec<PathBuf>, _>>().unwrap(),
+                   vec!(temp_dir.join("app.toml")));
+        assert!(path.find("app.toml~").is_err());
+        assert!(path.find(".git").is_err());
+        assert!(path.find(".git/config.toml").is_err());
+        assert!(path.clone().freeze().find("app.toml~").is_err());
+        assert!(path.clone().freeze().find(".git/config.toml").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "ignore")]
+    #[test]
+    fn ignore_files_are_respected() {
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join("target")).unwrap();
+        fs::File::create(temp_dir.join("target/tool")).unwrap();
+        fs::File::create(temp_dir.join("tool.bak")).unwrap();
+        fs::write(temp_dir.join(".gitignore"), "target/\n*.bak\n").unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        path.set_respect_ignore_files(true);
+
+        assert_eq!(path.walk().recursive(true).collect::<Result<Vec<PathBuf>, _>>().unwrap(),
+                   vec!(temp_dir.join(".gitignore")));
+        assert!(path.find("tool.bak").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn entry_filters_apply_per_entry() {
+        let temp_dir = temp_dir();
+        let system_dir = temp_dir.join("system");
+        let user_dir = temp_dir.join("user");
+        fs::create_dir_all(&system_dir).unwrap();
+        fs::create_dir_all(&user_dir).unwrap();
+        for dir in &[&system_dir, &user_dir] {
+            fs::File::create(dir.join("a.plugin")).unwrap();
+            fs::File::create(dir.join("b.so")).unwrap();
+            fs::File::create(dir.join("test-c.plugin")).unwrap();
+        }
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&system_dir);
+        path.add_directory(&user_dir);
+        path.set_entry_filter(0, EntryFilter::new().include("*.plugin").exclude("test-*")).unwrap();
+        assert!(path.set_entry_filter(2, EntryFilter::new()).is_err());
+
+        let mut files = path.walk().collect::<Result<Vec<PathBuf>, _>>().unwrap();
+        files.sort();
+        assert_eq!(files, vec!(system_dir.join("a.plugin"), user_dir.join("a.plugin"), user_dir.join("b.so"),
+                               user_dir.join("test-c.plugin")));
+        assert_eq!(path.find("b.so").unwrap(), FoundType::File(user_dir.join("b.so")));
+        assert_eq!(path.entries()[0].filter(), Some(&EntryFilter::new().include("*.plugin").exclude("test-*")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn canonicalize_policies() {
+        let temp_dir = temp_dir();
+        let sub_dir = temp_dir.join("sub");
+        fs::create_dir(&sub_dir).expect("Could not create sub-directory");
+        let indirect = temp_dir.join("sub").join("..").join("sub").join(".");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&indirect);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(indirect.clone()));
+        assert!(!path.contains(&sub_dir));
+        path.add_directory(&sub_dir);
+        assert_eq!(path.directories().count(), 2);
+
+        path.set_canonicalize(Canonicalize::AbsoluteOnly);
+        assert_eq!(path.canonicalize(), Canonicalize::AbsoluteOnly);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(sub_dir.clone()));
+        assert!(path.contains(&indirect));
+
+        #[cfg(unix)]
+        {
+            let link = temp_dir.join("link");
+            std::os::unix::fs::symlink(&sub_dir, &link).expect("Could not create symlink");
+            path.add_directory(&link);
+            assert_eq!(path.directories().count(), 2);
+
+            let mut path = Simpath::new("MyName");
+            path.set_canonicalize(Canonicalize::Full);
+            path.add_directory(&sub_dir);
+            path.add_directory(&link);
+            assert_eq!(path.directories().collect::<Vec<_>>(), vec!(fs::canonicalize(&sub_dir).expect("Could not canonicalize")));
+            assert!(path.contains(&link));
+        }
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn entries_share_storage() {
+        let mut first = Simpath::new("MyName");
+        first.add_directory("/shared/between/paths");
+        first.set_share_directories(true);
+        let mut second = Simpath::new("Other");
+        second.set_share_directories(true);
+        second.add_directory("/shared/between/paths");
+        let mut unshared = Simpath::new("Unshared");
+        unshared.add_directory("/shared/between/paths");
+        assert!(!Arc::ptr_eq(&first.directory_entries().next().expect("No entry").path,
+                             &unshared.directory_entries().next().expect("No entry").path));
+        assert!(Arc::ptr_eq(&first.directory_entries().next().expect("No entry").path,
+                            &second.directory_entries().next().expect("No entry").path));
+        let clone = first.clone();
+        assert!(Arc::ptr_eq(&first.directory_entries().next().expect("No entry").path,
+                            &clone.directory_entries().next().expect("No entry").path));
+    }
+
+    #[test]
+    fn entries_annotated() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        for dir in [&first, &second] {
+            fs::create_dir(dir).expect("Could not create directory");
+        }
+        fs::File::create(second.join("tool")).expect("Could not create file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+        path.annotate_entry(1, "plugin", "second").expect("Could not annotate");
+        path.annotate_entry(1, "mirror", "read-only").expect("Could not annotate");
+        assert!(path.annotate_entry(2, "plugin", "none").is_err());
+
+        let copy = path.clone();
+        let found = copy.find("tool").expect("Could not find");
+        let entry = copy.entry_found_in(&found).expect("Could not find entry");
+        assert_eq!(entry.path(), Some(second.as_path()));
+        assert_eq!(entry.annotation("plugin"), Some("second"));
+        assert_eq!(entry.annotations().len(), 2);
+        assert!(path.entries()[0].annotations().is_empty());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn entry_decisions_passed_to_handler() {
+        let temp_dir = temp_dir();
+        let missing = temp_dir.join("missing");
+        let var_name = "DecisionsPath";
+        env::set_var(var_name, format!("{};;{};{}", temp_dir.display(), missing.display(), temp_dir.display()));
+        let decisions = Arc::new(Mutex::new(vec!()));
+        let recorded = decisions.clone();
+
+        let mut path = Simpath::new_with_separator("Decisions", ';');
+        path.set_strict_directories(true);
+        path.on_entry(move |entry, source, decision| {
+            assert_eq!(source, &EntrySource::EnvVar("DecisionsPath".into()));
+            recorded.lock().unwrap().push((entry.to_string(), decision.clone()));
+        });
+        path.add_from_env_var(var_name);
+
+        let decisions = decisions.lock().unwrap();
+        assert_eq!(decisions.len(), 4);
+        assert_eq!(decisions[0], (temp_dir.display().to_string(), EntryDecision::Added));
+        assert_eq!(decisions[1], (String::new(), EntryDecision::Ignored("It is empty".into())));
+        assert_eq!(decisions[2], (missing.display().to_string(),
+                                  EntryDecision::Rejected(format!("'{}' does not exist", missing.display()))));
+        assert!(matches!(decisions[3].1, EntryDecision::Ignored(_)));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn rejected_entries_recorded() {
+        let temp_dir = temp_dir();
+        let file = temp_dir.join("file");
+        fs::File::create(&file).expect("Could not create file");
+        let missing = temp_dir.join("missing");
+        let var_name = "RejectedPath";
+        env::set_var(var_name, format!("{},{},{}", temp_dir.display(), missing.display(), file.display()));
+
+        let mut path = Simpath::new_with_separator("Rejected", ',');
+        path.add_from_env_var(var_name);
+        assert_eq!(path.directories().count(), 3);
+        assert!(path.rejected_entries().is_empty());
+
+        let mut path = Simpath::new_with_separator("Rejected", ',');
+        path.set_strict_directories(true);
+        path.add_from_env_var(var_name);
+        path.add("https://[not-a-host");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone()));
+        let rejected = path.rejected_entries();
+        assert_eq!(rejected.len(), 3);
+        assert_eq!(rejected[0], (&SkippedEntry { entry: missing.display().to_string(),
+            reason: format!("'{}' does not exist", missing.display()) }, &EntrySource::EnvVar(var_name.into())));
+        assert_eq!(rejected[1].0.reason, format!("'{}' is not a directory", file.display()));
+        assert_eq!(rejected[2].0.entry, "https://[not-a-host");
+        assert_eq!(rejected[2].1, &EntrySource::Programmatic);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn cannot_add_same_dir_twice() {
+        let mut path = Simpath::new("MyName");
+        assert!(path.directories().next().is_none());
+        path.add_directory(".");
+        path.add_directory(".");
+        assert_eq!(path.directories().count(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn edit_in_place() {
+        let temp_dir = temp_dir();
+        let (a, b, c) = (temp_dir.join("a"), temp_dir.join("b"), temp_dir.join("c"));
+        for dir in [&a, &b, &c] {
+            fs::create_dir(dir).expect("Could not create directory");
+        }
+        let missing = temp_dir.join("missing");
+        let var_name = "EditedPath";
+        env::set_var(var_name, format!("{}::{}:{}", a.display(), missing.display(), b.display()));
+
+        let mut path = Simpath::new_with_separator("", ':');
+        path.set_strict_directories(true);
+        path.add_from_env_var(var_name);
+        path.set_preserve_original(true);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(a.clone(), b.clone()));
+
+        path.ensure_first(&b);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(b.clone(), a.clone()));
+        assert_eq!(path.to_env_string(), format!("{}:{}::{}", b.display(), a.display(), missing.display()));
+
+        assert!(!path.ensure_present(&a));
+        assert!(path.ensure_present(&c));
+        assert_eq!(path.to_env_string(), format!("{}:{}::{}:{}", b.display(), a.display(), missing.display(),
+                                                 c.display()));
+
+        assert_eq!(path.remove_matching(&a).expect("Could not remove"), vec!(a.clone()));
+        assert_eq!(path.remove_matching("").unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(path.remove_matching("relative").unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(b.clone(), c.clone()));
+        assert_eq!(path.to_env_string(), format!("{}::{}:{}", b.display(), missing.display(), c.display()));
+
+        path.set_preserve_original(false);
+        assert_eq!(path.to_env_string(), format!("{}:{}", b.display(), c.display()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn removed_wildcard_entries_stay_removed() {
+        let temp_dir = temp_dir();
+        let tools = temp_dir.join("tools");
+        fs::create_dir_all(tools.join("a")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        path.add(tools.join("**").display().to_string());
+        assert_eq!(path.directories().count(), 3);
+
+        assert_eq!(path.remove_matching(&tools).expect("Could not remove"), vec!(tools.clone(), tools.join("a")));
+        path.refresh_wildcards();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn rebased_wildcard_entries_refreshed() {
+        let temp_dir = temp_dir();
+        let old = temp_dir.join("old");
+        let new = temp_dir.join("new");
+        fs::create_dir_all(old.join("plugins").join("a")).unwrap();
+        fs::create_dir_all(new.join("plugins").join("a")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add(old.join("plugins").join("*").display().to_string());
+
+        assert!(path.rebase(&old, &new).is_empty());
+        assert_eq!(path.entries()[0].expanded_from(), Some(new.join("plugins").as_path()));
+        fs::create_dir(new.join("plugins").join("b")).unwrap();
+        path.refresh_wildcards();
+        assert_eq!(path.directories().collect::<Vec<_>>(),
+                   vec!(new.join("plugins").join("a"), new.join("plugins").join("b")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn empty_wildcard_refreshed_in_place() {
+        let temp_dir = temp_dir();
+        let plugins = temp_dir.join("plugins");
+        fs::create_dir(&plugins).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add(plugins.join("*").display().to_string());
+        path.add_directory(&temp_dir);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone()));
+
+        fs::create_dir(plugins.join("a")).unwrap();
+        path.refresh_wildcards();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(plugins.join("a"), temp_dir.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn duplicates_found_after_entries_changed() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/usr/bin");
+        path.add_directory("/usr/local/bin");
+        path.add_directory("/opt/bin");
+        path.add_directory("/usr/bin");
+        assert_eq!(path.directories().count(), 3);
+
+        path.retain(|entry| entry.path() != Some(std::path::Path::new("/usr/local/bin")));
+        path.remove_matching("/opt").expect("Could not remove");
+        path.add_directory("/usr/local/bin");
+        path.add_directory("/opt/bin");
+        path.add_directory("/usr/bin");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/bin"),
+                                            PathBuf::from("/usr/local/bin"), PathBuf::from("/opt/bin")));
+
+        path.map_entries(|dir| PathBuf::from("/sysroot").join(dir.strip_prefix("/").unwrap_or(dir)));
+        path.add_directory("/sysroot/usr/bin");
+        path.add_directory("/usr/bin");
+        assert_eq!(path.directories().count(), 4);
+    }
+}
@@ -0,0 +1,152 @@
+//! How the errors found while using a `Simpath` are reported: to the handler set with
+//! `Simpath::on_error()`, and in the messages of the errors returned.
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{Simpath, SkippedEntry};
+
+// A callback that is passed the IO errors encountered while searching, with the path they
+// occurred on
+type ErrorCallback = dyn Fn(&Path, &Error) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct ErrorHandler(pub(crate) Arc<ErrorCallback>);
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ErrorHandler")
+    }
+}
+
+/// When validating a `Simpath` there can be the following types of `PathError`s returned
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    /// The `Path` entry does not exist on the file system
+    DoesNotExist(String),
+    /// The `Path` entry cannot be reads
+    CannotRead(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::DoesNotExist(entry) => write!(f, "'{}' does not exist", entry),
+            PathError::CannotRead(entry) => write!(f, "'{}' cannot be read", entry),
+        }
+    }
+}
+
+/// `NewError` is returned by `Simpath::try_new()` and its variants when the environment
+/// variable does not give a usable search path
+#[derive(Debug, PartialEq)]
+pub enum NewError {
+    /// The environment variable is not set
+    Unset(String),
+    /// The environment variable is set, but there are no entries in it once empty entries are
+    /// ignored and wildcard entries are expanded
+    Empty(String),
+    /// All the entries in the environment variable were rejected, with the reason each one was
+    Rejected {
+        /// The name of the environment variable
+        var_name: String,
+        /// The entries that were rejected, and why
+        rejected: Vec<SkippedEntry>,
+    },
+}
+
+impl fmt::Display for NewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NewError::Unset(var_name) => write!(f, "Environment variable '{}' is not set", var_name),
+            NewError::Empty(var_name) => write!(f, "Environment variable '{}' has no entries", var_name),
+            NewError::Rejected { var_name, rejected } => {
+                write!(f, "All {} entries in environment variable '{}' were rejected", rejected.len(), var_name)?;
+                for (index, skipped) in rejected.iter().enumerate() {
+                    let separator = if index == 0 { ": " } else { ", " };
+                    write!(f, "{}'{}' ({})", separator, skipped.entry, skipped.reason)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for NewError {}
+
+// Displays the name of a `Simpath` in messages, handling `Simpath`s without a name
+pub(crate) struct PathName<'a>(pub(crate) &'a str);
+
+impl fmt::Display for PathName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "the search path")
+        } else {
+            write!(f, "search path '{}'", self.0)
+        }
+    }
+}
+
+impl Simpath {
+    /// Set a `handler` to be called with every IO error encountered while searching the path
+    /// (such as a directory that does not exist or cannot be read), along with the path it
+    /// occurred on.
+    ///
+    /// By default an IO error ends a search, which returns it, but once a handler is set the
+    /// error is passed to it and the search carries on with the next entry. This applies to
+    /// finds, `find_matching()`, `walk()` (and so `list_with_extension()`) and `freeze()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.on_error(|path, error| eprintln!("skipped {}: {}", path.display(), error));
+    ///     println!("{:?}", search_path.find("ls"));
+    /// }
+    /// ```
+    pub fn on_error<F>(&mut self, handler: F) where F: Fn(&Path, &Error) + Send + Sync + 'static {
+        self.error_handler = Some(ErrorHandler(Arc::new(handler)));
+    }
+
+    // Pass an IO error to the error handler, if one is set
+    pub(crate) fn notify_error(&self, path: &Path, error: &Error) {
+        trace_event!(warn, path = %path.display(), error = %error, "error while searching");
+        if let Some(handler) = &self.error_handler {
+            (handler.0)(path, error);
+        }
+    }
+
+    // Pass an IO error to the error handler, returning `Ok` so that the search can continue if
+    // one is set, or the error if not
+    pub(crate) fn handle_error(&self, path: &Path, error: Error) -> Result<(), Error> {
+        match self.error_handler {
+            Some(_) => {
+                self.notify_error(path, &error);
+                Ok(())
+            }
+            None => Err(error)
+        }
+    }
+
+    // Create a `NotFound` error with `message`. With the "suggest" feature, the names in the
+    // search path that are close to `file_name` are suggested in it.
+    pub(crate) fn not_found(&self, _file_name: &str, message: String) -> Error {
+        #[cfg(feature = "suggest")]
+        let message = Self::with_suggestions(message, &self.suggestions(_file_name));
+        Error::new(ErrorKind::NotFound, message)
+    }
+
+    #[cfg(feature = "suggest")]
+    // Add the names in `suggestions` (if there are any) to an error message
+    pub(crate) fn with_suggestions(message: String, suggestions: &[String]) -> String {
+        if suggestions.is_empty() {
+            return message;
+        }
+
+        let names: Vec<String> = suggestions.iter().map(|name| format!("`{}`", name)).collect();
+        format!("{}, did you mean {}?", message, names.join(" or "))
+    }
+}
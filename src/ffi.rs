@@ -0,0 +1,145 @@
+//! A C ABI for `Simpath`, activated by the "ffi" feature, so that it can be used from C and other
+//! languages. The C header for it, `include/simpath.h`, is generated using `cbindgen`. The C
+//! library is built with `cargo rustc --lib --features ffi --crate-type cdylib` (or `staticlib`).
+//!
+//! Ownership rules:
+//! * A `Simpath` created with `simpath_new()` is owned by the caller, who must free it with
+//!   `simpath_free()`
+//! * Strings passed to these functions are borrowed for the duration of the call only, and must
+//!   be nul-terminated and valid UTF-8
+//! * Strings returned by these functions are owned by the caller, who must free them with
+//!   `simpath_string_free()` (and not `free()`)
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use super::Simpath;
+
+// Get a `&str` from a C string, or `None` if it is NULL or not valid UTF-8
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// Create a new `Simpath`, loading its entries from the environment variable `var_name`, as
+/// `Simpath::new()` does. Returns NULL if `var_name` is NULL or not valid UTF-8.
+///
+/// # Safety
+/// `var_name` must be NULL or a valid nul-terminated string. The `Simpath` returned must be
+/// freed with `simpath_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn simpath_new(var_name: *const c_char) -> *mut Simpath {
+    match to_str(var_name) {
+        Some(var_name) => Box::into_raw(Box::new(Simpath::new(var_name))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free a `Simpath` created with `simpath_new()`. Does nothing if `simpath` is NULL.
+///
+/// # Safety
+/// `simpath` must be NULL or a `Simpath` returned by `simpath_new()` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn simpath_free(simpath: *mut Simpath) {
+    if !simpath.is_null() {
+        drop(Box::from_raw(simpath));
+    }
+}
+
+/// Add an entry (a directory or, with the "urls" feature, a Url) to a `Simpath`, as
+/// `Simpath::add()` does. Returns 0 on success, or -1 if `simpath` or `entry` is NULL or
+/// `entry` is not valid UTF-8.
+///
+/// # Safety
+/// `simpath` must be NULL or a valid `Simpath` returned by `simpath_new()`, and `entry` must be
+/// NULL or a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn simpath_add(simpath: *mut Simpath, entry: *const c_char) -> c_int {
+    match (simpath.as_mut(), to_str(entry)) {
+        (Some(simpath), Some(entry)) => {
+            simpath.add(entry);
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Find a file, directory or resource called `name` in a `Simpath`, as `Simpath::find()` does.
+/// Returns the path (or Url) of the entry found, which must be freed with
+/// `simpath_string_free()`, or NULL if it was not found or an argument is not valid.
+///
+/// # Safety
+/// `simpath` must be NULL or a valid `Simpath` returned by `simpath_new()`, and `name` must be
+/// NULL or a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn simpath_find(simpath: *const Simpath, name: *const c_char) -> *mut c_char {
+    let found = match (simpath.as_ref(), to_str(name)) {
+        (Some(simpath), Some(name)) => simpath.find(name),
+        _ => return ptr::null_mut(),
+    };
+
+    match found.ok().and_then(|found| CString::new(Simpath::location(&found)).ok()) {
+        Some(location) => location.into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free a string returned by one of these functions. Does nothing if `string` is NULL.
+///
+/// # Safety
+/// `string` must be NULL or a string returned by one of these functions that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn simpath_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{CStr, CString};
+    use std::fs;
+    use std::ptr;
+
+    use super::{simpath_add, simpath_find, simpath_free, simpath_new, simpath_string_free};
+
+    #[test]
+    fn find_through_ffi() {
+        let temp_dir = tempdir::TempDir::new("simpath").unwrap().into_path();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let var_name = CString::new("SIMPATH_FFI_TEST").unwrap();
+        let dir = CString::new(temp_dir.display().to_string()).unwrap();
+        let name = CString::new("tool").unwrap();
+        let missing = CString::new("no-way-this-exists").unwrap();
+
+        unsafe {
+            let simpath = simpath_new(var_name.as_ptr());
+            assert!(!simpath.is_null());
+            assert_eq!(simpath_add(simpath, dir.as_ptr()), 0);
+
+            let found = simpath_find(simpath, name.as_ptr());
+            assert!(!found.is_null());
+            assert_eq!(CStr::from_ptr(found).to_str().unwrap(), temp_dir.join("tool").display().to_string());
+            simpath_string_free(found);
+
+            assert!(simpath_find(simpath, missing.as_ptr()).is_null());
+            simpath_free(simpath);
+        }
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn null_arguments() {
+        unsafe {
+            assert!(simpath_new(ptr::null()).is_null());
+            assert_eq!(simpath_add(ptr::null_mut(), ptr::null()), -1);
+            assert!(simpath_find(ptr::null(), ptr::null()).is_null());
+            simpath_free(ptr::null_mut());
+            simpath_string_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,781 @@
+//! Freezing a `Simpath` into a `FrozenSimpath`, whose lookups use an index of the file names in
+//! its directories built when it was frozen.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::SystemTime;
+
+#[cfg(feature = "persist")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+use super::{FileType, FoundType, Simpath};
+use super::directory::EntryKind;
+use super::error::PathName;
+use super::search::Stat;
+
+// The number of bits in a `BloomFilter` for each name added to it, and the number of bits set for
+// each name, which give a false positive rate of about 1%
+const BLOOM_BITS_PER_NAME: usize = 10;
+const BLOOM_HASHES: u64 = 7;
+
+// A Bloom filter of the names of the files in a directory, that can tell for certain that a name
+// is not in the directory without reading it
+#[derive(Clone)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    // Create an empty filter sized for `names` names
+    fn new(names: usize) -> Self {
+        BloomFilter { bits: vec![0; (names.max(1) * BLOOM_BITS_PER_NAME).div_ceil(64)] }
+    }
+
+    // The bits for `name`, derived from one hash of it using double hashing
+    fn positions(&self, name: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let hash = hasher.finish();
+        let bit_count = self.bits.len() as u64 * 64;
+        (0..BLOOM_HASHES).map(move |i| ((hash & 0xffff_ffff).wrapping_add(i.wrapping_mul(hash >> 32)) % bit_count) as usize)
+    }
+
+    fn insert(&mut self, name: &str) {
+        for position in self.positions(name).collect::<Vec<_>>() {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    // If this is false the name is certainly not in the filter, if true it probably is
+    fn may_contain(&self, name: &str) -> bool {
+        self.positions(name).all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+impl fmt::Debug for BloomFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BloomFilter({} bits)", self.bits.len() * 64)
+    }
+}
+
+// The files read from a directory to index it, along with when the directory was modified (if
+// that was recorded) so that it can be read again if it changes
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "persist", derive(Serialize, Deserialize))]
+struct DirectoryListing {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    files: Vec<(String, EntryKind)>,
+}
+
+#[cfg(feature = "persist")]
+// The contents of an index file written by `Simpath::freeze_with_index_file()`
+#[derive(Serialize, Deserialize)]
+struct SavedIndex {
+    directories: Vec<DirectoryListing>,
+}
+
+// The index of the file names in the directories of a `FrozenSimpath`
+#[derive(Debug)]
+struct FrozenIndex {
+    names: HashMap<String, Vec<(PathBuf, EntryKind)>>,
+    // What the names were built from, only kept when the index is revalidated
+    listings: Vec<DirectoryListing>,
+}
+
+impl Simpath {
+    /// Set whether a Bloom filter of the file names in each directory is built when the `Simpath`
+    /// is frozen. Lookups in the `FrozenSimpath` of relative paths with several components (such
+    /// as `sys/types.h`) then skip the directories that certainly do not contain their first
+    /// component without touching the file system, which speeds up workloads that mostly look
+    /// for names that do not exist. The default is not to build them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("CPATH");
+    ///     search_path.set_bloom_filters(true);
+    ///     let frozen = search_path.freeze();
+    ///     println!("{:?}", frozen.find("sys/types.h"));
+    /// }
+    /// ```
+    pub fn set_bloom_filters(&mut self, bloom_filters: bool) {
+        self.bloom_filters = bloom_filters;
+    }
+
+    /// Get whether a Bloom filter of the file names in each directory is built when frozen
+    pub fn bloom_filters(&self) -> bool {
+        self.bloom_filters
+    }
+
+    /// Set whether a `FrozenSimpath` made from this `Simpath` checks, before each lookup, if any of
+    /// its directories have been modified (using their modification times) since they were
+    /// indexed, and reads again only those that have. This keeps the index fresh at the cost of
+    /// checking each directory, which is much cheaper than reading them all. Directories that
+    /// could not be read when it was frozen are not checked. The default is not to check them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_revalidate_index(true);
+    ///     let frozen = search_path.freeze();
+    ///     println!("{:?}", frozen.find("ls"));
+    /// }
+    /// ```
+    pub fn set_revalidate_index(&mut self, revalidate_index: bool) {
+        self.revalidate_index = revalidate_index;
+    }
+
+    /// Get whether a `FrozenSimpath` made from this `Simpath` re-reads directories that have been
+    /// modified since they were indexed
+    pub fn revalidate_index(&self) -> bool {
+        self.revalidate_index
+    }
+
+    // Check if `dir` may contain `relative_path`, using the Bloom filter built for it when frozen.
+    // If there is no filter for it then it may.
+    pub(crate) fn may_contain(&self, dir: &Path, relative_path: &Path) -> bool {
+        let filters = self.directory_filters.as_ref()
+            .map(|filters| filters.read().unwrap_or_else(PoisonError::into_inner));
+        match (filters.as_ref().and_then(|filters| filters.get(dir)), relative_path.components().next()) {
+            (Some(filter), Some(Component::Normal(first))) => first.to_str()
+                .map(|first| filter.may_contain(&self.index_key(first))).unwrap_or(true),
+            _ => true,
+        }
+    }
+
+    /// Freeze the `Simpath` into an immutable `FrozenSimpath`, reading all its directories once to
+    /// build an index of the file names in them so that lookups do not need to read them again.
+    /// Directories that cannot be read are left out of the index, and the errors passed to the
+    /// handler set with `on_error()`, if there is one.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let frozen = Simpath::new("PATH").freeze();
+    ///     let shared = frozen.clone();
+    ///     std::thread::spawn(move || println!("{:?}", shared.find("ls"))).join().unwrap();
+    /// }
+    /// ```
+    pub fn freeze(self) -> FrozenSimpath {
+        let mut files_scanned = 0;
+        let listings = self.search_dirs().filter_map(|dir| {
+            // Read before the directory is, so that changes made while reading it are not missed
+            let modified = if self.revalidate_index { Self::modified(dir) } else { None };
+            self.index_directory(dir, &mut files_scanned)
+                .map(|files| DirectoryListing { path: dir.to_path_buf(), modified, files })
+        }).collect();
+        self.into_frozen(listings)
+    }
+
+    #[cfg(feature = "persist")]
+    /// Freeze the `Simpath` as `freeze()` does, saving the file names read from its directories
+    /// to `index_file` (as JSON) so that later invocations can load them from there instead of
+    /// reading the directories again. A directory is only read again if it has been modified since
+    /// its file names were saved, or was not in the index file, and the file is then updated.
+    ///
+    /// The index file should only be shared by `Simpath`s that are configured the same way, as
+    /// the files saved for a directory are those that were not excluded when it was read.
+    /// Errors reading the index file are ignored (the directories are read instead), and errors
+    /// writing it are passed to the handler set with `on_error()`, if there is one.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let index_file = std::env::temp_dir().join("simpath-index.json");
+    ///     let frozen = Simpath::new("PATH").freeze_with_index_file(&index_file);
+    ///     println!("{:?}", frozen.find("ls"));
+    /// }
+    /// ```
+    pub fn freeze_with_index_file<P: AsRef<Path>>(self, index_file: P) -> FrozenSimpath {
+        let index_file = index_file.as_ref();
+        let mut saved = match fs::read_to_string(index_file) {
+            Ok(contents) => match serde_json::from_str::<SavedIndex>(&contents) {
+                Ok(saved) => saved.directories,
+                Err(_error) => {
+                    trace_event!(debug, index_file = %index_file.display(), reason = %_error,
+                                 "ignored invalid index file");
+                    vec!()
+                }
+            },
+            Err(_) => vec!(),
+        };
+
+        let mut files_scanned = 0;
+        let mut changed = false;
+        let mut directories = vec!();
+        for dir in self.search_dirs() {
+            // Read before the directory is, so that changes made while reading it are not missed
+            let modified = match fs::metadata(dir).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified,
+                Err(e) => {
+                    self.notify_error(dir, &e);
+                    continue;
+                }
+            };
+
+            match saved.iter().position(|saved| saved.path == *dir && saved.modified == Some(modified)) {
+                Some(position) => {
+                    trace_event!(debug, directory = %dir.display(), "loaded directory from index file");
+                    directories.push(saved.swap_remove(position));
+                }
+                None => {
+                    changed = true;
+                    if let Some(files) = self.index_directory(dir, &mut files_scanned) {
+                        directories.push(DirectoryListing { path: dir.to_path_buf(), modified: Some(modified), files });
+                    }
+                }
+            }
+        }
+
+        // Directories no longer in the search path are dropped from the index file
+        if changed || !saved.is_empty() {
+            let saved = SavedIndex { directories };
+            if let Err(e) = serde_json::to_string(&saved).map_err(Error::other)
+                .and_then(|contents| fs::write(index_file, contents)) {
+                self.notify_error(index_file, &e);
+            }
+            directories = saved.directories;
+        }
+
+        self.into_frozen(directories)
+    }
+
+    // Get when `dir` was last modified, if it can be read
+    fn modified(dir: &Path) -> Option<SystemTime> {
+        fs::metadata(dir).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    // Read the names and kinds of the files in `dir` that are not excluded, for an index. If it
+    // cannot be read then `None` is returned and the error passed to the error handler.
+    fn index_directory(&self, dir: &Path, files_scanned: &mut usize) -> Option<Vec<(String, EntryKind)>> {
+        trace_event!(debug, directory = %dir.display(), "indexing directory");
+        let dir_exclusions = self.dir_exclusions(dir);
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.notify_error(dir, &e);
+                return None;
+            }
+        };
+
+        let mut files = vec!();
+        for entry in entries {
+            match entry.and_then(|entry| entry.metadata().map(|metadata| (entry, metadata))) {
+                Ok((entry, _)) if self.excluded(&dir_exclusions, &entry) => {}
+                Ok((entry, metadata)) => if let Some(filename) = entry.file_name().to_str() {
+                    files.push((filename.to_string(), metadata.file_type().into()));
+                },
+                Err(e) => self.notify_error(dir, &e),
+            }
+            *files_scanned += 1;
+        }
+        self.notify_progress(dir, *files_scanned);
+        Some(files)
+    }
+
+    // Build a `FrozenSimpath` from the files read from each directory, in search order
+    fn into_frozen(mut self, listings: Vec<DirectoryListing>) -> FrozenSimpath {
+        if self.bloom_filters {
+            let filters = listings.iter()
+                .map(|listing| (listing.path.clone(), self.bloom_filter(&listing.files))).collect();
+            self.directory_filters = Some(Arc::new(RwLock::new(filters)));
+        }
+
+        let names = self.index_names(&listings);
+        FrozenSimpath {
+            directories: self.directories().map(Path::to_path_buf).collect(),
+            index: Arc::new(RwLock::new(FrozenIndex { names, listings })),
+            simpath: Arc::new(self),
+        }
+    }
+
+    // Build a Bloom filter of the names of `files`
+    fn bloom_filter(&self, files: &[(String, EntryKind)]) -> BloomFilter {
+        let mut filter = BloomFilter::new(files.len());
+        for (filename, _) in files {
+            filter.insert(&self.index_key(filename));
+        }
+        filter
+    }
+
+    // Index the files read from each directory by name, in search order
+    fn index_names(&self, listings: &[DirectoryListing]) -> HashMap<String, Vec<(PathBuf, EntryKind)>> {
+        let mut names: HashMap<String, Vec<(PathBuf, EntryKind)>> = HashMap::new();
+        for listing in listings {
+            for (filename, kind) in &listing.files {
+                names.entry(self.index_key(filename)).or_default().push((listing.path.join(filename), *kind));
+            }
+        }
+        names
+    }
+
+    // The key used to index a file name, so that names that match are indexed together
+    fn index_key(&self, file_name: &str) -> String {
+        #[cfg(feature = "unicode")]
+        if self.unicode_normalization {
+            return file_name.nfc().collect();
+        }
+
+        file_name.to_string()
+    }
+}
+
+/// `FrozenSimpath` is an immutable snapshot of a `Simpath`, created using `Simpath::freeze()`,
+/// that finds file names using an index built when it was frozen. It is `Send` and `Sync` and
+/// cheap to clone, so it can be shared between threads that resolve names concurrently.
+///
+/// Entries are still checked for the required access when they are found, but files added to or
+/// removed from the directories after it was frozen are not seen, unless the `Simpath` was set
+/// to revalidate its index using `Simpath::set_revalidate_index()`, or a directory is read again
+/// using `invalidate_entry()`.
+#[derive(Clone, Debug)]
+pub struct FrozenSimpath {
+    simpath: Arc<Simpath>,
+    directories: Arc<[PathBuf]>,
+    index: Arc<RwLock<FrozenIndex>>,
+}
+
+impl FrozenSimpath {
+    /// Get the name associated with the `Simpath` that was frozen
+    pub fn name(&self) -> &str {
+        self.simpath.name()
+    }
+
+    /// Get the directories that were searched by the `Simpath` when it was frozen
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Try to find a file or resource by name (not full path), as `Simpath::find()` does
+    pub fn find(&self, file_name: &str) -> Result<FoundType, Error> {
+        self.find_type(file_name, FileType::Any)
+    }
+
+    /// Try to find an entry of a specific `FileType`, as `Simpath::find_type()` does
+    pub fn find_type(&self, file_name: &str, file_type: FileType) -> Result<FoundType, Error> {
+        self.lookup(file_name, &file_type, false).map(|mut found| found.remove(0))
+    }
+
+    /// Find all the entries of a specific `FileType`, in search order, as
+    /// `Simpath::find_all()` does
+    pub fn find_all(&self, file_name: &str, file_type: FileType) -> Result<Vec<FoundType>, Error> {
+        self.lookup(file_name, &file_type, true)
+    }
+
+    /// Try to find an entry of a specific `FileType`, as `find_type()` does, also returning the
+    /// index in `directories()` of the directory it was found in, or `None` if it was not found
+    /// in one (e.g. it is a resource). If the entry turns out to be stale (e.g. the file was
+    /// deleted before it could be opened) the index can be passed to `invalidate_entry()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let frozen = Simpath::new("PATH").freeze();
+    ///     if let Ok((Some(index), found)) = frozen.find_with_index("ls", FileType::File) {
+    ///         println!("Found {:?} in {}", found, frozen.directories()[index].display());
+    ///     }
+    /// }
+    /// ```
+    pub fn find_with_index(&self, file_name: &str, file_type: FileType)
+        -> Result<(Option<usize>, FoundType), Error> {
+        let found = self.find_type(file_name, file_type)?;
+        let location = Simpath::location(&found);
+        let components = Path::new(file_name).components().count();
+        let index = self.directories.iter().position(|dir| Path::new(&location).strip_prefix(dir)
+            .map(|rest| rest.components().count() == components).unwrap_or(false));
+        Ok((index, found))
+    }
+
+    /// Read again the directory at `index` in `directories()`, replacing what was indexed for it,
+    /// so that files removed from or added to just that directory are seen by later lookups,
+    /// without revalidating the others. Returns `false` if there is no directory at `index`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, FoundType, Simpath};
+    /// use std::fs::File;
+    ///
+    /// fn main() {
+    ///     let frozen = Simpath::new("PATH").freeze();
+    ///     if let Ok((Some(index), FoundType::File(path))) = frozen.find_with_index("ls", FileType::File) {
+    ///         if File::open(&path).is_err() {
+    ///             frozen.invalidate_entry(index);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn invalidate_entry(&self, index: usize) -> bool {
+        let dir = match self.directories.get(index) {
+            Some(dir) => dir,
+            None => return false,
+        };
+        trace_event!(debug, directory = %dir.display(), "directory invalidated");
+
+        let modified = Simpath::modified(dir);
+        let mut files_scanned = 0;
+        let files = self.simpath.index_directory(dir, &mut files_scanned);
+
+        if let Some(filters) = &self.simpath.directory_filters {
+            let mut filters = filters.write().unwrap_or_else(PoisonError::into_inner);
+            match &files {
+                Some(files) => filters.insert(dir.clone(), self.simpath.bloom_filter(files)),
+                None => filters.remove(dir),
+            };
+        }
+
+        let mut frozen_index = self.index.write().unwrap_or_else(PoisonError::into_inner);
+        let position = frozen_index.listings.iter().position(|listing| listing.path == *dir);
+        match (position, files) {
+            (Some(position), Some(files)) => {
+                frozen_index.listings[position] = DirectoryListing { path: dir.clone(), modified, files };
+            }
+            (Some(position), None) => {
+                frozen_index.listings.remove(position);
+            }
+            (None, Some(files)) => {
+                // It could not be read before, so insert it before the directories after it
+                let later = &self.directories[index + 1..];
+                let position = frozen_index.listings.iter().position(|listing| later.contains(&listing.path))
+                    .unwrap_or(frozen_index.listings.len());
+                frozen_index.listings.insert(position, DirectoryListing { path: dir.clone(), modified, files });
+            }
+            (None, None) => {}
+        }
+        frozen_index.names = self.simpath.index_names(&frozen_index.listings);
+        true
+    }
+
+    // Read again the directories that have been modified since they were indexed, updating the
+    // index (and Bloom filters) with the files now in them
+    fn revalidate(&self) {
+        let modified: Vec<DirectoryListing> = {
+            let index = self.index.read().unwrap_or_else(PoisonError::into_inner);
+            let mut files_scanned = 0;
+            index.listings.iter().filter_map(|listing| {
+                let modified = Simpath::modified(&listing.path);
+                if modified == listing.modified {
+                    return None;
+                }
+                trace_event!(debug, directory = %listing.path.display(), "directory modified since it was indexed");
+                let files = self.simpath.index_directory(&listing.path, &mut files_scanned).unwrap_or_default();
+                Some(DirectoryListing { path: listing.path.clone(), modified, files })
+            }).collect()
+        };
+
+        if modified.is_empty() {
+            return;
+        }
+
+        // The listings may have been changed by `invalidate_entry()` since the read lock was
+        // released, so each directory is looked up again, and skipped if it is no longer indexed
+        let mut index = self.index.write().unwrap_or_else(PoisonError::into_inner);
+        let mut filters = self.simpath.directory_filters.as_ref()
+            .map(|filters| filters.write().unwrap_or_else(PoisonError::into_inner));
+        for listing in modified {
+            if let Some(position) = index.listings.iter().position(|indexed| indexed.path == listing.path) {
+                if let Some(filters) = filters.as_mut() {
+                    filters.insert(listing.path.clone(), self.simpath.bloom_filter(&listing.files));
+                }
+                index.listings[position] = listing;
+            }
+        }
+        drop(filters);
+        index.names = self.simpath.index_names(&index.listings);
+    }
+
+    // Look up `file_name` in the index, returning just the first match unless `all` is true.
+    // Paths and resources are not indexed, so they are searched for as the `Simpath` would.
+    fn lookup(&self, file_name: &str, file_type: &FileType, all: bool) -> Result<Vec<FoundType>, Error> {
+        if self.simpath.revalidate_index {
+            self.revalidate();
+        }
+
+        if *file_type == FileType::Resource || Path::new(file_name).is_absolute()
+            || Simpath::relative_path(file_name)?.is_some() {
+            return self.simpath.search_without_suggestions(file_name, file_type, all);
+        }
+
+        let mut found = vec!();
+        // The first entry that matched but could not be accessed, reported if nothing else is found
+        let mut denied: Option<Error> = None;
+        // Resources may be before directories in the search order, so all matches are needed to
+        // find the first one
+        #[cfg(feature = "urls")]
+        let all_matches = all || (*file_type == FileType::Any && self.simpath.has_urls());
+        #[cfg(not(feature = "urls"))]
+        let all_matches = all;
+
+        let index = self.index.read().unwrap_or_else(PoisonError::into_inner);
+        let candidates = index.names.get(&self.simpath.index_key(file_name));
+        trace_event!(debug, search_path = %self.simpath.name, name = file_name, hit = candidates.is_some(),
+                     "index lookup");
+        self.simpath.count(Stat::Search);
+        self.simpath.count(if candidates.is_some() { Stat::CacheHit } else { Stat::CacheMiss });
+        for (path, found_filetype) in candidates.into_iter().flatten() {
+            if let Some(entry) = Simpath::matching_type(path.clone(), *found_filetype, file_type) {
+                match self.simpath.check_access(path) {
+                    Ok(()) => {
+                        if let Some(dir) = path.parent() {
+                            self.simpath.count_hit(dir.display().to_string());
+                        }
+                        if !all_matches {
+                            return Ok(vec!(entry));
+                        }
+                        found.push(entry);
+                    }
+                    Err(e) => { denied.get_or_insert(e); }
+                }
+            }
+        }
+
+        #[cfg(feature = "urls")]
+        if *file_type == FileType::Any {
+            if let Ok(resources) = self.simpath.search_without_suggestions(file_name, &FileType::Resource, true) {
+                found.extend(resources);
+                self.simpath.sort_by_search_order(&mut found);
+                if !all {
+                    found.truncate(1);
+                }
+            }
+        }
+
+        if !found.is_empty() {
+            return Ok(found);
+        }
+
+        let message = format!("Could not find type '{:?}' called '{}' in {}",
+                              file_type, file_name, PathName(&self.simpath.name));
+        #[cfg(feature = "suggest")]
+        let message = match denied {
+            Some(_) => message,
+            None => Simpath::with_suggestions(message, &self.suggestions(file_name)),
+        };
+        Err(denied.unwrap_or_else(|| Error::new(ErrorKind::NotFound, message)))
+    }
+
+    #[cfg(feature = "suggest")]
+    /// Get the names in the index that are close to `file_name` (e.g. `clang-14` for `clang`),
+    /// as `Simpath::suggestions()` does, without reading the directories again
+    pub fn suggestions(&self, file_name: &str) -> Vec<String> {
+        let index = self.index.read().unwrap_or_else(PoisonError::into_inner);
+        Simpath::close_names(file_name, index.listings.iter()
+            .flat_map(|listing| listing.files.iter().map(|(name, _)| name.as_str())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use crate::{FileType, FoundType, FrozenSimpath, Simpath};
+    use crate::test::temp_dir;
+
+    #[test]
+    fn find_in_frozen_path() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FrozenSimpath>();
+
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join("first")).unwrap();
+        fs::create_dir_all(temp_dir.join("second/sub")).unwrap();
+        fs::File::create(temp_dir.join("second/tool")).unwrap();
+        fs::File::create(temp_dir.join("first/tool")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(temp_dir.join("first"));
+        path.add_directory(temp_dir.join("second"));
+
+        let frozen = path.freeze();
+        // files created after freezing are not in the index
+        fs::File::create(temp_dir.join("second/late")).unwrap();
+
+        assert_eq!(frozen.clone().find("tool").unwrap(), FoundType::File(temp_dir.join("first/tool")));
+        assert_eq!(frozen.find_all("tool", FileType::File).unwrap().len(), 2);
+        assert_eq!(frozen.find_type("sub", FileType::Directory).unwrap(),
+                   FoundType::Directory(temp_dir.join("second/sub")));
+        assert!(frozen.find_type("sub", FileType::File).is_err());
+        assert!(frozen.find("late").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    #[cfg(feature = "persist")]
+    fn freeze_with_index_file() {
+        let index_dir = temp_dir();
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let index_file = index_dir.join("index.json");
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert_eq!(path.clone().freeze_with_index_file(&index_file).find("tool").unwrap(),
+                   FoundType::File(temp_dir.join("tool")));
+        assert!(index_file.exists());
+
+        // An unmodified directory is not read again, so the files saved for it are used
+        let contents = fs::read_to_string(&index_file).unwrap();
+        fs::write(&index_file, contents.replace("\"tool\"", "\"saved-tool\"")).unwrap();
+        assert_eq!(path.clone().freeze_with_index_file(&index_file).find("saved-tool").unwrap(),
+                   FoundType::File(temp_dir.join("saved-tool")));
+
+        // A modified directory is read again, once its modification time can have changed
+        std::thread::sleep(Duration::from_secs(1));
+        fs::File::create(temp_dir.join("new-tool")).unwrap();
+        let frozen = path.freeze_with_index_file(&index_file);
+        assert!(frozen.find("new-tool").is_ok());
+        assert!(frozen.find("saved-tool").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+        let _ = fs::remove_dir_all(index_dir);
+    }
+
+    #[test]
+    fn frozen_with_bloom_filters() {
+        let temp_dir = temp_dir();
+        fs::create_dir(temp_dir.join("sys")).unwrap();
+        fs::File::create(temp_dir.join("sys").join("types.h")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        path.set_bloom_filters(true);
+        assert!(path.bloom_filters());
+        let with_filters = path.clone().freeze();
+        path.set_bloom_filters(false);
+        let without_filters = path.freeze();
+
+        assert_eq!(with_filters.find("sys/types.h").unwrap(),
+                   FoundType::File(temp_dir.join("sys").join("types.h")));
+        assert!(with_filters.find("net/if.h").is_err());
+
+        // Directories that did not contain the first component when frozen are not read again
+        fs::create_dir(temp_dir.join("net")).unwrap();
+        fs::File::create(temp_dir.join("net").join("if.h")).unwrap();
+        assert!(with_filters.find("net/if.h").is_err());
+        assert!(without_filters.find("net/if.h").is_ok());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn frozen_revalidates_modified_directories() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        path.set_bloom_filters(true);
+        let not_revalidated = path.clone().freeze();
+        path.set_revalidate_index(true);
+        assert!(path.revalidate_index());
+        let revalidated = path.freeze();
+        assert!(revalidated.find("tool").is_ok());
+
+        // Wait until the modification time of the directory can have changed
+        std::thread::sleep(Duration::from_secs(1));
+        fs::create_dir(temp_dir.join("sys")).unwrap();
+        fs::File::create(temp_dir.join("sys").join("types.h")).unwrap();
+        fs::remove_file(temp_dir.join("tool")).unwrap();
+
+        assert!(revalidated.find("sys").is_ok());
+        assert!(revalidated.find("sys/types.h").is_ok());
+        assert!(revalidated.find("tool").is_err());
+        assert!(not_revalidated.find("sys").is_err());
+        assert!(not_revalidated.find("sys/types.h").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn frozen_entry_invalidated() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        let frozen = path.freeze();
+
+        let (index, found) = frozen.find_with_index("tool", FileType::File).unwrap();
+        assert_eq!(index, Some(0));
+        assert_eq!(found, FoundType::File(frozen.directories()[0].join("tool")));
+
+        // Removing the file does not change the index until the entry is invalidated
+        fs::remove_file(temp_dir.join("tool")).unwrap();
+        fs::File::create(temp_dir.join("other")).unwrap();
+        assert!(frozen.find("tool").is_ok());
+        assert!(frozen.invalidate_entry(0));
+        assert!(frozen.find("tool").is_err());
+        assert!(frozen.find("other").is_ok());
+        assert!(!frozen.invalidate_entry(1));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn frozen_revalidated_while_entries_invalidated() {
+        let temp_dir = temp_dir();
+        let dirs: Vec<PathBuf> = (0..4).map(|i| temp_dir.join(format!("dir{}", i))).collect();
+        let mut path = Simpath::new("MyName");
+        for dir in &dirs {
+            fs::create_dir(dir).unwrap();
+            fs::File::create(dir.join("tool")).unwrap();
+            path.add_directory(dir);
+        }
+        path.set_revalidate_index(true);
+        let frozen = path.freeze();
+
+        // Directories are removed and created again while they are invalidated, which removes
+        // them from the index and inserts them again, while lookups revalidate the index
+        let done = Arc::new(AtomicBool::new(false));
+        let finders: Vec<_> = (0..4).map(|_| {
+            let frozen = frozen.clone();
+            let done = done.clone();
+            std::thread::spawn(move || while !done.load(Ordering::Relaxed) {
+                let _ = frozen.find("tool");
+            })
+        }).collect();
+        for round in 0..200 {
+            let index = round % dirs.len();
+            fs::remove_dir_all(&dirs[index]).unwrap();
+            frozen.invalidate_entry(index);
+            fs::create_dir(&dirs[index]).unwrap();
+            fs::File::create(dirs[index].join("tool")).unwrap();
+            frozen.invalidate_entry(index);
+        }
+        done.store(true, Ordering::Relaxed);
+        for finder in finders {
+            finder.join().unwrap();
+        }
+
+        let found = frozen.find_all("tool", FileType::File).unwrap();
+        assert_eq!(found, dirs.iter().map(|dir| FoundType::File(dir.join("tool"))).collect::<Vec<_>>());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,82 @@
+//! Finding the headers included by C and C++ source files in a `Simpath`, such as one created by
+//! `Simpath::c_include_path()`, in the order a compiler searches for them.
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use super::{FileType, FoundType, PathName, Simpath, DEFAULT_SEPARATOR_CHAR};
+
+/// `IncludeStyle` is how a header is included, which decides where it is searched for
+#[derive(Clone, Debug, PartialEq)]
+pub enum IncludeStyle {
+    /// `#include <header.h>`: only the entries of the search path are searched
+    Angle,
+    /// `#include "header.h"` in a file in the directory given: that directory is searched first,
+    /// then the entries of the search path
+    Quote(PathBuf),
+}
+
+impl Simpath {
+    /// Create a new simpath for finding the headers included by C and C++ source files, from the
+    /// `CPATH` environment variable followed by `C_INCLUDE_PATH`, as GCC and Clang search them.
+    /// As for them, an empty entry in either (including a leading or trailing separator) is the
+    /// current directory.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::c_include_path();
+    ///     println!("{}", search_path);
+    /// }
+    /// ```
+    pub fn c_include_path() -> Self {
+        let mut search_path = Self::empty("CPATH,C_INCLUDE_PATH", DEFAULT_SEPARATOR_CHAR);
+        search_path.posix_empty_entries = true;
+
+        for var_name in &["CPATH", "C_INCLUDE_PATH"] {
+            search_path.add_from_env_var(var_name);
+        }
+
+        search_path
+    }
+
+    /// Find the header `header`, which may be a relative path such as `sys/types.h`, as a
+    /// compiler does for an include of it in the `IncludeStyle` given. The path of the header
+    /// is returned, or a `NotFound` error if it could not be found.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{IncludeStyle, Simpath};
+    /// use std::path::PathBuf;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::c_include_path();
+    ///     search_path.add_directory("/usr/include");
+    ///     match search_path.find_header("sys/types.h", &IncludeStyle::Angle) {
+    ///         Ok(header) => println!("<sys/types.h> is '{}'", header.display()),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    ///     let style = IncludeStyle::Quote(PathBuf::from("src"));
+    ///     match search_path.find_header("config.h", &style) {
+    ///         Ok(header) => println!("\"config.h\" is '{}'", header.display()),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_header(&self, header: &str, style: &IncludeStyle) -> Result<PathBuf, Error> {
+        if let IncludeStyle::Quote(dir) = style {
+            let path = dir.join(header);
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+
+        match self.find_type(header, FileType::File) {
+            Ok(FoundType::File(path)) => Ok(path),
+            Ok(_) => Err(Error::new(ErrorKind::NotFound,
+                                    format!("Could not find header '{}' in {}", header, PathName(&self.name)))),
+            Err(e) => Err(e),
+        }
+    }
+}
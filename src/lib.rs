@@ -8,33 +8,99 @@
 //!
 //! If you wish to separate entries with a different separator, it can be modified via API.
 //!
-#[cfg(feature = "urls")]
+#[cfg(all(feature = "urls", not(target_arch = "wasm32")))]
 extern crate curl;
-#[cfg(feature = "urls")]
-extern crate url;
+#[cfg(feature = "semver")]
+extern crate semver;
+#[cfg(unix)]
+extern crate libc;
+#[cfg(feature = "unicode")]
+extern crate unicode_normalization;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(feature = "config", feature = "persist"))]
+extern crate serde_json;
+#[cfg(feature = "config")]
+extern crate toml;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "ignore")]
+extern crate ignore;
+#[cfg(feature = "digest")]
+extern crate sha2;
+#[cfg(feature = "clap")]
+extern crate clap;
+#[cfg(feature = "term")]
+extern crate termcolor;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+extern crate web_sys;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::fs;
 use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::ops::BitOr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "urls")]
-use curl::easy::{Handler, WriteError};
+use ::url::Url;
+#[cfg(feature = "semver")]
+use semver::{Version, VersionReq};
+#[cfg(feature = "term")]
+use termcolor::{Color, ColorSpec, WriteColor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+// Emit a `tracing` event at `level` (e.g. `debug`), when the "tracing" feature is enabled
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    }};
+}
+
+mod directory;
+mod error;
+mod frozen;
+mod include;
+mod pkg_config;
+mod search;
+mod shared;
 #[cfg(feature = "urls")]
-use url::Url;
-use std::collections::HashSet;
+mod url;
 
+pub use directory::{AddReport, Canonicalize, DirectoryEntry, Entry, EntryDecision, EntryFilter, EntrySource};
+pub use error::{NewError, PathError};
+pub use frozen::FrozenSimpath;
+pub use include::IncludeStyle;
+pub use pkg_config::PcFile;
+pub use search::{CancellationToken, CaseInsensitiveName, ExactName, FindReport, GlobName, NameMatcher, SearchOptions,
+                 SearchStats, SearchTrace};
+pub use shared::{SharedSimpath, SimpathGuard};
 #[cfg(feature = "urls")]
-struct Collector(Vec<u8>);
+pub use self::url::{HttpClient, HttpMethod, HttpRequest, HttpResponse, MockHttpClient, ProbeMethod, ResourceMetadata,
+                    UrlEntry, UrlProfile};
 
+use directory::{DirExclusions, EntryHandler, Exclusions, Wildcard, DEFAULT_MAX_WILDCARD_DEPTH};
+use error::{ErrorHandler, PathName};
+use frozen::BloomFilter;
+use search::StatsCollector;
 #[cfg(feature = "urls")]
-impl Handler for Collector {
-    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
-        self.0.extend_from_slice(data);
-        Ok(data.len())
-    }
-}
+use self::url::{HttpClientHandle, HttpOptions, DEFAULT_MAX_CONCURRENT_PROBES};
+
+// In the browser there is no file system, so directory entries are not searched
+const NO_FILE_SYSTEM: bool = cfg!(all(feature = "wasm", target_arch = "wasm32"));
 
 // Character used to separate directories in a Path Environment variable on windows is ";"
 #[cfg(target_family = "windows")]
@@ -49,14 +115,126 @@ const DEFAULT_SEPARATOR_CHAR: char = ':';
 pub struct Simpath {
     separator: char,
     name: String,
-    directories: HashSet<PathBuf>,
+    posix_empty_entries: bool,
+    access: Access,
+    #[cfg(feature = "unicode")]
+    unicode_normalization: bool,
+    canonicalize: Canonicalize,
+    share_directories: bool,
+    limits: Limits,
+    entries: Arc<Vec<Entry>>,
+    // The comparable forms of the directories in `entries`, to find duplicates without a scan
+    comparables: Arc<HashSet<PathBuf>>,
+    wildcards: Vec<Wildcard>,
+    max_wildcard_depth: usize,
+    strict_directories: bool,
+    rejected: Vec<(SkippedEntry, EntrySource)>,
+    original: Option<OriginalValue>,
+    preserve_original: bool,
+    expand_variables: bool,
+    variables: Option<HashMap<String, String>>,
+    env_source: Option<EnvSourceHandle>,
+    // The separators of environment variables that were read with one other than `separator`
+    env_separators: HashMap<String, char>,
+    #[cfg(feature = "urls")]
+    probe_method: ProbeMethod,
+    #[cfg(feature = "urls")]
+    accepted_statuses: Vec<u32>,
+    #[cfg(feature = "urls")]
+    http: HttpOptions,
     #[cfg(feature = "urls")]
-    urls: HashSet<Url>,
+    max_concurrent_probes: usize,
+    #[cfg(feature = "urls")]
+    http_client: Option<HttpClientHandle>,
+    #[cfg(feature = "urls")]
+    strict_urls: bool,
+    error_handler: Option<ErrorHandler>,
+    entry_handler: Option<EntryHandler>,
+    stats: Option<Arc<StatsCollector>>,
+    cancellation: Vec<CancellationToken>,
+    progress_handler: Option<ProgressHandler>,
+    exclusions: Exclusions,
+    #[cfg(unix)]
+    mode_filter: ModeFilter,
+    bloom_filters: bool,
+    directory_filters: Option<Arc<RwLock<HashMap<PathBuf, BloomFilter>>>>,
+    revalidate_index: bool,
+}
+
+// The exact text of the environment variable a `Simpath` was parsed from, along with the search
+// path it gave (as `to_env_string()` would return it), to tell if it has been changed since
+#[derive(Clone, Debug)]
+struct OriginalValue {
+    text: String,
+    parsed: String,
+}
+
+// A callback that is passed each directory as it is finished with during a long enumeration,
+// with the number of files scanned so far
+type ProgressCallback = dyn Fn(&Path, usize) + Send + Sync;
+
+#[derive(Clone)]
+struct ProgressHandler(Arc<ProgressCallback>);
+
+impl fmt::Debug for ProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressHandler")
+    }
+}
+
+/// `EnvSource` is where a `Simpath` reads environment variables from. By default they are read
+/// from the process environment (`ProcessEnv`), but another source can be set with
+/// `Simpath::set_env_source()` or used to create one with `Simpath::new_with_env_source()`, such
+/// as a `HashMap` holding a snapshot of an environment, one parsed from a remote shell or a
+/// container spec, or fixed values for deterministic tests.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::Simpath;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// fn main() {
+///     let snapshot: HashMap<String, String> = std::env::vars().collect();
+///     let search_path = Simpath::new_with_env_source("PATH", Arc::new(snapshot));
+///     println!("{}", search_path);
+/// }
+/// ```
+pub trait EnvSource: Send + Sync {
+    /// Get the value of the environment variable called `name`, or `None` if it is not set
+    fn var_os(&self, name: &str) -> Option<OsString>;
+}
+
+/// `ProcessEnv` is an `EnvSource` that reads environment variables from the process environment
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessEnv;
+
+impl EnvSource for ProcessEnv {
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        env::var_os(name)
+    }
+}
+
+impl<S: BuildHasher + Send + Sync> EnvSource for HashMap<String, String, S> {
+    fn var_os(&self, name: &str) -> Option<OsString> {
+        self.get(name).map(OsString::from)
+    }
+}
+
+#[derive(Clone)]
+struct EnvSourceHandle(Arc<dyn EnvSource>);
+
+impl fmt::Debug for EnvSourceHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EnvSource")
+    }
 }
 
 /// `FileType` can be used to find an entry in a path of a specific type (`Directory`, `File`, `URL`)
-/// or of `Any` type
-#[derive(Debug, PartialEq)]
+/// or of `Any` type. On unix the special file types (`Symlink`, `Fifo`, `Socket`, `BlockDevice` and
+/// `CharDevice`) can also be searched for.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum FileType {
     /// An entry in the `Simpath` of type `File`
     File,
@@ -66,6 +244,164 @@ pub enum FileType {
     Resource,
     /// An entry in the `Simpath` of `Any` types
     Any,
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a symbolic link
+    Symlink,
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a FIFO (named pipe)
+    Fifo,
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a socket
+    Socket,
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a block device
+    BlockDevice,
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a character device
+    CharDevice,
+}
+
+/// `FileTypes` is a set of `FileType`s, any of which is accepted by `Simpath::find_types()` and
+/// `Simpath::find_all_types()`. It is created by combining `FileType`s with `|`, or from a slice
+/// of them. The types are tried in the order they were added.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::{FileType, FileTypes};
+///
+/// fn main() {
+///     let types = FileType::File | FileType::Directory;
+///     assert_eq!(types, FileTypes::from(&[FileType::File, FileType::Directory][..]));
+///     assert!(types.contains(&FileType::Directory));
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileTypes(Vec<FileType>);
+
+impl FileTypes {
+    /// Create an empty set of `FileType`s
+    pub fn new() -> Self {
+        FileTypes::default()
+    }
+
+    /// Add `file_type` to the set, if it is not already in it
+    pub fn insert(&mut self, file_type: FileType) {
+        if !self.0.contains(&file_type) {
+            self.0.push(file_type);
+        }
+    }
+
+    /// Check if `file_type` is in the set
+    pub fn contains(&self, file_type: &FileType) -> bool {
+        self.0.contains(file_type)
+    }
+
+    /// Get the `FileType`s in the set, in the order they were added
+    pub fn types(&self) -> &[FileType] {
+        &self.0
+    }
+}
+
+impl From<FileType> for FileTypes {
+    fn from(file_type: FileType) -> Self {
+        FileTypes(vec!(file_type))
+    }
+}
+
+impl From<&[FileType]> for FileTypes {
+    fn from(file_types: &[FileType]) -> Self {
+        let mut set = FileTypes::new();
+        for file_type in file_types {
+            set.insert(file_type.clone());
+        }
+        set
+    }
+}
+
+impl BitOr for FileType {
+    type Output = FileTypes;
+
+    fn bitor(self, other: FileType) -> FileTypes {
+        FileTypes::from(self) | other
+    }
+}
+
+impl BitOr<FileType> for FileTypes {
+    type Output = FileTypes;
+
+    fn bitor(mut self, file_type: FileType) -> FileTypes {
+        self.insert(file_type);
+        self
+    }
+}
+
+/// `Access` is the access the current user must have to an entry for it to be found
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum Access {
+    /// Entries are found regardless of the current user's access to them
+    Any,
+    /// Only entries the current user can read are found
+    Readable,
+    /// Only entries the current user can execute are found
+    Executable,
+}
+
+/// `Limits` bound the size of a `Simpath` and the work done by each search of it, so that an
+/// enormous environment variable, accidental or malicious, cannot cause unbounded memory use or
+/// IO when a `Simpath` is created from untrusted input. They are set with `Simpath::set_limits()`
+/// or when creating one with `Simpath::new_with_limits()`. By default there are no limits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// The maximum number of entries (directories and Urls) in the `Simpath`
+    pub max_entries: usize,
+    /// The maximum length, in bytes, of an entry added to the `Simpath`
+    pub max_entry_length: usize,
+    /// The maximum number of entries searched by each find
+    pub max_searches: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_entries: usize::MAX,
+            max_entry_length: usize::MAX,
+            max_searches: usize::MAX,
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+// The declarative description of a `Simpath` read by `Simpath::from_config()`
+#[derive(Deserialize)]
+#[serde(default)]
+struct Config {
+    name: String,
+    separator: char,
+    directories: Vec<String>,
+    urls: Vec<String>,
+    resolve: Vec<String>,
+    posix_empty_entries: bool,
+    required_access: Access,
+    unicode_normalization: bool,
+    canonicalize: Canonicalize,
+}
+
+#[cfg(feature = "config")]
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            name: String::new(),
+            separator: DEFAULT_SEPARATOR_CHAR,
+            directories: vec!(),
+            urls: vec!(),
+            resolve: vec!(),
+            posix_empty_entries: false,
+            required_access: Access::Any,
+            unicode_normalization: false,
+            canonicalize: Canonicalize::None,
+        }
+    }
 }
 
 /// `FoundType` indicates what type of entry was found
@@ -78,14 +414,294 @@ pub enum FoundType {
     #[cfg(feature = "urls")]
     /// An entry in the `Simpath` of type `Url`
     Resource(Url),
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a symbolic link
+    Symlink(PathBuf),
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a FIFO (named pipe)
+    Fifo(PathBuf),
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a socket
+    Socket(PathBuf),
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a block device
+    BlockDevice(PathBuf),
+    #[cfg(unix)]
+    /// An entry in the `Simpath` that is a character device
+    CharDevice(PathBuf),
+}
+
+impl FoundType {
+    /// Get the path of the entry found, or `None` if it is a `Resource`
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     if let Some(path) = search_path.find("ls").ok().as_ref().and_then(|found| found.path()) {
+    ///         println!("'ls' is in '{}'", path.parent().unwrap_or(path).display());
+    ///     }
+    /// }
+    /// ```
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            FoundType::File(path) | FoundType::Directory(path) => Some(path),
+            #[cfg(feature = "urls")]
+            FoundType::Resource(_) => None,
+            #[cfg(unix)]
+            FoundType::Symlink(path) | FoundType::Fifo(path) | FoundType::Socket(path) |
+            FoundType::BlockDevice(path) | FoundType::CharDevice(path) => Some(path),
+        }
+    }
+
+    /// Convert the entry found into its path, or `None` if it is a `Resource`
+    pub fn into_path(self) -> Option<PathBuf> {
+        match self {
+            FoundType::File(path) | FoundType::Directory(path) => Some(path),
+            #[cfg(feature = "urls")]
+            FoundType::Resource(_) => None,
+            #[cfg(unix)]
+            FoundType::Symlink(path) | FoundType::Fifo(path) | FoundType::Socket(path) |
+            FoundType::BlockDevice(path) | FoundType::CharDevice(path) => Some(path),
+        }
+    }
+
+    #[cfg(feature = "urls")]
+    /// Get the Url of the entry found if it is a `Resource`, or `None` if it is not
+    pub fn as_url(&self) -> Option<&Url> {
+        match self {
+            FoundType::Resource(url) => Some(url),
+            _ => None,
+        }
+    }
+}
+
+/// A `FoundType` can be used wherever a path is, e.g. with `File::open()` or `Command::new()`.
+/// A `Resource` is used as a path of its Url, which is not a file that can be opened.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::Simpath;
+/// use std::process::Command;
+///
+/// fn main() {
+///     let search_path = Simpath::new("PATH");
+///     if let Ok(ls) = search_path.find("ls") {
+///         let _ = Command::new(ls).arg("-l").status();
+///     }
+/// }
+/// ```
+impl AsRef<Path> for FoundType {
+    fn as_ref(&self) -> &Path {
+        match self {
+            #[cfg(feature = "urls")]
+            FoundType::Resource(url) => Path::new(url.as_str()),
+            _ => self.path().unwrap_or_else(|| Path::new("")),
+        }
+    }
+}
+
+impl AsRef<OsStr> for FoundType {
+    fn as_ref(&self) -> &OsStr {
+        AsRef::<Path>::as_ref(self).as_os_str()
+    }
+}
+
+/// A `FoundType` is displayed as its path, or the Url of a `Resource`
+impl fmt::Display for FoundType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "urls")]
+            FoundType::Resource(url) => write!(f, "{}", url),
+            _ => write!(f, "{}", AsRef::<Path>::as_ref(self).display()),
+        }
+    }
+}
+
+#[cfg(feature = "semver")]
+/// `VersionedFile` is a file found in the `Simpath` whose name is of the form `name-version`
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionedFile {
+    /// The path to the file that was found
+    pub path: PathBuf,
+    /// The version parsed from the file's name
+    pub version: Version,
+}
+
+/// `SecurityWarning` describes a potential security problem with a directory in a `Simpath`,
+/// as reported by `Simpath::audit()`
+#[derive(Debug, PartialEq)]
+pub enum SecurityWarning {
+    /// The directory is relative, so what it refers to depends on the current directory
+    Relative(PathBuf),
+    /// The directory is in a temporary directory, such as `/tmp`
+    Temporary(PathBuf),
+    #[cfg(unix)]
+    /// The directory can be written to by any user
+    WorldWritable(PathBuf),
+    #[cfg(unix)]
+    /// The directory is owned by a user (uid) that is neither root nor the current user
+    UnexpectedOwner(PathBuf, u32),
+}
+
+impl fmt::Display for SecurityWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecurityWarning::Relative(dir) =>
+                write!(f, "'{}' is a relative directory", dir.display()),
+            SecurityWarning::Temporary(dir) =>
+                write!(f, "'{}' is in a temporary directory", dir.display()),
+            #[cfg(unix)]
+            SecurityWarning::WorldWritable(dir) =>
+                write!(f, "'{}' is writable by all users", dir.display()),
+            #[cfg(unix)]
+            SecurityWarning::UnexpectedOwner(dir, uid) =>
+                write!(f, "'{}' is owned by uid {} which is not root or the current user",
+                       dir.display(), uid),
+        }
+    }
+}
+
+#[cfg(unix)]
+/// `ModeFilter` selects the files found in a `Simpath` by their unix owner, group and mode bits,
+/// for example to only find files owned by root, or to not find setuid binaries, as security
+/// tooling auditing what a `PATH` lets a user run may need. It is set with
+/// `Simpath::set_mode_filter()`. Files it rejects are skipped and the search continues, as for
+/// files the current user does not have the required `Access` to.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::ModeFilter;
+///
+/// fn main() {
+///     let filter = ModeFilter::new().owned_by(0).exclude_setuid().exclude_setgid();
+///     println!("{:?}", filter);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModeFilter {
+    owners: Vec<u32>,
+    groups: Vec<u32>,
+    excluded_mode: u32,
+    required_mode: u32,
+}
+
+#[cfg(unix)]
+impl ModeFilter {
+    /// Create a `ModeFilter` that accepts all files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept files owned by the user `uid`, or one of the other owners added
+    pub fn owned_by(mut self, uid: u32) -> Self {
+        self.owners.push(uid);
+        self
+    }
+
+    /// Only accept files whose group is `gid`, or one of the other groups added
+    pub fn group(mut self, gid: u32) -> Self {
+        self.groups.push(gid);
+        self
+    }
+
+    /// Do not accept files with any of the `bits` set in their mode, e.g. `0o002` for files that
+    /// any user can write to
+    pub fn exclude_mode(mut self, bits: u32) -> Self {
+        self.excluded_mode |= bits;
+        self
+    }
+
+    /// Only accept files with all of the `bits` set in their mode
+    pub fn require_mode(mut self, bits: u32) -> Self {
+        self.required_mode |= bits;
+        self
+    }
+
+    /// Do not accept setuid files
+    pub fn exclude_setuid(self) -> Self {
+        self.exclude_mode(0o4000)
+    }
+
+    /// Do not accept setgid files
+    pub fn exclude_setgid(self) -> Self {
+        self.exclude_mode(0o2000)
+    }
+
+    // Check if the filter accepts every file, so files do not need to be checked against it
+    fn accepts_all(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Get the reason a file with `metadata` is rejected by the filter, if it is
+    fn rejects(&self, metadata: &fs::Metadata) -> Option<String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mode = metadata.mode();
+        if !self.owners.is_empty() && !self.owners.contains(&metadata.uid()) {
+            Some(format!("is owned by uid {} which is not an accepted owner", metadata.uid()))
+        } else if !self.groups.is_empty() && !self.groups.contains(&metadata.gid()) {
+            Some(format!("has group gid {} which is not an accepted group", metadata.gid()))
+        } else if mode & self.excluded_mode != 0 {
+            Some(format!("has mode {:o} with excluded bits {:o} set", mode & 0o7777, mode & self.excluded_mode))
+        } else if mode & self.required_mode != self.required_mode {
+            Some(format!("has mode {:o} without required bits {:o} set", mode & 0o7777, self.required_mode))
+        } else {
+            None
+        }
+    }
+}
+
+/// `SkippedEntry` is an entry that was skipped while searching or validating a `Simpath`,
+/// with the reason why
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SkippedEntry {
+    /// The entry (directory, file or URL) that was skipped
+    pub entry: String,
+    /// The reason it was skipped
+    pub reason: String,
+}
+
+/// `ValidateReport` describes the validation of the directories in a `Simpath`, as returned by
+/// `Simpath::validate_report()`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ValidateReport {
+    /// The name of the `Simpath` that was validated
+    pub search_path: String,
+    /// The directories that are valid
+    pub valid: Vec<String>,
+    /// The directories that are not valid, and why
+    pub invalid: Vec<SkippedEntry>,
+}
+
+/// `Shadowed` is a file name that exists in more than one directory in a `Simpath`, so that the
+/// later ones are shadowed by the first
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Shadowed {
+    /// The file name
+    pub name: String,
+    /// The file that will be found when searching for `name`
+    pub found: String,
+    /// The files that are shadowed by it, in search order
+    pub shadowed: Vec<String>,
 }
 
-/// When validating a `Simpath` there can be the following types of `PathError`s returned
-pub enum PathError {
-    /// The `Path` entry does not exist on the file system
-    DoesNotExist(String),
-    /// The `Path` entry cannot be reads
-    CannotRead(String),
+/// `ShadowReport` lists the files in a `Simpath` that are shadowed by files of the same name in
+/// earlier directories, as returned by `Simpath::shadow_report()`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ShadowReport {
+    /// The name of the `Simpath` that was checked
+    pub search_path: String,
+    /// The file names that are shadowed
+    pub shadowed: Vec<Shadowed>,
+    /// The directories that could not be read, and why
+    pub skipped: Vec<SkippedEntry>,
 }
 
 impl Simpath {
@@ -113,17 +729,7 @@ impl Simpath {
     /// ```
     ///
     pub fn new(var_name: &str) -> Self {
-        let mut search_path = Simpath {
-            separator: DEFAULT_SEPARATOR_CHAR,
-            name: var_name.to_string(),
-            directories: HashSet::<PathBuf>::new(),
-            #[cfg(feature = "urls")]
-            urls: HashSet::<Url>::new(),
-        };
-
-        search_path.add_from_env_var(var_name);
-
-        search_path
+        Self::new_with_separator(var_name, DEFAULT_SEPARATOR_CHAR)
     }
 
     /// Create a new simpath, providing the name of the environment variable to initialize the
@@ -153,288 +759,564 @@ impl Simpath {
     /// }
     /// ```
     pub fn new_with_separator(var_name: &str, separator: char) -> Self {
-        let mut search_path = Simpath {
-            separator,
-            name: var_name.to_string(),
-            directories: HashSet::<PathBuf>::new(),
-            #[cfg(feature = "urls")]
-            urls: HashSet::<Url>::new(),
-        };
+        let mut search_path = Self::empty(var_name, separator);
 
         search_path.add_from_env_var(var_name);
 
         search_path
     }
 
-    /// Get the currently set separator character that is used when parsing entries from an environment
-    /// variable
-    pub fn separator(&self) -> char {
-        self.separator
-    }
-
-    /// Get the name associated with the simpath. Note that this could be an empty String
+    /// Create a new simpath, as `new()` does, calling `handler` with each entry parsed from the
+    /// environment variable as it is added, ignored or rejected (see `on_entry()`)
+    ///
     /// ```
     /// extern crate simpath;
-    /// use simpath::Simpath;
+    /// use simpath::{EntryDecision, Simpath};
     ///
     /// fn main() {
-    ///     let search_path = Simpath::new("PATH");
-    ///     println!("Directories in Search Path: {:?}", search_path.name());
+    ///     let search_path = Simpath::new_with_entry_handler("PATH", |entry, _source, decision| {
+    ///         if let EntryDecision::Rejected(reason) = decision {
+    ///             eprintln!("Bad entry '{}' in PATH: {}", entry, reason);
+    ///         }
+    ///     });
+    ///     println!("{}", search_path);
     /// }
     /// ```
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn new_with_entry_handler<F>(var_name: &str, handler: F) -> Self
+        where F: Fn(&str, &EntrySource, &EntryDecision) + Send + Sync + 'static {
+        let mut search_path = Self::empty(var_name, DEFAULT_SEPARATOR_CHAR);
+        search_path.on_entry(handler);
+
+        search_path.add_from_env_var(var_name);
+
+        search_path
     }
 
-    /// Get the list of directories that are included in the Search Path
+    /// Create a new simpath, as `new()` does, with `Limits` on its size and on the work done by
+    /// each search of it. If the environment variable has more entries than `limits.max_entries`,
+    /// or an entry longer than `limits.max_entry_length`, then an `InvalidInput` error is returned
+    /// instead of a `Simpath`.
     ///
     /// ```
     /// extern crate simpath;
-    /// use simpath::Simpath;
+    /// use simpath::{Limits, Simpath};
     ///
     /// fn main() {
-    ///     let search_path = Simpath::new("PATH");
-    ///     println!("Directories in Search Path: {:?}", search_path.directories());
+    ///     let limits = Limits { max_entries: 64, max_entry_length: 4096, max_searches: 64 };
+    ///     match Simpath::new_with_limits("PATH", limits) {
+    ///         Ok(search_path) => println!("{}", search_path),
+    ///         Err(e) => eprintln!("{}", e)
+    ///     }
     /// }
     /// ```
-    pub fn directories(&self) -> &HashSet<PathBuf> {
-        &self.directories
+    pub fn new_with_limits(var_name: &str, limits: Limits) -> Result<Self, Error> {
+        let mut search_path = Self::empty(var_name, DEFAULT_SEPARATOR_CHAR);
+        search_path.limits = limits;
+
+        if let Some(value) = search_path.env_var(var_name) {
+            let parts = Self::split_entries(&value, search_path.separator);
+            let entries: Vec<&OsStr> = parts.iter().filter_map(|part| search_path.parsed_entry(part)).collect();
+            if entries.len() > limits.max_entries {
+                return Err(search_path.entry_limit_error());
+            }
+            for entry in entries {
+                search_path.check_entry_length(entry)?;
+            }
+
+            search_path.add_env_value(var_name, &value, search_path.separator);
+        }
+
+        Ok(search_path)
     }
 
-    #[cfg(feature = "urls")]
-    /// Get the list of URLs that are included in the Search Path
+    /// Set the `Limits` on the size of the search path and the work done by each search of it.
+    /// Entries already in it are kept, but no more are added while there are `max_entries` or
+    /// more, and entries added that are longer than `max_entry_length` are rejected. Entries that
+    /// are not added because of the limits are passed to the error handler (see `on_error()`).
+    /// Finds that search more than `max_searches` entries stop and return an error.
     ///
     /// ```
     /// extern crate simpath;
-    /// use simpath::Simpath;
-    /// use std::env;
+    /// use simpath::{Limits, Simpath};
     ///
     /// fn main() {
-    ///     env::set_var("TEST", "http://ibm.com,https://hp.com");
-    ///     let search_path = Simpath::new("TEST");
-    ///     println!("URLs in Search Path: {:?}", search_path.urls());
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.set_limits(Limits { max_entries: 1, ..Default::default() });
+    ///     search_path.add_directory("/usr/bin");
+    ///     search_path.add_directory("/bin");
+    ///     assert_eq!(search_path.directories().count(), 1);
     /// }
     /// ```
-    pub fn urls(&self) -> &HashSet<Url> {
-        &self.urls
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
     }
 
-    /// Try to find a file or resource by name (not full path) on a search path.
-    /// Searching for a file could cause errors, so Result<FoundType, io::Error> is returned
-    /// If it is found `Ok(FoundType)` is returned indicating where the resource/file can be found.
-    /// If it is not found then `Err` is returned.
-    ///
-    /// ```
-    /// extern crate simpath;
-    /// use simpath::Simpath;
-    ///
-    /// fn main() {
-    ///     let search_path = Simpath::new("PATH");
-    ///     match search_path.find("my-file") {
-    ///         Ok(_found_dir) => println!("Didn't expect that!!"),
-    ///         Err(e)         => println!("{}", e.to_string())
-    ///     }
-    /// }
-    /// ```
-    pub fn find(&self, file_name: &str) -> Result<FoundType, Error> {
-        self.find_type(file_name, FileType::Any)
+    /// Get the `Limits` on the size of the search path and the work done by each search of it
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    // Create the error for an entry that cannot be added because the search path is full
+    pub(crate) fn entry_limit_error(&self) -> Error {
+        Error::new(ErrorKind::InvalidInput,
+                   format!("{} cannot have more than the limit of {} entries",
+                           PathName(&self.name), self.limits.max_entries))
     }
 
-    /// find an entry of a specific `FileType` in a `Path`
+    // Check that an entry is not longer than the limit for entries of the search path
+    fn check_entry_length(&self, entry: &OsStr) -> Result<(), Error> {
+        if entry.len() > self.limits.max_entry_length {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("An entry of {} bytes is longer than the limit of {} bytes for entries of {}",
+                                          entry.len(), self.limits.max_entry_length, PathName(&self.name))));
+        }
+
+        Ok(())
+    }
+
+    /// Create a new simpath, as `new()` does, but return a `NewError` if the environment variable
+    /// does not give a usable search path: if it is not set, if it has no entries, or if all of
+    /// its directory entries are rejected because they do not exist or cannot be read. This lets
+    /// an application fail with a clear message instead of continuing with an empty search path.
     ///
     /// ```
     /// extern crate simpath;
     /// use simpath::Simpath;
     ///
     /// fn main() {
-    ///     use simpath::FileType;
-    ///     let search_path = Simpath::new("PATH");
-    ///     match search_path.find_type("my-file", FileType::Directory) {
-    ///         Ok(_found_dir) => println!("Didn't expect that!!"),
-    ///         Err(e)         => println!("{}", e.to_string())
+    ///     match Simpath::try_new("PATH") {
+    ///         Ok(search_path) => println!("{}", search_path),
+    ///         Err(e) => eprintln!("{}", e)
     ///     }
     /// }
     /// ```
-    pub fn find_type(&self, file_name: &str, file_type: FileType) -> Result<FoundType, Error> {
-        if file_type == FileType::File || file_type == FileType::Directory || file_type == FileType::Any {
-            for search_dir in &self.directories {
-                for entry in fs::read_dir(search_dir)? {
-                    let file = entry?;
-                    if let Some(filename) = file.file_name().to_str() {
-                        if filename == file_name {
-                            let found_filetype = file.metadata()?.file_type();
-                            match file_type {
-                                FileType::Any => return Ok(FoundType::File(file.path())),
-                                FileType::Directory if found_filetype.is_dir() => return Ok(FoundType::Directory(file.path())),
-                                FileType::File if found_filetype.is_file() || found_filetype.is_symlink() => return Ok(FoundType::File(file.path())),
-                                _ => { /* keep looking */ }
-                            }
-                        }
-                    }
-                }
-            }
+    pub fn try_new(var_name: &str) -> Result<Self, NewError> {
+        Self::new(var_name).checked(var_name)
+    }
+
+    /// Create a new simpath, as `new_with_separator()` does, but return a `NewError` if the
+    /// environment variable does not give a usable search path, as `try_new()` does
+    pub fn try_new_with_separator(var_name: &str, separator: char) -> Result<Self, NewError> {
+        Self::new_with_separator(var_name, separator).checked(var_name)
+    }
+
+    /// Create a new simpath, as `new_with_env_source()` does, but return a `NewError` if the
+    /// environment variable does not give a usable search path, as `try_new()` does
+    pub fn try_new_with_env_source<E: EnvSource + 'static>(var_name: &str, source: Arc<E>)
+        -> Result<Self, NewError> {
+        Self::new_with_env_source(var_name, source).checked(var_name)
+    }
+
+    // Check that a simpath created from the environment variable `var_name` has entries that
+    // can be used, returning a `NewError` describing the problem if it does not
+    fn checked(self, var_name: &str) -> Result<Self, NewError> {
+        if self.env_var(var_name).is_none() {
+            return Err(NewError::Unset(var_name.to_string()));
+        }
+
+        if self.is_empty() {
+            return Err(NewError::Empty(var_name.to_string()));
         }
 
         #[cfg(feature = "urls")]
-            // Look for a URL that ends with '/file_name'
-        if file_type == FileType::Resource || file_type == FileType::Any {
-            for url in &self.urls {
-                let mut segments = url.path_segments()
-                    .ok_or_else(|| Error::new(ErrorKind::NotFound, "Could not get path segments"))?;
-                if segments.next_back() == Some(file_name) {
-                    return Ok(FoundType::Resource(url.clone()));
-                }
-            }
+        if self.has_urls() {
+            return Ok(self);
+        }
+
+        let report = self.validate_report();
+        if report.valid.is_empty() {
+            return Err(NewError::Rejected { var_name: var_name.to_string(), rejected: report.invalid });
         }
 
-        Err(Error::new(ErrorKind::NotFound,
-                       format!("Could not find type '{:?}' called '{}' in search path '{}'",
-                               file_type, file_name, self.name)))
+        Ok(self)
     }
 
-    /// Add an to the search path.
-    ///
-    /// if "urls" feature is enabled:
-    ///     If it parses as as web Url it will be added to the list of
-    ///     base Urls to search, otherwise it will be added to the list of directories to search.
-    /// if "urls" feature is *not* enabled:
-    ///     It is assumed to be a directory and added using `add_directory()`
+    /// Create a new simpath, as `new()` does, but reading environment variables from `source`
+    /// instead of the process environment, both to initialize it and later (e.g. when expanding
+    /// references to variables in entries, or adding entries from other variables).
     ///
     /// ```
     /// extern crate simpath;
     /// use simpath::Simpath;
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("PATH");
-    ///     search_path.add(".");
-    ///
-    /// #[cfg(feature = "urls")]
-    ///     search_path.add("http://ibm.com");
+    ///     let mut env = HashMap::new();
+    ///     env.insert("PATH".to_string(), "/usr/bin".to_string());
+    ///     let search_path = Simpath::new_with_env_source("PATH", Arc::new(env));
+    ///     assert_eq!(search_path.directories().collect::<Vec<_>>(), vec!(std::path::PathBuf::from("/usr/bin")));
+    /// }
+    /// ```
+    pub fn new_with_env_source<E: EnvSource + 'static>(var_name: &str, source: Arc<E>) -> Self {
+        let mut search_path = Self::empty(var_name, DEFAULT_SEPARATOR_CHAR);
+        search_path.set_env_source(source);
+
+        search_path.add_from_env_var(var_name);
+
+        search_path
+    }
+
+    /// Set the `EnvSource` that environment variables are read from, instead of the process
+    /// environment. Entries already added are not changed.
+    pub fn set_env_source<E: EnvSource + 'static>(&mut self, source: Arc<E>) {
+        self.env_source = Some(EnvSourceHandle(source));
+    }
+
+    // Get the value of the environment variable called `name`, from the `EnvSource` if one is set
+    fn env_var(&self, name: &str) -> Option<OsString> {
+        match &self.env_source {
+            Some(EnvSourceHandle(source)) => source.var_os(name),
+            None => env::var_os(name),
+        }
+    }
+
+    // Get the value of the environment variable `name`, if it is set and is valid unicode
+    #[cfg(any(all(not(unix), not(target_os = "wasi")), all(unix, feature = "xdg"),
+              all(feature = "urls", not(target_arch = "wasm32"))))]
+    fn env_var_string(&self, name: &str) -> Option<String> {
+        self.env_var(name).and_then(|value| value.into_string().ok())
+    }
+
+    /// Create a new simpath from a number of environment variables, adding the entries of each
+    /// in turn, so that entries from earlier variables are searched first. The environment variable
+    /// each entry came from is recorded, and can be retrieved using `source()` on `entries()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::from_env_vars(&["CPATH", "C_INCLUDE_PATH", "INCLUDE"]);
+    ///     for entry in search_path.entries() {
+    ///         println!("'{}' came from {}", entry, entry.source());
+    ///     }
+    /// }
+    /// ```
+    pub fn from_env_vars(var_names: &[&str]) -> Self {
+        let mut search_path = Self::empty(&var_names.join(","), DEFAULT_SEPARATOR_CHAR);
+
+        for var_name in var_names {
+            search_path.add_from_env_var(var_name);
+        }
+
+        search_path
+    }
+
+    /// Create a new simpath from the environment variable `var_name`, as `new()` does, but if the
+    /// variable is not set or contains no entries then use the `defaults` directories instead.
     ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::from_env_or("PLUGIN_PATH", &["/usr/lib/app/plugins"]);
     ///     println!("{}", search_path);
     /// }
     /// ```
-    pub fn add(&mut self, entry: &str) {
-        #[cfg(not(feature = "urls"))]
-            self.add_directory(entry);
+    pub fn from_env_or(var_name: &str, defaults: &[&str]) -> Self {
+        let mut search_path = Self::new(var_name);
 
-        #[cfg(feature = "urls")]
-        match Url::parse(entry) {
-            Ok(url) => {
-                match url.scheme() {
-                    #[cfg(feature = "urls")]
-                    "http" | "https" => self.add_url(&url),
-                    "file" => self.add_directory(url.path()),
-                    _ => self.add_directory(entry)
-                }
+        if search_path.is_empty() {
+            for dir in defaults {
+                search_path.push_directory(dir, EntrySource::Default);
             }
-            Err(_) => self.add_directory(entry) /* default to being a directory path */
         }
+
+        search_path
     }
 
-    /// Add a directory to the list of directories to search for files.
+    /// Create a new simpath for finding executables from the `PATH` environment variable, using
+    /// the platform's default system directories if `PATH` is not set or is empty
+    /// (`/usr/local/bin:/usr/bin:/bin` on unix, `%SystemRoot%\System32;%SystemRoot%;...`
+    /// on windows).
     ///
     /// ```
     /// extern crate simpath;
     /// use simpath::Simpath;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("PATH");
-    ///     search_path.add_directory(".");
-    ///     println!("Directories in Search Path: {:?}", search_path.directories());
+    ///     let search_path = Simpath::system_path();
+    ///     match search_path.find("ls") {
+    ///         Ok(found) => println!("'ls' was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
     /// }
     /// ```
-    pub fn add_directory(&mut self, dir: &str) {
-        self.directories.insert(PathBuf::from(dir));
+    pub fn system_path() -> Self {
+        Self::system_path_from(Self::empty("PATH", DEFAULT_SEPARATOR_CHAR))
     }
 
-    #[cfg(feature = "urls")]
-    /// Add a Url to the list of Base Urls to be used when searching for resources.
+    /// Create a new simpath for finding executables, as `system_path()` does, but reading
+    /// environment variables from `source` instead of the process environment (see
+    /// `new_with_env_source()`)
     ///
     /// ```
     /// extern crate simpath;
-    /// extern crate url;
-    ///
     /// use simpath::Simpath;
-    /// use url::Url;
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("WEB");
-    ///     search_path.add_url(&Url::parse("http://ibm.com").unwrap());
-    ///     println!("Urls in Search Path: {:?}", search_path.urls());
+    ///     let search_path = Simpath::system_path_with_env_source(Arc::new(HashMap::<String, String>::new()));
+    ///     assert!(!search_path.is_empty());
     /// }
     /// ```
-    pub fn add_url(&mut self, url: &Url) {
-        self.urls.insert(url.clone());
+    pub fn system_path_with_env_source<E: EnvSource + 'static>(source: Arc<E>) -> Self {
+        let mut search_path = Self::empty("PATH", DEFAULT_SEPARATOR_CHAR);
+        search_path.set_env_source(source);
+        Self::system_path_from(search_path)
+    }
+
+    // Add the directories in `PATH` to `search_path`, or the platform's default system
+    // directories if it is not set or is empty
+    fn system_path_from(mut search_path: Self) -> Self {
+        search_path.add_from_env_var("PATH");
+        if search_path.is_empty() {
+            for dir in search_path.system_directories() {
+                search_path.push_directory(dir, EntrySource::Default);
+            }
+        }
+
+        search_path
     }
 
-    /// Check if a search path contains an entry
+    // The platform's default system directories for executables
+    fn system_directories(&self) -> Vec<String> {
+        #[cfg(not(target_family = "windows"))]
+        return vec!("/usr/local/bin".into(), "/usr/bin".into(), "/bin".into());
+
+        #[cfg(target_family = "windows")]
+        {
+            let system_root = self.system_root();
+            vec!(format!("{}\\System32", system_root),
+                 system_root.clone(),
+                 format!("{}\\System32\\Wbem", system_root))
+        }
+    }
+
+    #[cfg(target_family = "windows")]
+    // The directory Windows is installed in, from `%SystemRoot%`
+    fn system_root(&self) -> String {
+        self.env_var_string("SystemRoot").unwrap_or_else(|| "C:\\Windows".into())
+    }
+
+    /// Create a new simpath for finding executables that, for compatibility with how
+    /// `CreateProcess` on Windows resolves a command, searches the directory the running
+    /// application was loaded from and then the current directory before the directories in
+    /// `PATH`. On Windows the system directories (`%SystemRoot%\System32`, `%SystemRoot%\System`
+    /// and `%SystemRoot%`) are also searched, in that order, before `PATH`. Elsewhere, if `PATH`
+    /// is not set or is empty, the platform's default system directories are used after the
+    /// current directory, as `system_path()` does.
+    ///
+    /// The current directory is the one when the simpath is created, and is not updated if it
+    /// changes later.
     ///
     /// ```
     /// extern crate simpath;
     /// use simpath::Simpath;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("FakeEnvVar");
-    ///     if search_path.contains(".") {
-    ///         println!("Well that's a surprise!");
+    ///     let search_path = Simpath::system_path_with_current_dir();
+    ///     match search_path.find("tool") {
+    ///         Ok(found) => println!("'tool' would be run from '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
     ///     }
     /// }
     /// ```
-    pub fn contains(&self, entry: &str) -> bool {
-        if self.directories.contains(&PathBuf::from(entry)) {
-            return true;
+    pub fn system_path_with_current_dir() -> Self {
+        Self::system_path_with_current_dir_from(Self::empty("PATH", DEFAULT_SEPARATOR_CHAR))
+    }
+
+    /// Create a new simpath for finding executables, as `system_path_with_current_dir()` does,
+    /// but reading environment variables from `source` instead of the process environment (see
+    /// `new_with_env_source()`)
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
+    ///
+    /// fn main() {
+    ///     let mut env = HashMap::new();
+    ///     env.insert("PATH".to_string(), "/usr/bin".to_string());
+    ///     let search_path = Simpath::system_path_with_current_dir_and_env_source(Arc::new(env));
+    ///     assert!(search_path.contains("/usr/bin"));
+    /// }
+    /// ```
+    pub fn system_path_with_current_dir_and_env_source<E: EnvSource + 'static>(source: Arc<E>) -> Self {
+        let mut search_path = Self::empty("PATH", DEFAULT_SEPARATOR_CHAR);
+        search_path.set_env_source(source);
+        Self::system_path_with_current_dir_from(search_path)
+    }
+
+    // Add the directory of the application, the current directory and the directories in `PATH`
+    // to `search_path`, as `system_path_with_current_dir()` describes
+    fn system_path_with_current_dir_from(mut search_path: Self) -> Self {
+        if let Some(app_dir) = env::current_exe().ok().as_deref().and_then(Path::parent) {
+            search_path.push_directory(app_dir, EntrySource::Default);
+        }
+        if let Ok(current_dir) = env::current_dir() {
+            search_path.push_directory(current_dir, EntrySource::Default);
         }
 
-        #[cfg(feature = "urls")]
-        if let Ok(url_entry) = Url::parse(entry) {
-            return self.urls.contains(&url_entry);
+        #[cfg(target_family = "windows")]
+        {
+            let system_root = search_path.system_root();
+            search_path.push_directory(format!("{}\\System32", system_root), EntrySource::Default);
+            search_path.push_directory(format!("{}\\System", system_root), EntrySource::Default);
+            search_path.push_directory(system_root, EntrySource::Default);
         }
 
-        false
+        let implicit = search_path.len();
+        search_path.add_from_env_var("PATH");
+        if search_path.len() == implicit {
+            for dir in search_path.system_directories() {
+                search_path.push_directory(dir, EntrySource::Default);
+            }
+        }
+
+        search_path
     }
 
-    /// Add entries to the search path, by reading them from an environment variable.
-    ///
-    /// The environment variable should have a set of entries separated by the separator character.
-    /// By default the separator char is `":"` (on non-windows platforms) and `";"` (on windows)
-    /// but it can be modified after creation of search path.
+    /// Create a new simpath for finding executables from the `PATH` environment variable, as
+    /// `system_path()` does, but with empty entries interpreted as the current directory on unix
+    /// as POSIX specifies for `PATH`
     ///
-    /// The environment variable is parsed using the separator char set at the time this function
-    /// is called.
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
     ///
-    /// To be added each entry must exist and be readable.
+    /// fn main() {
+    ///     let search_path = Simpath::path();
+    ///     println!("{}", search_path);
+    /// }
+    /// ```
+    pub fn path() -> Self {
+        let mut search_path = Self::empty("PATH", DEFAULT_SEPARATOR_CHAR);
+        search_path.posix_empty_entries = cfg!(unix);
+        search_path.add_from_env_var("PATH");
+
+        if search_path.is_empty() {
+            for dir in search_path.system_directories() {
+                search_path.push_directory(dir, EntrySource::Default);
+            }
+        }
+
+        search_path
+    }
+
+    /// Create a new simpath for finding shared libraries as the dynamic loader does: the
+    /// directories in `LD_LIBRARY_PATH` (`DYLD_LIBRARY_PATH` on macOS) followed by the system
+    /// library directories. As for the loader, empty entries are the current directory. On
+    /// windows libraries are found on the `PATH`, so this is the same as `path()`.
     ///
     /// ```
     /// extern crate simpath;
     /// use simpath::Simpath;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("MyPathName");
-    ///     search_path.add_from_env_var("PATH");
-    ///     if search_path.contains(".") {
-    ///         println!("'.' was in your 'PATH' and has been added to the search path called '{}'",
-    ///                  search_path.name());
+    ///     let search_path = Simpath::ld_library_path();
+    ///     match search_path.find("libc.so.6") {
+    ///         Ok(found) => println!("libc was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
     ///     }
     /// }
     /// ```
-    pub fn add_from_env_var(&mut self, var_name: &str) {
-        if let Ok(var_string) = env::var(var_name) {
-            for part in var_string.split(self.separator) {
-                self.add(part);
+    pub fn ld_library_path() -> Self {
+        #[cfg(target_family = "windows")]
+        return Self::path();
+
+        #[cfg(not(target_family = "windows"))]
+        {
+            let var_name = if cfg!(target_os = "macos") { "DYLD_LIBRARY_PATH" } else { "LD_LIBRARY_PATH" };
+            let mut search_path = Self::empty(var_name, DEFAULT_SEPARATOR_CHAR);
+            search_path.posix_empty_entries = true;
+            search_path.add_from_env_var(var_name);
+
+            let defaults: &[&str] = if cfg!(target_os = "macos") {
+                &["/usr/local/lib", "/usr/lib"]
+            } else if cfg!(target_pointer_width = "64") {
+                &["/lib64", "/usr/lib64", "/lib", "/usr/lib"]
+            } else {
+                &["/lib", "/usr/lib"]
+            };
+            for dir in defaults {
+                search_path.push_directory(dir, EntrySource::Default);
             }
+
+            search_path
         }
     }
 
-    /// Add entries to the search path, by reading them from an environment variable.
+    /// Create a new simpath for finding `pkg-config` `.pc` files as `pkg-config` does: the
+    /// directories in `PKG_CONFIG_PATH` followed by those in `PKG_CONFIG_LIBDIR`, or if that is
+    /// not set the default `pkg-config` directories (`/usr/local/lib/pkgconfig`,
+    /// `/usr/local/share/pkgconfig`, `/usr/lib/pkgconfig` and `/usr/share/pkgconfig` on unix)
     ///
-    /// The environment variable should have a set of entries separated by the specified
-    /// separator character.
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
     ///
-    /// To be added each entry must exist and be readable.
+    /// fn main() {
+    ///     let search_path = Simpath::pkg_config_path();
+    ///     match search_path.find("zlib.pc") {
+    ///         Ok(found) => println!("zlib.pc was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn pkg_config_path() -> Self {
+        let mut search_path = Self::empty("PKG_CONFIG_PATH", DEFAULT_SEPARATOR_CHAR);
+        search_path.add_from_env_var("PKG_CONFIG_PATH");
+
+        if search_path.env_var("PKG_CONFIG_LIBDIR").is_some() {
+            search_path.add_from_env_var("PKG_CONFIG_LIBDIR");
+        } else {
+            #[cfg(not(target_family = "windows"))]
+            for dir in &["/usr/local/lib/pkgconfig", "/usr/local/share/pkgconfig", "/usr/lib/pkgconfig",
+                         "/usr/share/pkgconfig"] {
+                search_path.push_directory(dir, EntrySource::Default);
+            }
+        }
+
+        search_path
+    }
+
+    /// Create a new simpath for finding manual pages from the `MANPATH` environment variable, as
+    /// `man` does: an empty entry in it, including a leading or trailing separator, is where the
+    /// default manual page directories (`/usr/local/share/man`, `/usr/share/man` and
+    /// `/usr/local/man`) are searched, and if `MANPATH` is not set or is empty only they are
     ///
-    /// NOTE: The separator char is only used while parsing the specified environment variable and
-    /// *does not* modify the separator character in use in the Simpath after this function completes.
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::manpath();
+    ///     match search_path.find("man1/ls.1.gz") {
+    ///         Ok(found) => println!("The manual page for ls was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn manpath() -> Self {
+        let mut defaults = Self::empty("MANPATH", DEFAULT_SEPARATOR_CHAR);
+        for dir in &["/usr/local/share/man", "/usr/share/man", "/usr/local/man"] {
+            defaults.push_directory(dir, EntrySource::Default);
+        }
+
+        Self::new_with_defaults("MANPATH", &defaults)
+    }
+
+    /// Create a new simpath from the environment variable `var_name` using the convention of
+    /// `MANPATH`, where the entries of `defaults` (e.g. the system's default path) are searched
+    /// in place of an empty entry, such as a leading or trailing separator or `::`. So a leading
+    /// separator appends the entries of the variable to the defaults, and a trailing one adds
+    /// them before the defaults. If the variable is not set or is empty only the defaults are
+    /// searched. The entries added from `defaults` have `EntrySource::Default` as their source.
     ///
     /// ```
     /// extern crate simpath;
@@ -442,118 +1324,2792 @@ impl Simpath {
     /// use std::env;
     ///
     /// fn main() {
-    ///     let mut search_path = Simpath::new("MyPathName");
-    ///     env::set_var("TEST", "/,.,~");
-    ///     search_path.add_from_env_var_with_separator("TEST", ',');
-    ///     if search_path.contains(".") {
-    ///         println!("'.' was in your 'TEST' environment variable and has been added to the search path called '{}'",
-    ///                  search_path.name());
+    ///     env::set_var("INFOPATH", "/opt/info:");
+    ///     let mut defaults = Simpath::new("");
+    ///     defaults.add_directory("/usr/share/info");
+    ///     let search_path = Simpath::new_with_defaults("INFOPATH", &defaults);
+    ///     assert_eq!(search_path.directories().count(), 2);
+    /// }
+    /// ```
+    pub fn new_with_defaults(var_name: &str, defaults: &Simpath) -> Self {
+        let mut search_path = Self::empty(var_name, DEFAULT_SEPARATOR_CHAR);
+
+        match search_path.env_var(var_name).filter(|value| !value.is_empty()) {
+            Some(value) => {
+                for part in Self::split_entries(&value, search_path.separator) {
+                    if part.is_empty() {
+                        search_path.add_defaults(defaults);
+                    } else {
+                        search_path.add_entry(&part, EntrySource::EnvVar(var_name.to_string()));
+                    }
+                }
+            }
+            None => search_path.add_defaults(defaults),
+        }
+
+        search_path
+    }
+
+    // Add the entries of `defaults` to the end of the search path, as defaults
+    fn add_defaults(&mut self, defaults: &Simpath) {
+        for entry in defaults.entries.iter() {
+            match entry {
+                Entry::Directory(directory) => { self.push_directory(&directory.path, EntrySource::Default); }
+                #[cfg(feature = "urls")]
+                Entry::Url(url_entry) => self.insert_url(&url_entry.url, EntrySource::Default),
+            }
+        }
+    }
+
+    // Create a new simpath with no entries, without reading any environment variable
+    fn empty(name: &str, separator: char) -> Self {
+        Simpath {
+            separator,
+            name: name.to_string(),
+            posix_empty_entries: false,
+            access: Access::Any,
+            #[cfg(feature = "unicode")]
+            unicode_normalization: false,
+            canonicalize: Canonicalize::None,
+            share_directories: false,
+            limits: Limits::default(),
+            entries: Arc::default(),
+            comparables: Arc::default(),
+            wildcards: vec!(),
+            max_wildcard_depth: DEFAULT_MAX_WILDCARD_DEPTH,
+            strict_directories: false,
+            rejected: vec!(),
+            original: None,
+            preserve_original: false,
+            expand_variables: false,
+            variables: None,
+            env_source: None,
+            env_separators: HashMap::new(),
+            #[cfg(feature = "urls")]
+            probe_method: ProbeMethod::Head,
+            #[cfg(feature = "urls")]
+            accepted_statuses: vec!(),
+            #[cfg(feature = "urls")]
+            http: HttpOptions::default(),
+            #[cfg(feature = "urls")]
+            max_concurrent_probes: DEFAULT_MAX_CONCURRENT_PROBES,
+            #[cfg(feature = "urls")]
+            http_client: None,
+            #[cfg(feature = "urls")]
+            strict_urls: false,
+            error_handler: None,
+            entry_handler: None,
+            stats: None,
+            cancellation: vec!(),
+            progress_handler: None,
+            exclusions: Exclusions::default(),
+            #[cfg(unix)]
+            mode_filter: ModeFilter::default(),
+            bloom_filters: false,
+            directory_filters: None,
+            revalidate_index: false,
+        }
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user data files, according to the XDG Base Directory
+    /// specification. It contains `$XDG_DATA_HOME` (default `$HOME/.local/share`) followed by the
+    /// directories in `$XDG_DATA_DIRS` (default `/usr/local/share/:/usr/share/`).
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::xdg_data();
+    ///     match search_path.find("applications") {
+    ///         Ok(found) => println!("Applications found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
     ///     }
     /// }
     /// ```
-    pub fn add_from_env_var_with_separator(&mut self, var_name: &str, separator: char) {
-        if let Ok(var_string) = env::var(var_name) {
-            for part in var_string.split(separator) {
-                self.add_directory(part);
+    pub fn xdg_data() -> Self {
+        Self::xdg("XDG_DATA_HOME", ".local/share",
+                  Some(("XDG_DATA_DIRS", "/usr/local/share/:/usr/share/")), None)
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user data files, as `xdg_data()` does, but reading
+    /// environment variables from `source` instead of the process environment (see
+    /// `new_with_env_source()`)
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
+    ///
+    /// fn main() {
+    ///     let mut env = HashMap::new();
+    ///     env.insert("XDG_DATA_HOME".to_string(), "/data/home".to_string());
+    ///     let search_path = Simpath::xdg_data_with_env_source(Arc::new(env));
+    ///     assert!(search_path.contains("/data/home"));
+    /// }
+    /// ```
+    pub fn xdg_data_with_env_source<E: EnvSource + 'static>(source: Arc<E>) -> Self {
+        Self::xdg("XDG_DATA_HOME", ".local/share",
+                  Some(("XDG_DATA_DIRS", "/usr/local/share/:/usr/share/")), Some(EnvSourceHandle(source)))
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user configuration files, according to the XDG Base
+    /// Directory specification. It contains `$XDG_CONFIG_HOME` (default `$HOME/.config`) followed
+    /// by the directories in `$XDG_CONFIG_DIRS` (default `/etc/xdg`).
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::xdg_config();
+    ///     println!("{}", search_path);
+    /// }
+    /// ```
+    pub fn xdg_config() -> Self {
+        Self::xdg("XDG_CONFIG_HOME", ".config", Some(("XDG_CONFIG_DIRS", "/etc/xdg")), None)
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user configuration files, as `xdg_config()` does, but
+    /// reading environment variables from `source` instead of the process environment
+    pub fn xdg_config_with_env_source<E: EnvSource + 'static>(source: Arc<E>) -> Self {
+        Self::xdg("XDG_CONFIG_HOME", ".config", Some(("XDG_CONFIG_DIRS", "/etc/xdg")),
+                  Some(EnvSourceHandle(source)))
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user cache files, according to the XDG Base Directory
+    /// specification. It contains `$XDG_CACHE_HOME` (default `$HOME/.cache`).
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::xdg_cache();
+    ///     println!("{}", search_path);
+    /// }
+    /// ```
+    pub fn xdg_cache() -> Self {
+        Self::xdg("XDG_CACHE_HOME", ".cache", None, None)
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    /// Create a new simpath for finding user cache files, as `xdg_cache()` does, but reading
+    /// environment variables from `source` instead of the process environment
+    pub fn xdg_cache_with_env_source<E: EnvSource + 'static>(source: Arc<E>) -> Self {
+        Self::xdg("XDG_CACHE_HOME", ".cache", None, Some(EnvSourceHandle(source)))
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    // Create a simpath from an XDG "home" variable and optional "dirs" variable, using the defaults
+    // supplied when they are unset or empty. As per the specification, relative paths are ignored.
+    // Environment variables are read from `env_source`, if there is one.
+    fn xdg(home_var: &str, home_default: &str, dirs: Option<(&str, &str)>,
+           env_source: Option<EnvSourceHandle>) -> Self {
+        let name = dirs.map(|(dirs_var, _)| dirs_var).unwrap_or(home_var);
+        let mut search_path = Self::empty(name, ':');
+        search_path.env_source = env_source;
+
+        let var_or_default = |var_name: &str, default: Option<String>| {
+            match search_path.env_var_string(var_name).filter(|value| !value.is_empty()) {
+                Some(value) => Some((value, EntrySource::EnvVar(var_name.to_string()))),
+                None => default.map(|default| (default, EntrySource::Default)),
+            }
+        };
+
+        let home_default = search_path.env_var_string("HOME")
+            .map(|home| Path::new(&home).join(home_default).display().to_string());
+        let mut values = vec!(var_or_default(home_var, home_default));
+        if let Some((dirs_var, dirs_default)) = dirs {
+            values.push(var_or_default(dirs_var, Some(dirs_default.to_string())));
+        }
+
+        for (value, source) in values.into_iter().flatten() {
+            for dir in value.split(':').filter(|dir| Path::new(dir).is_absolute()) {
+                search_path.push_directory(dir, source.clone());
             }
         }
+
+        search_path
+    }
+
+    #[cfg(feature = "config")]
+    /// Create a new simpath from a configuration file that describes it, instead of from an
+    /// environment variable. The file can be in TOML (`.toml` extension) or JSON (`.json` extension)
+    /// format and all fields are optional. In TOML it looks like this:
+    ///
+    /// ```toml
+    /// name = "PLUGIN_PATH"
+    /// separator = ":"
+    /// directories = ["/usr/lib/app/plugins", "~/.app/plugins"]
+    /// urls = ["https://plugins.example.com/"] # requires the "urls" feature
+    /// resolve = ["plugins.example.com:443:10.0.0.5"] # "host:port:address", requires the "urls" feature
+    /// posix_empty_entries = false
+    /// required_access = "readable" # "any", "readable" or "executable"
+    /// unicode_normalization = false # requires the "unicode" feature
+    /// canonicalize = "absolute_only" # "full", "absolute_only" or "none"
+    /// ```
+    ///
+    /// An `Err` is returned if the file cannot be read or parsed.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     match Simpath::from_config("plugin_path.toml") {
+    ///         Ok(search_path) => println!("{}", search_path),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn from_config<P: AsRef<Path>>(config_path: P) -> Result<Self, Error> {
+        let config_path = config_path.as_ref();
+        let contents = fs::read_to_string(config_path)?;
+        let invalid = |e: &dyn fmt::Display| Error::new(ErrorKind::InvalidData,
+                                                        format!("Could not parse config file '{}': {}",
+                                                                config_path.display(), e));
+
+        let config: Config = match config_path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|e| invalid(&e))?,
+            Some("json") => serde_json::from_str(&contents).map_err(|e| invalid(&e))?,
+            _ => return Err(Error::new(ErrorKind::InvalidInput,
+                                       format!("Config file '{}' does not have a '.toml' or '.json' extension",
+                                               config_path.display())))
+        };
+
+        let mut search_path = Self::empty(&config.name, config.separator);
+        search_path.posix_empty_entries = config.posix_empty_entries;
+        search_path.access = config.required_access;
+        search_path.canonicalize = config.canonicalize;
+
+        #[cfg(feature = "unicode")]
+        {
+            search_path.unicode_normalization = config.unicode_normalization;
+        }
+        #[cfg(not(feature = "unicode"))]
+        if config.unicode_normalization {
+            return Err(invalid(&"'unicode_normalization' requires the \"unicode\" feature"));
+        }
+
+        let source = EntrySource::ConfigFile(config_path.to_path_buf());
+        for dir in &config.directories {
+            search_path.push_directory(dir, source.clone());
+        }
+
+        #[cfg(feature = "urls")]
+        for url in &config.urls {
+            search_path.push_url(&Url::parse(url).map_err(|e| invalid(&e))?, source.clone());
+        }
+        #[cfg(feature = "urls")]
+        for resolve in &config.resolve {
+            match resolve.splitn(3, ':').collect::<Vec<&str>>()[..] {
+                [host, port, address] if !host.is_empty() && !address.is_empty() => {
+                    let port = port.parse().map_err(|e| invalid(&format!("'{}': {}", resolve, e)))?;
+                    search_path.add_resolve(host, port, address);
+                }
+                _ => return Err(invalid(&format!("'{}' is not of the form \"host:port:address\"", resolve))),
+            }
+        }
+        #[cfg(not(feature = "urls"))]
+        if !config.urls.is_empty() || !config.resolve.is_empty() {
+            return Err(invalid(&"'urls' and 'resolve' require the \"urls\" feature"));
+        }
+
+        Ok(search_path)
+    }
+
+    /// Get the currently set separator character that is used when parsing entries from an environment
+    /// variable
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    /// Set the separator character used when parsing entries from an environment variable and
+    /// when writing the search path with `to_env_string()`. Entries already added are not changed.
+    pub fn set_separator(&mut self, separator: char) {
+        self.separator = separator;
+    }
+
+    /// Set whether empty entries parsed from an environment variable (e.g. `::` or a trailing `:`)
+    /// should be interpreted as the current directory (`.`), as specified by POSIX for `PATH`.
+    /// By default empty entries are ignored.
+    ///
+    /// This affects environment variables parsed after it is set, so to apply it to the initial
+    /// environment variable create the `Simpath` with an unused name and then add it.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     env::set_var("TEST", "/:");
+    ///     let mut search_path = Simpath::new("");
+    ///     search_path.set_posix_empty_entries(true);
+    ///     search_path.add_from_env_var("TEST");
+    ///     assert!(search_path.contains("."));
+    /// }
+    /// ```
+    pub fn set_posix_empty_entries(&mut self, posix_empty_entries: bool) {
+        self.posix_empty_entries = posix_empty_entries;
+    }
+
+    /// Get whether empty entries parsed from an environment variable are interpreted as the
+    /// current directory
+    pub fn posix_empty_entries(&self) -> bool {
+        self.posix_empty_entries
+    }
+
+    /// Set whether `to_env_string()` returns the exact original text of the environment variable
+    /// the search path was parsed from (including empty entries and trailing separators), as
+    /// long as the search path has not been changed since. This matters when a `Simpath` is
+    /// used to edit a user's `PATH` rather than to replace it. The default is not to preserve it.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     env::set_var("MyPreservedPath", "/usr/bin::/bin:");
+    ///     let mut search_path = Simpath::new_with_separator("MyPreservedPath", ':');
+    ///     assert_eq!(search_path.to_env_string(), "/usr/bin:/bin");
+    ///     search_path.set_preserve_original(true);
+    ///     assert_eq!(search_path.to_env_string(), "/usr/bin::/bin:");
+    /// }
+    /// ```
+    pub fn set_preserve_original(&mut self, preserve_original: bool) {
+        self.preserve_original = preserve_original;
+    }
+
+    /// Get whether `to_env_string()` returns the original text of the environment variable
+    pub fn preserve_original(&self) -> bool {
+        self.preserve_original
+    }
+
+    /// Get the exact original text of the environment variable the search path was parsed from,
+    /// if it was parsed from one that is valid unicode
+    pub fn original_value(&self) -> Option<&str> {
+        self.original.as_ref().map(|original| original.text.as_str())
+    }
+
+    // Check if the search path has not been changed since it was parsed from the original text
+    // of an environment variable
+    pub(crate) fn original_in_sync(&self) -> bool {
+        matches!(&self.original, Some(original) if original.parsed == self.joined_entries())
+    }
+
+    // Edit the entries (separated by the separator character) in the original text of the
+    // environment variable with `edit`, if the search path was in sync with it before being
+    // changed in the same way, so that it stays in sync
+    pub(crate) fn edit_original<F>(&mut self, in_sync: bool, edit: F) where F: FnOnce(&Self, &mut Vec<String>) {
+        if let (true, Some(original)) = (in_sync, &self.original) {
+            let mut segments: Vec<String> = match original.text.is_empty() {
+                true => vec!(),
+                false => original.text.split(self.separator).map(String::from).collect(),
+            };
+            edit(self, &mut segments);
+            let text = segments.join(&self.separator.to_string());
+            self.original = Some(OriginalValue { text, parsed: self.joined_entries() });
+        }
+    }
+
+    /// Set the `Access` the current user must have to a file or directory for it to be found.
+    /// Entries that match but are not accessible are skipped, and the search continues.
+    /// By default this is `Access::Any`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{Access, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_required_access(Access::Executable);
+    ///     match search_path.find("ls") {
+    ///         Ok(found) => println!("Executable 'ls' was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn set_required_access(&mut self, access: Access) {
+        self.access = access;
+    }
+
+    /// Get the `Access` the current user must have to an entry for it to be found
+    pub fn required_access(&self) -> Access {
+        self.access
+    }
+
+    #[cfg(unix)]
+    /// Set the `ModeFilter` that files must be accepted by, using their owner, group and mode
+    /// bits, to be found. Files that match but are rejected are skipped, and the search continues.
+    /// This applies to `which()` and `which_all()` as well as to finds. By default all files are
+    /// accepted.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{ModeFilter, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_mode_filter(ModeFilter::new().owned_by(0).exclude_setuid());
+    ///     match search_path.which("passwd") {
+    ///         Ok(found) => println!("'passwd' is '{}' and is not setuid", found.display()),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn set_mode_filter(&mut self, filter: ModeFilter) {
+        self.mode_filter = filter;
+    }
+
+    #[cfg(unix)]
+    /// Get the `ModeFilter` that files must be accepted by to be found
+    pub fn mode_filter(&self) -> &ModeFilter {
+        &self.mode_filter
+    }
+
+    #[cfg(unix)]
+    // Get the reason the file at `path` is rejected by the mode filter, if it is
+    fn mode_rejects(&self, path: &Path) -> Option<String> {
+        if self.mode_filter.accepts_all() {
+            return None;
+        }
+
+        match fs::metadata(path) {
+            Ok(metadata) => self.mode_filter.rejects(&metadata),
+            Err(e) => Some(format!("could not be checked: {}", e)),
+        }
+    }
+
+    /// Set a `handler` to be called as each directory is finished with during searches, walks
+    /// (including recursive ones) and while building the index in `freeze()`, with the path of the
+    /// directory and the number of files scanned so far, so that interactive tools can show
+    /// progress through long enumerations.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.on_progress(|dir, files| eprintln!("{} files scanned ({})", files, dir.display()));
+    ///     println!("{:?}", search_path.find_all("ls", FileType::File));
+    /// }
+    /// ```
+    pub fn on_progress<F>(&mut self, handler: F) where F: Fn(&Path, usize) + Send + Sync + 'static {
+        self.progress_handler = Some(ProgressHandler(Arc::new(handler)));
+    }
+
+    // Pass the progress of an enumeration to the progress handler, if one is set
+    fn notify_progress(&self, dir: &Path, files_scanned: usize) {
+        if let Some(handler) = &self.progress_handler {
+            (handler.0)(dir, files_scanned);
+        }
+    }
+
+    #[cfg(feature = "unicode")]
+    /// Set whether file names should be compared after Unicode NFC normalization when searching.
+    /// This avoids spurious misses on file systems (such as on macOS) that may store names in
+    /// a different normalization form (NFD) to that used in the name searched for.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.set_unicode_normalization(true);
+    ///     match search_path.find("caf\u{e9}") {
+    ///         Ok(found) => println!("Found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn set_unicode_normalization(&mut self, unicode_normalization: bool) {
+        self.unicode_normalization = unicode_normalization;
+    }
+
+    #[cfg(feature = "unicode")]
+    /// Get whether file names are compared after Unicode NFC normalization when searching
+    pub fn unicode_normalization(&self) -> bool {
+        self.unicode_normalization
+    }
+
+    /// Get the name associated with the simpath. Note that this could be an empty String, e.g. for
+    /// a `Simpath` created with `Simpath::default()`
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("Directories in Search Path: {:?}", search_path.name());
+    /// }
+    /// ```
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get an iterator over the directories that are included in the Search Path, in the order they
+    /// will be searched. Disabled entries are not included.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("Directories in Search Path: {:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn directories(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.search_dirs()
+    }
+
+    /// Get the `Entry`s of the Search Path, directories and Urls, in the order they will be searched, which
+    /// include where each entry came from. Disabled entries are included.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for entry in search_path.entries() {
+    ///         println!("'{}' came from {}", entry, entry.source());
+    ///     }
+    /// }
+    /// ```
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Get the `Entry` at `index` in `entries()`, or `None` if there is no entry there
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     if let Some(entry) = search_path.entry(0) {
+    ///         println!("The first entry in PATH is '{}'", entry);
+    ///     }
+    /// }
+    /// ```
+    pub fn entry(&self, index: usize) -> Option<&Entry> {
+        self.entries.get(index)
+    }
+
+    /// Get all the entries (directories and, if the "urls" feature is enabled, Urls) in the
+    /// Search Path along with where each one came from (an environment variable, a config file,
+    /// defaults or added programmatically)
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for (entry, source) in search_path.entry_sources() {
+    ///         println!("'{}' came from {}", entry, source);
+    ///     }
+    /// }
+    /// ```
+    pub fn entry_sources(&self) -> Vec<(String, &EntrySource)> {
+        self.entries.iter().map(|entry| (entry.to_string(), entry.source())).collect()
+    }
+
+    // Iterate over the directories to be searched (those enabled), in search order
+    fn search_dirs(&self) -> impl Iterator<Item = &Path> {
+        self.directory_entries().filter(|entry| entry.info.enabled && !NO_FILE_SYSTEM).map(|entry| &*entry.path)
+    }
+
+    /// Get the search path as a string in the form of an environment variable, with the
+    /// directories (and Urls) that are searched separated by the separator character, e.g.
+    /// "/usr/bin:/bin". This can be used to set an environment variable for a child process.
+    /// If the separator is the platform's separator the entries are joined using
+    /// `std::env::join_paths()`, as the OS would. If `set_preserve_original()` has been set and
+    /// the search path has not been changed since it was parsed, the original text of the
+    /// environment variable is returned instead.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new_with_separator("MyPath", ':');
+    ///     search_path.add_directory("/usr/bin");
+    ///     search_path.add_directory("/bin");
+    ///     assert_eq!(search_path.to_env_string(), "/usr/bin:/bin");
+    /// }
+    /// ```
+    pub fn to_env_string(&self) -> String {
+        let joined = self.joined_entries();
+        match &self.original {
+            Some(original) if self.preserve_original && original.parsed == joined => original.text.clone(),
+            _ => joined,
+        }
+    }
+
+    // Join the entries that are searched with the separator character
+    fn joined_entries(&self) -> String {
+        let entries: Vec<String> = self.search_entries().iter().map(|entry| entry.location()).collect();
+
+        // Join them as the OS does if possible, so entries are quoted if needed (on Windows)
+        if self.separator == DEFAULT_SEPARATOR_CHAR {
+            if let Ok(joined) = env::join_paths(&entries) {
+                return joined.to_string_lossy().into_owned();
+            }
+        }
+
+        entries.join(&self.separator.to_string())
+    }
+
+    /// Set the environment variable with the name of this search path on `command`, to the
+    /// search path as returned by `to_env_string()`, so that the child process it spawns
+    /// uses it. `command` is returned so more calls can be chained on it.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::process::Command;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new_with_separator("MyPath", ':');
+    ///     search_path.add_directory("/usr/bin");
+    ///     let mut command = Command::new("ls");
+    ///     search_path.set_command_env(&mut command);
+    ///     let (name, value) = command.get_envs().next().unwrap();
+    ///     assert_eq!(name, "MyPath");
+    ///     assert_eq!(value.unwrap(), "/usr/bin");
+    /// }
+    /// ```
+    pub fn set_command_env<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        command.env(&self.name, self.to_env_string())
+    }
+
+    /// Create a new simpath from the environment variable called `var_name` that `command` will
+    /// pass to the child process it spawns, with entries separated by `separator`. If the
+    /// variable has not been set or removed on `command` it is read from the process environment,
+    /// as the child process would inherit it. This is the inverse of `set_command_env()`.
+    ///
+    /// NOTE: If `env_clear()` has been called on `command`, variables that have not been set on it
+    /// since will still be read from the process environment, as `Command` does not expose that.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::process::Command;
+    ///
+    /// fn main() {
+    ///     let mut command = Command::new("ls");
+    ///     command.env("MyPath", ".");
+    ///     let search_path = Simpath::from_command(&command, "MyPath", ':');
+    ///     assert_eq!(search_path.len(), 1);
+    /// }
+    /// ```
+    pub fn from_command(command: &Command, var_name: &str, separator: char) -> Self {
+        let mut search_path = Self::empty(var_name, separator);
+
+        let configured = command.get_envs()
+            .find(|(name, _)| Self::same_var_name(name, var_name));
+        let value = match configured {
+            Some((_, value)) => value.map(OsStr::to_os_string),
+            None => search_path.env_var(var_name),
+        };
+
+        if let Some(value) = value {
+            search_path.add_env_value(var_name, &value, search_path.separator);
+        }
+
+        search_path
+    }
+
+    // Environment variable names are case-insensitive on Windows
+    fn same_var_name(name: &OsStr, var_name: &str) -> bool {
+        if cfg!(windows) {
+            name.to_string_lossy().eq_ignore_ascii_case(var_name)
+        } else {
+            name == var_name
+        }
+    }
+
+    /// Create a `Chain` that searches this `Simpath` and then, if no match is found, `fallback`.
+    /// More fallbacks can be added to the `Chain` using its `with_fallback()` method.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let chain = Simpath::new("PROJECT_PATH").with_fallback(Simpath::new("PATH"));
+    ///     match chain.find("ls") {
+    ///         Ok((level, found)) => println!("Found {:?} in '{}'", found, chain.levels()[level].name()),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn with_fallback(self, fallback: Simpath) -> Chain {
+        Chain { levels: vec!(self, fallback) }
+    }
+
+    /// Walk all the files in all the directories of the `Simpath`, in search order.
+    ///
+    /// The `Walk` returned is an `Iterator` over the paths of the files found. By default only
+    /// the files directly in each directory are returned, but it can be made to descend into
+    /// sub-directories using `recursive(true)` and to skip files that are shadowed by a file
+    /// of the same name in an earlier directory using `unique(true)`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for file in search_path.walk().unique(true) {
+    ///         match file {
+    ///             Ok(path) => println!("{}", path.display()),
+    ///             Err(e) => println!("{}", e),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            simpath: self,
+            dir_exclusions: DirExclusions::default(),
+            roots: Box::new(self.search_dirs()),
+            root: PathBuf::new(),
+            pending: vec!(),
+            current: None,
+            recursive: false,
+            unique: false,
+            seen: HashSet::new(),
+            dir: PathBuf::new(),
+            error_handler: self.error_handler.clone(),
+            progress_handler: self.progress_handler.clone(),
+            files_scanned: 0,
+        }
+    }
+
+    /// List all the files in the directories of the `Simpath` that have the extension
+    /// `extension` (without the leading `.`), in search order.
+    ///
+    /// If `unique` is true then files shadowed by a file of the same name in an earlier directory
+    /// are not included.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PLUGIN_PATH");
+    ///     match search_path.list_with_extension("wasm", true) {
+    ///         Ok(plugins) => println!("Plugins found: {:?}", plugins),
+    ///         Err(e) => println!("{}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn list_with_extension(&self, extension: &str, unique: bool) -> Result<Vec<PathBuf>, Error> {
+        let mut found = vec!();
+
+        for file in self.walk().unique(unique) {
+            let path = file?;
+            if path.extension().map(|ext| ext == extension).unwrap_or(false) {
+                found.push(path);
+            }
+        }
+
+        Ok(found)
+    }
+
+    #[cfg(feature = "semver")]
+    /// Find the highest version of a versioned file (e.g. `tool-1.2.3`) in the `Simpath` that
+    /// satisfies the `VersionReq` supplied.
+    ///
+    /// A file is considered a candidate if its name is `name` followed by `-` and a version that
+    /// can be parsed as a semantic version, optionally followed by an extension
+    /// (e.g. `tool-1.2.3.exe`).
+    ///
+    /// If a matching version is found then `Ok` is returned with the highest matching version
+    /// and the full list of candidates found (matching or not), sorted from highest to lowest
+    /// version. If no candidate satisfies `version_req` then `Err` is returned. Files that are
+    /// excluded, or that the current user does not have the required access to, are not
+    /// candidates, and errors reading directories are passed to the handler set with
+    /// `on_error()`, if there is one, as `find()` does.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate semver;
+    /// use simpath::Simpath;
+    /// use semver::VersionReq;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_versioned("my-tool", &VersionReq::parse(">=1.0").unwrap()) {
+    ///         Ok((best, _candidates)) => println!("Found version {} at {:?}", best.version, best.path),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_versioned(&self, name: &str, version_req: &VersionReq)
+                          -> Result<(VersionedFile, Vec<VersionedFile>), Error> {
+        let prefix = format!("{}-", name);
+        let mut candidates = vec!();
+        // The first candidate that could not be accessed, reported if no other matches
+        let mut denied = None;
+
+        for search_dir in self.search_dirs() {
+            self.check_cancelled(name)?;
+            let dir_exclusions = self.dir_exclusions(search_dir);
+            let entries = match fs::read_dir(search_dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.handle_error(search_dir, e)?;
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let file = match entry {
+                    Ok(file) => file,
+                    Err(e) => {
+                        self.handle_error(search_dir, e)?;
+                        continue;
+                    }
+                };
+                if self.excluded(&dir_exclusions, &file) {
+                    continue;
+                }
+                if let Some(version) = file.file_name().to_str()
+                    .and_then(|filename| filename.strip_prefix(&prefix)).and_then(Self::parse_version) {
+                    match self.check_access(&file.path()) {
+                        Ok(()) => candidates.push(VersionedFile { path: file.path(), version }),
+                        Err(e) => if version_req.matches(&version) {
+                            denied.get_or_insert(e);
+                        },
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| b.version.cmp(&a.version));
+
+        match candidates.iter().position(|candidate| version_req.matches(&candidate.version)) {
+            Some(index) => Ok((candidates[index].clone(), candidates)),
+            None => Err(denied.unwrap_or_else(|| Error::new(ErrorKind::NotFound,
+                format!("Could not find a version of '{}' matching '{}' in {}",
+                        name, version_req, PathName(&self.name)))))
+        }
+    }
+
+    #[cfg(feature = "semver")]
+    // Parse a version from the end of a file name, ignoring any trailing extension
+    fn parse_version(version_string: &str) -> Option<Version> {
+        Version::parse(version_string).ok().or_else(|| {
+            version_string.rsplit_once('.')
+                .and_then(|(without_extension, _)| Version::parse(without_extension).ok())
+        })
+    }
+
+    /// Add an to the search path.
+    ///
+    /// if "urls" feature is enabled:
+    ///     If it parses as as web Url it will be added to the list of
+    ///     base Urls to search, otherwise it will be added to the list of directories to search.
+    /// if "urls" feature is *not* enabled:
+    ///     It is assumed to be a directory and added using `add_directory()`, unless it is a web
+    ///     Url (`http://` or `https://`), which is not added and is reported to the handler set
+    ///     with `on_error()` as needing the "urls" feature
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.add(".");
+    ///
+    /// #[cfg(feature = "urls")]
+    ///     search_path.add("http://ibm.com");
+    ///
+    ///     println!("{}", search_path);
+    /// }
+    /// ```
+    pub fn add<S: AsRef<OsStr>>(&mut self, entry: S) {
+        self.add_entry(entry.as_ref(), EntrySource::Programmatic);
+    }
+
+    // Add an entry, which may be a Url, recording where it came from
+    fn add_entry(&mut self, entry: &OsStr, source: EntrySource) {
+        let expanded = match entry.to_str() {
+            Some(value) if self.expand_variables => Some(OsString::from(self.expand(value))),
+            _ => None,
+        };
+        let entry = expanded.as_deref().unwrap_or(entry);
+        if let Err(error) = self.check_entry_length(entry) {
+            self.notify_error(Path::new(entry), &error);
+            self.reject_entry(entry.to_string_lossy().into_owned(), error.to_string(), source);
+            return;
+        }
+
+        #[cfg(not(feature = "urls"))]
+        if Self::is_web_url(entry) {
+            let error = Error::new(ErrorKind::Unsupported,
+                                   format!("'{}' in {} is a Url, which can only be added with the \"urls\" feature",
+                                           entry.to_string_lossy(), PathName(&self.name)));
+            self.notify_error(Path::new(entry), &error);
+            self.reject_entry(entry.to_string_lossy().into_owned(), error.to_string(), source);
+        } else {
+            self.push_parsed_directory(entry, source);
+        }
+
+        #[cfg(feature = "urls")]
+        match entry.to_str().map(Url::parse) {
+            Some(Ok(url)) => {
+                match url.scheme() {
+                    #[cfg(feature = "urls")]
+                    "http" | "https" => self.push_url(&url, source),
+                    "file" => self.push_parsed_directory(url.path(), source),
+                    _ => self.push_parsed_directory(entry, source)
+                }
+            }
+            Some(Err(error)) if Self::is_web_url(entry) => {
+                self.reject_entry(entry.to_string_lossy().into_owned(), format!("It is not a valid Url: {}", error),
+                                  source);
+            }
+            _ => self.push_parsed_directory(entry, source) /* default to being a directory path */
+        }
+    }
+
+    // Record that an entry was rejected, and not added, and why
+    pub(crate) fn reject_entry(&mut self, entry: String, reason: String, source: EntrySource) {
+        trace_event!(debug, search_path = %self.name, entry = %entry, reason = %reason, "rejected entry");
+        self.rejected.push((SkippedEntry { entry, reason }, source));
+    }
+
+    /// Get the entries that were rejected, and not added, while parsing environment variables
+    /// and config files or adding entries with `add()`, along with why each one was rejected and
+    /// where it came from. Entries are rejected if they are longer than the limit set with
+    /// `set_limits()` or would exceed the number of entries allowed, if they are Urls that are
+    /// not valid (or need the "urls" feature), if strict mode has been set with
+    /// `set_strict_directories()` and they do not exist, are not directories or cannot be
+    /// read, and with the "urls" feature if strict mode has been set with `set_strict_urls()`
+    /// and they cannot be reached. Empty entries and duplicates are ignored, not rejected.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.set_strict_directories(true);
+    ///     search_path.add("/no/such/directory");
+    ///     for (rejected, source) in search_path.rejected_entries() {
+    ///         println!("'{}' from {} was rejected: {}", rejected.entry, source, rejected.reason);
+    ///     }
+    /// }
+    /// ```
+    pub fn rejected_entries(&self) -> Vec<(&SkippedEntry, &EntrySource)> {
+        self.rejected.iter().map(|(rejected, source)| (rejected, source)).collect()
+    }
+
+    // Check if an entry is a web Url, which can only be added with the "urls" feature
+    fn is_web_url(entry: &OsStr) -> bool {
+        entry.to_str().and_then(|entry| entry.split_once("://"))
+            .map(|(scheme, _)| scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+            .unwrap_or(false)
+    }
+
+    /// Set whether references to variables in entries parsed from environment variables or added
+    /// using `add()` are expanded before they are added, so that values such as
+    /// `$JAVA_HOME/bin:$HOME/.cargo/bin` can be used. References can be written as `$VAR`,
+    /// `${VAR}` or `%VAR%`. They are expanded using the environment (see `set_env_source()`),
+    /// unless variables have been supplied using `set_variables()`. References to variables that are not defined are
+    /// left as they are. The default is not to expand them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.set_expand_variables(true);
+    ///     search_path.add("${HOME}/.cargo/bin");
+    ///     println!("{:?}", search_path.directories().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn set_expand_variables(&mut self, expand_variables: bool) {
+        self.expand_variables = expand_variables;
+    }
+
+    /// Get whether references to variables in entries are expanded before they are added
+    pub fn expand_variables(&self) -> bool {
+        self.expand_variables
+    }
+
+    /// Supply the variables used to expand references to variables in entries (see
+    /// `set_expand_variables()`), instead of using the environment.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.set_expand_variables(true);
+    ///     search_path.set_variables([("JAVA_HOME", "/opt/jdk")]);
+    ///     search_path.add("$JAVA_HOME/bin");
+    ///     assert_eq!(search_path.to_env_string(), "/opt/jdk/bin");
+    /// }
+    /// ```
+    pub fn set_variables<I, K, V>(&mut self, variables: I)
+        where I: IntoIterator<Item = (K, V)>, K: Into<String>, V: Into<String> {
+        self.variables = Some(variables.into_iter().map(|(name, value)| (name.into(), value.into())).collect());
+    }
+
+    // Get the value of the variable called `name`, from those supplied or the environment
+    fn variable(&self, name: &str) -> Option<String> {
+        match &self.variables {
+            Some(variables) => variables.get(name).cloned(),
+            None => self.env_var(name).and_then(|value| value.into_string().ok()),
+        }
+    }
+
+    // Expand the references to variables (`$VAR`, `${VAR}` or `%VAR%`) in `value` that are defined
+    fn expand(&self, value: &str) -> String {
+        let is_name = |c: char| c.is_alphanumeric() || c == '_';
+        let mut expanded = String::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find(['$', '%']) {
+            expanded.push_str(&rest[..start]);
+            let marker = &rest[start..start + 1];
+            let after = &rest[start + 1..];
+            // The name referenced and the length of the reference after the marker
+            let reference = if marker == "%" {
+                after.find('%').map(|end| (&after[..end], end + 1))
+            } else if let Some(braced) = after.strip_prefix('{') {
+                braced.find('}').map(|end| (&braced[..end], end + 2))
+            } else {
+                let end = after.find(|c: char| !is_name(c)).unwrap_or(after.len());
+                Some((&after[..end], end))
+            };
+
+            match reference.filter(|(name, _)| !name.is_empty() && name.chars().all(is_name))
+                .and_then(|(name, length)| self.variable(name).map(|value| (value, length))) {
+                Some((value, length)) => {
+                    expanded.push_str(&value);
+                    rest = &after[length..];
+                }
+                None => {
+                    expanded.push_str(marker);
+                    rest = after;
+                }
+            }
+        }
+
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// Check if a search path contains an entry.
+    ///
+    /// Directories are compared in the form given by the `Canonicalize` policy set with
+    /// `set_canonicalize()`, so with `Canonicalize::Full` different ways of referring to the same
+    /// directory are considered equal. On windows the comparison also ignores the difference
+    /// between `/` and `\` separators and is case-insensitive.
+    /// Use `contains_exact()` to compare entries literally.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("FakeEnvVar");
+    ///     if search_path.contains(".") {
+    ///         println!("Well that's a surprise!");
+    ///     }
+    /// }
+    /// ```
+    pub fn contains<P: AsRef<Path>>(&self, entry: P) -> bool {
+        let comparable = self.comparable(entry.as_ref());
+        if self.search_dirs().any(|dir| self.comparable(dir) == comparable) {
+            return true;
+        }
+
+        #[cfg(feature = "urls")]
+        if let Some(Ok(url_entry)) = entry.as_ref().to_str().map(Url::parse) {
+            return self.has_url(&url_entry);
+        }
+
+        false
+    }
+
+    /// Check if a search path contains an entry, comparing it literally with the entries
+    /// as they were added
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("FakeEnvVar");
+    ///     search_path.add_directory(".");
+    ///     assert!(search_path.contains_exact("."));
+    ///     assert!(!search_path.contains_exact("./"));
+    /// }
+    /// ```
+    pub fn contains_exact<P: AsRef<Path>>(&self, entry: P) -> bool {
+        if self.search_dirs().any(|dir| dir.as_os_str() == entry.as_ref().as_os_str()) {
+            return true;
+        }
+
+        #[cfg(feature = "urls")]
+        if let Some(Ok(url_entry)) = entry.as_ref().to_str().map(Url::parse) {
+            return self.has_url(&url_entry);
+        }
+
+        false
+    }
+
+    /// Add entries to the search path, by reading them from an environment variable.
+    ///
+    /// The environment variable should have a set of entries separated by the separator character.
+    /// By default the separator char is `":"` (on non-windows platforms) and `";"` (on windows)
+    /// but it can be modified after creation of search path.
+    ///
+    /// The environment variable is parsed using the separator char set at the time this function
+    /// is called. If it is the platform's separator the variable is split using
+    /// `std::env::split_paths()`, exactly as the OS does (including quoted entries on Windows).
+    ///
+    /// To be added each entry must exist and be readable.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPathName");
+    ///     search_path.add_from_env_var("PATH");
+    ///     if search_path.contains(".") {
+    ///         println!("'.' was in your 'PATH' and has been added to the search path called '{}'",
+    ///                  search_path.name());
+    ///     }
+    /// }
+    /// ```
+    pub fn add_from_env_var(&mut self, var_name: &str) {
+        self.add_env_var(var_name, self.separator);
+    }
+
+    // Add the entries of the environment variable `var_name`, separated by `separator`, recording
+    // its original text if it is the first one read and uses the separator of the search path
+    fn add_env_var(&mut self, var_name: &str, separator: char) {
+        if let Some(value) = self.env_var(var_name) {
+            let first = separator == self.separator && self.is_empty() && self.original.is_none();
+            self.add_env_value(var_name, &value, separator);
+            if first {
+                self.original = value.into_string().ok()
+                    .map(|text| OriginalValue { text, parsed: self.joined_entries() });
+            }
+        }
+    }
+
+    // Add the entries in `value`, the value of the environment variable called `var_name`,
+    // separated by `separator`
+    fn add_env_value(&mut self, var_name: &str, value: &OsStr, separator: char) {
+        for part in Self::split_entries(value, separator) {
+            self.add_parsed_entry(&part, var_name);
+        }
+    }
+
+    // Add an entry parsed from the environment variable `var_name`, passing what was decided
+    // about it to the entry handler, if one is set
+    fn add_parsed_entry(&mut self, part: &OsStr, var_name: &str) {
+        let source = EntrySource::EnvVar(var_name.to_string());
+        let entry = match self.parsed_entry(part) {
+            Some(entry) => entry,
+            None => return self.notify_entry(part, &source, &EntryDecision::Ignored("It is empty".into())),
+        };
+        if self.entry_handler.is_none() {
+            return self.add_entry(entry, source);
+        }
+
+        let (rejected, len) = (self.rejected.len(), self.len());
+        self.add_entry(entry, source.clone());
+        let decision = if self.rejected.len() > rejected {
+            EntryDecision::Rejected(self.rejected[self.rejected.len() - 1].0.reason.clone())
+        } else if self.len() > len {
+            EntryDecision::Added
+        } else {
+            EntryDecision::Ignored("It adds no entries that are not already in the search path".into())
+        };
+        self.notify_entry(entry, &source, &decision);
+    }
+
+    /// Add entries to the search path, by reading them from an environment variable.
+    ///
+    /// The environment variable should have a set of entries separated by the specified
+    /// separator character.
+    ///
+    /// Each entry is added as `add()` adds it, so it can be a directory or a Url.
+    ///
+    /// NOTE: The separator char is only used while parsing the specified environment variable and
+    /// *does not* modify the separator character in use in the Simpath after this function completes.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPathName");
+    ///     env::set_var("TEST", "/,.,~");
+    ///     search_path.add_from_env_var_with_separator("TEST", ',');
+    ///     if search_path.contains(".") {
+    ///         println!("'.' was in your 'TEST' environment variable and has been added to the search path called '{}'",
+    ///                  search_path.name());
+    ///     }
+    /// }
+    /// ```
+    pub fn add_from_env_var_with_separator(&mut self, var_name: &str, separator: char) {
+        if separator != self.separator {
+            self.env_separators.insert(var_name.to_string(), separator);
+        }
+        self.add_env_var(var_name, separator);
+    }
+
+    // Create a `Simpath` called `name` with the entries in `value`, separated by `separator`
+    fn parse(name: &str, value: &str, separator: char) -> Self {
+        let mut search_path = Simpath::empty(name, separator);
+        for part in Self::split_entries(OsStr::new(value), separator) {
+            if let Some(entry) = search_path.parsed_entry(&part) {
+                search_path.add_entry(entry, EntrySource::Programmatic);
+            }
+        }
+        search_path
+    }
+
+    // Split a value into its entries. If `separator` is the platform's separator then it is split
+    // using `env::split_paths()`, so entries are split exactly as the OS does, otherwise the value
+    // is split on `separator`, keeping entries that are not valid unicode unchanged.
+    fn split_entries(value: &OsStr, separator: char) -> Vec<OsString> {
+        if separator == DEFAULT_SEPARATOR_CHAR {
+            return env::split_paths(value).map(PathBuf::into_os_string).collect();
+        }
+
+        let mut buffer = [0; 4];
+        let separator = separator.encode_utf8(&mut buffer).as_bytes();
+        let bytes = value.as_encoded_bytes();
+        let mut entries = vec!();
+        let mut start = 0;
+        let mut index = 0;
+        while index + separator.len() <= bytes.len() {
+            if bytes[index..].starts_with(separator) {
+                entries.push(&bytes[start..index]);
+                index += separator.len();
+                start = index;
+            } else {
+                index += 1;
+            }
+        }
+        entries.push(&bytes[start..]);
+
+        entries.into_iter()
+            // SAFETY: each entry was split from the bytes of an `OsStr` immediately before or
+            // after a valid UTF-8 separator, so it is itself a valid encoding of an `OsStr`
+            .map(|entry| unsafe { OsStr::from_encoded_bytes_unchecked(entry) }.to_os_string())
+            .collect()
+    }
+
+    // Re-read the entries that came from environment variables, keeping the entries that were
+    // added in other ways after them. If no entries came from an environment variable then the
+    // variable with the name of the `Simpath` is read.
+    fn reload_env_entries(&mut self) {
+        let mut var_names: Vec<String> = vec!();
+        for source in self.entries.iter().map(Entry::source) {
+            if let EntrySource::EnvVar(var_name) = source {
+                if !var_names.contains(var_name) {
+                    var_names.push(var_name.clone());
+                }
+            }
+        }
+        if var_names.is_empty() && !self.name.is_empty() {
+            var_names.push(self.name.clone());
+        }
+
+        self.wildcards.retain(|wildcard| !matches!(wildcard.source, EntrySource::EnvVar(_)));
+        self.rejected.retain(|(_, source)| !matches!(source, EntrySource::EnvVar(_)));
+        self.original = None;
+        let (_, others): (Vec<Entry>, Vec<Entry>) = self.take_entries().into_iter()
+            .partition(|entry| matches!(entry.source(), EntrySource::EnvVar(_)));
+
+        for var_name in var_names {
+            let separator = self.env_separators.get(&var_name).copied().unwrap_or(self.separator);
+            self.add_env_var(&var_name, separator);
+        }
+
+        // The other entries are now after those from environment variables in the search order
+        for entry in others {
+            let duplicate = match &entry {
+                Entry::Directory(directory) => self.has_directory(&directory.path),
+                #[cfg(feature = "urls")]
+                Entry::Url(url_entry) => self.has_url(&url_entry.url),
+            };
+            if !duplicate {
+                self.append_entry(entry);
+            }
+        }
+    }
+
+    // Interpret an entry parsed from an environment variable, handling empty entries
+    fn parsed_entry<'a>(&self, part: &'a OsStr) -> Option<&'a OsStr> {
+        if !part.is_empty() {
+            Some(part)
+        } else if self.posix_empty_entries {
+            Some(OsStr::new("."))
+        } else {
+            trace_event!(debug, search_path = %self.name, "ignored empty entry");
+            None
+        }
+    }
+
+    /// Validate that all the directories in the search path exist and can be read, returning
+    /// a `PathError` for each one that is not valid
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for error in search_path.validate() {
+    ///         println!("Invalid entry in PATH: {}", error);
+    ///     }
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<PathError> {
+        self.search_dirs().filter_map(Self::validate_directory).collect()
+    }
+
+    /// Check a single entry of the search path, without validating all of them as `validate()`
+    /// does. `index` is the position of the entry in `entries()`. A directory is checked as
+    /// `validate()` does, and a Url as `check_url()` does.
+    ///
+    /// A `NotFound` error is returned if there is no entry at `index` or the directory does not
+    /// exist, and a `PermissionDenied` error if the directory cannot be read.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("MyPath");
+    ///     search_path.add_directory("/no-way-this-exists");
+    ///     assert!(search_path.check_entry(0).is_err());
+    /// }
+    /// ```
+    pub fn check_entry(&self, index: usize) -> Result<(), Error> {
+        match self.entries.get(index) {
+            Some(Entry::Directory(directory)) => match Self::validate_directory(&directory.path) {
+                None => Ok(()),
+                Some(e @ PathError::DoesNotExist(_)) => Err(Error::new(ErrorKind::NotFound, e.to_string())),
+                Some(e @ PathError::CannotRead(_)) => Err(Error::new(ErrorKind::PermissionDenied, e.to_string())),
+            },
+            #[cfg(feature = "urls")]
+            Some(Entry::Url(url_entry)) => self.check_url(&url_entry.url).map(|_| ()),
+            None => Err(Error::new(ErrorKind::NotFound,
+                                   format!("There is no entry {} in {}", index, PathName(&self.name)))),
+        }
+    }
+
+    /// Validate the directories in the search path, as `validate()` does, returning a
+    /// `ValidateReport` listing the valid and invalid directories
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let report = search_path.validate_report();
+    ///     println!("Invalid directories: {:?}", report.invalid);
+    /// }
+    /// ```
+    pub fn validate_report(&self) -> ValidateReport {
+        let mut report = ValidateReport {
+            search_path: self.name.clone(),
+            ..Default::default()
+        };
+
+        for dir in self.search_dirs() {
+            let entry = dir.display().to_string();
+            if !dir.exists() {
+                report.invalid.push(SkippedEntry { entry, reason: "It does not exist".into() });
+            } else if let Err(e) = fs::read_dir(dir) {
+                report.invalid.push(SkippedEntry { entry, reason: e.to_string() });
+            } else {
+                report.valid.push(entry);
+            }
+        }
+
+        report
+    }
+
+    /// Find the files in the search path that are shadowed by a file of the same name in an
+    /// earlier directory, returning a `ShadowReport` listing them
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for shadowed in search_path.shadow_report().shadowed {
+    ///         println!("'{}' shadows {:?}", shadowed.found, shadowed.shadowed);
+    ///     }
+    /// }
+    /// ```
+    pub fn shadow_report(&self) -> ShadowReport {
+        let mut report = ShadowReport {
+            search_path: self.name.clone(),
+            ..Default::default()
+        };
+        let mut files: Vec<Shadowed> = vec!();
+        let mut index_by_name: HashMap<String, usize> = HashMap::new();
+
+        for dir in self.search_dirs() {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    report.skipped.push(SkippedEntry { entry: dir.display().to_string(), reason: e.to_string() });
+                    continue;
+                }
+            };
+
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let location = entry.path().display().to_string();
+                match index_by_name.get(&name) {
+                    Some(&index) => files[index].shadowed.push(location),
+                    None => {
+                        index_by_name.insert(name.clone(), files.len());
+                        files.push(Shadowed { name, found: location, shadowed: vec!() });
+                    }
+                }
+            }
+        }
+
+        report.shadowed = files.into_iter().filter(|file| !file.shadowed.is_empty()).collect();
+        report
+    }
+
+    #[cfg(feature = "term")]
+    /// Produce a report of the validity of the entries in the search path, for showing in a
+    /// terminal, with one entry per line and the paths aligned. Entries that are valid are shown
+    /// in green, ones that do not exist in red and ones that cannot be read in yellow, and
+    /// entries that contain files that are shadowed by files in earlier entries are marked.
+    ///
+    /// The report is colored using ANSI escape sequences, unless the `NO_COLOR` environment
+    /// variable is set (in the `EnvSource` of the `Simpath`, if it has one). Use `write_report()`
+    /// to write it to a terminal that may not support them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     print!("{}", search_path.report());
+    /// }
+    /// ```
+    pub fn report(&self) -> String {
+        if self.env_var("NO_COLOR").is_some() {
+            let mut out = termcolor::NoColor::new(Vec::new());
+            let _ = self.write_report(&mut out);
+            String::from_utf8_lossy(&out.into_inner()).to_string()
+        } else {
+            let mut out = termcolor::Ansi::new(Vec::new());
+            let _ = self.write_report(&mut out);
+            String::from_utf8_lossy(&out.into_inner()).to_string()
+        }
+    }
+
+    #[cfg(feature = "term")]
+    /// Write the report produced by `report()` to `out`, which decides if and how it is colored,
+    /// e.g. a `termcolor::StandardStream` that colors it only if it is a terminal.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate termcolor;
+    /// use simpath::Simpath;
+    /// use termcolor::{ColorChoice, StandardStream};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    ///     search_path.write_report(&mut stdout).unwrap();
+    /// }
+    /// ```
+    pub fn write_report<W: WriteColor>(&self, out: &mut W) -> Result<(), Error> {
+        let mut shadowed: HashMap<String, usize> = HashMap::new();
+        for file in self.shadow_report().shadowed {
+            for location in file.shadowed {
+                if let Some(dir) = Path::new(&location).parent() {
+                    *shadowed.entry(dir.display().to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        if self.name.is_empty() {
+            writeln!(out, "Search Path:")?;
+        } else {
+            writeln!(out, "Search Path '{}':", self.name)?;
+        }
+
+        let width = self.entries.iter().map(|entry| entry.to_string().chars().count()).max().unwrap_or(0);
+        for (index, entry) in self.entries.iter().enumerate() {
+            let (marker, color, status) = match entry.path().map(Self::validate_directory) {
+                _ if !entry.is_enabled() => ("-", None, "disabled"),
+                Some(Some(PathError::DoesNotExist(_))) => ("✗", Some(Color::Red), "does not exist"),
+                Some(Some(PathError::CannotRead(_))) => ("!", Some(Color::Yellow), "cannot be read"),
+                Some(None) => ("✓", Some(Color::Green), ""),
+                None => (" ", None, ""),
+            };
+            let path = entry.to_string();
+            let shadowing = shadowed.get(&path).filter(|_| entry.is_enabled());
+
+            out.set_color(ColorSpec::new().set_fg(color))?;
+            write!(out, "  {} {:>2}  {}", marker, index, path)?;
+            if !status.is_empty() || shadowing.is_some() {
+                write!(out, "{:pad$}  {}", "", status, pad = width - path.chars().count())?;
+            }
+            out.reset()?;
+            if let Some(count) = shadowing {
+                out.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+                write!(out, "{}{} shadowed by earlier entries", if status.is_empty() { "" } else { ", " },
+                       if *count == 1 { "1 file".to_string() } else { format!("{} files", count) })?;
+                out.reset()?;
+            }
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Audit the directories in the search path for common security problems, before trusting
+    /// it to locate executables. The following are reported as `SecurityWarning`s:
+    ///  - relative directories, such as `.`
+    ///  - directories within a temporary directory, such as `/tmp`
+    ///  - (on unix) directories that can be written to by any user
+    ///  - (on unix) directories owned by a user other than root or the current user
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     for warning in search_path.audit() {
+    ///         println!("Warning: {}", warning);
+    ///     }
+    /// }
+    /// ```
+    pub fn audit(&self) -> Vec<SecurityWarning> {
+        let mut warnings = vec!();
+        #[cfg(not(target_os = "wasi"))]
+        let temp_dirs = [PathBuf::from("/tmp"), PathBuf::from("/var/tmp"), env::temp_dir()];
+        // WASI has no default temporary directory
+        #[cfg(target_os = "wasi")]
+        let temp_dirs = [PathBuf::from("/tmp"), PathBuf::from("/var/tmp")];
+
+        for dir in self.search_dirs() {
+            if dir.is_relative() {
+                warnings.push(SecurityWarning::Relative(dir.to_path_buf()));
+            }
+
+            if temp_dirs.iter().any(|temp_dir| dir.starts_with(temp_dir)) {
+                warnings.push(SecurityWarning::Temporary(dir.to_path_buf()));
+            }
+
+            #[cfg(unix)]
+            if let Ok(metadata) = fs::metadata(dir) {
+                use std::os::unix::fs::MetadataExt;
+
+                if metadata.mode() & 0o002 != 0 {
+                    warnings.push(SecurityWarning::WorldWritable(dir.to_path_buf()));
+                }
+
+                let uid = metadata.uid();
+                // SAFETY: getuid() has no preconditions and cannot fail
+                if uid != 0 && uid != unsafe { libc::getuid() } {
+                    warnings.push(SecurityWarning::UnexpectedOwner(dir.to_path_buf(), uid));
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Get the number of entries in the path, i.e. the number of directories (including disabled
+    /// ones) and, if the "urls" feature is enabled, the number of urls
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("Foo");
+    ///     search_path.add_directory("/usr/bin");
+    ///     assert_eq!(search_path.len(), 1);
+    /// }
+    /// ```
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the path is empty, i.e. has no directories added to it, and if the "urls"
+    /// feature is enabled, that is has no urls added to it either.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("Foo");
+    ///     assert!(search_path.is_empty(), "The 'Foo' SearchPath should be empty");
+    /// }
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// `Chain` is a list of `Simpath`s that are searched in order, each one being a fallback for the
+/// ones before it, such as "project overrides" then "user config" then "system defaults".
+/// It is created using `Simpath::with_fallback()`.
+#[derive(Clone, Debug)]
+pub struct Chain {
+    levels: Vec<Simpath>,
+}
+
+impl Chain {
+    /// Add another `Simpath` to the end of the chain, to be searched if no match is found in
+    /// the ones before it
+    pub fn with_fallback(mut self, fallback: Simpath) -> Self {
+        self.levels.push(fallback);
+        self
+    }
+
+    /// Get the `Simpath`s in the chain, in the order they are searched
+    pub fn levels(&self) -> &[Simpath] {
+        &self.levels
+    }
+
+    /// Try to find a file or resource by name in the `Simpath`s of the chain, in order.
+    /// If it is found `Ok` is returned with the index of the `Simpath` in the chain (its level)
+    /// where it was found and the `FoundType`. If it is not found then `Err` is returned.
+    pub fn find(&self, file_name: &str) -> Result<(usize, FoundType), Error> {
+        self.find_type(file_name, FileType::Any)
+    }
+
+    /// Try to find an entry of a specific `FileType` in the `Simpath`s of the chain, in order,
+    /// as `find()` does
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let chain = Simpath::new("PROJECT_PATH")
+    ///         .with_fallback(Simpath::new("USER_PATH"))
+    ///         .with_fallback(Simpath::new("PATH"));
+    ///     match chain.find_type("ls", FileType::File) {
+    ///         Ok((level, found)) => println!("Found {:?} at level {}", found, level),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_type(&self, file_name: &str, file_type: FileType) -> Result<(usize, FoundType), Error> {
+        // The first error other than not found, reported if nothing is found in any level
+        let mut error: Option<Error> = None;
+
+        for (level, search_path) in self.levels.iter().enumerate() {
+            match search_path.find_type(file_name, file_type.clone()) {
+                Ok(found) => return Ok((level, found)),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => { error.get_or_insert(e); }
+            }
+        }
+
+        Err(error.unwrap_or_else(|| {
+            let names: Vec<&str> = self.levels.iter().map(|level| level.name()).collect();
+            Error::new(ErrorKind::NotFound,
+                       format!("Could not find type '{:?}' called '{}' in search paths '{}'",
+                               file_type, file_name, names.join("', '")))
+        }))
+    }
+}
+
+/// `Walk` is an `Iterator` over all the files in the directories of a `Simpath`,
+/// created using `Simpath::walk()`
+pub struct Walk<'a> {
+    simpath: &'a Simpath,
+    dir_exclusions: DirExclusions,
+    roots: Box<dyn Iterator<Item = &'a Path> + 'a>,
+    root: PathBuf,
+    pending: Vec<PathBuf>,
+    current: Option<fs::ReadDir>,
+    recursive: bool,
+    unique: bool,
+    seen: HashSet<PathBuf>,
+    dir: PathBuf,
+    error_handler: Option<ErrorHandler>,
+    progress_handler: Option<ProgressHandler>,
+    files_scanned: usize,
+}
+
+impl<'a> Walk<'a> {
+    /// Descend into sub-directories of the `Simpath`'s directories when walking
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Only return the first file found with a given name (relative to the directory of the
+    /// `Simpath` it was found in), skipping files shadowed by it in later directories
+    pub fn unique(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
+    }
+}
+
+impl<'a> Walk<'a> {
+    // Pass an error to the error handler, if there is one, so the walk can continue. If not
+    // the error is returned, to be returned to the caller.
+    fn handle_error(&self, path: &Path, error: Error) -> Option<Error> {
+        match &self.error_handler {
+            Some(handler) => {
+                (handler.0)(path, &error);
+                None
+            }
+            None => Some(error)
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Result<PathBuf, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(read_dir) = &mut self.current {
+                match read_dir.next() {
+                    Some(Ok(entry)) => {
+                        self.files_scanned += 1;
+                        if self.simpath.excluded(&self.dir_exclusions, &entry) {
+                            continue;
+                        }
+                        let path = entry.path();
+                        match entry.file_type() {
+                            Ok(file_type) if file_type.is_dir() => {
+                                if self.recursive {
+                                    self.pending.push(path);
+                                }
+                            }
+                            Ok(_) if path.is_dir() => { /* skip links to directories */ }
+                            Ok(_) => {
+                                if self.unique {
+                                    let relative = path.strip_prefix(&self.root)
+                                        .unwrap_or(&path).to_path_buf();
+                                    if !self.seen.insert(relative) {
+                                        continue;
+                                    }
+                                }
+                                return Some(Ok(path));
+                            }
+                            Err(e) => if let Some(e) = self.handle_error(&path, e) {
+                                return Some(Err(e));
+                            },
+                        }
+                    }
+                    Some(Err(e)) => if let Some(e) = self.handle_error(&self.dir, e) {
+                        return Some(Err(e));
+                    },
+                    None => {
+                        self.current = None;
+                        if let Some(handler) = &self.progress_handler {
+                            (handler.0)(&self.dir, self.files_scanned);
+                        }
+                    }
+                }
+            } else if let Some(dir) = self.pending.pop() {
+                match fs::read_dir(&dir) {
+                    Ok(read_dir) => {
+                        self.current = Some(read_dir);
+                        self.dir = dir;
+                    }
+                    Err(e) => if let Some(e) = self.handle_error(&dir, e) {
+                        return Some(Err(e));
+                    },
+                }
+            } else {
+                let root = self.roots.next()?;
+                self.dir_exclusions = self.simpath.dir_exclusions(root);
+                self.root = root.to_path_buf();
+                self.pending.push(root.to_path_buf());
+            }
+        }
+    }
+}
+
+/// Parse a `Simpath` from a string of entries separated by the default separator character for
+/// the platform (':', or ';' on windows), e.g. "/usr/bin:/bin". The `Simpath` has no name.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::Simpath;
+///
+/// fn main() {
+///     let search_path: Simpath = "/usr/bin:/bin".parse().unwrap();
+///     println!("Directories in Search Path: {:?}", search_path.directories().collect::<Vec<_>>());
+/// }
+/// ```
+impl FromStr for Simpath {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Simpath::parse("", value, DEFAULT_SEPARATOR_CHAR))
+    }
+}
+
+#[cfg(feature = "clap")]
+/// `SimpathParser` is a `clap` value parser for command line arguments that are search paths,
+/// such as `--search-path a:b:c`, parsing them into `Simpath`s. The separator used can be set,
+/// and if validation is enabled an argument with a directory that does not exist or cannot be
+/// read is rejected with an error that describes what is wrong with it.
+///
+/// The `Simpath` is named after the argument, unless a name is set.
+///
+/// ```
+/// extern crate clap;
+/// extern crate simpath;
+/// use clap::{Arg, Command};
+/// use simpath::{Simpath, SimpathParser};
+///
+/// fn main() {
+///     let matches = Command::new("tool")
+///         .arg(Arg::new("search-path").long("search-path")
+///             .value_parser(SimpathParser::new().separator(',').validate(true)))
+///         .get_matches_from(vec!("tool", "--search-path", "/,."));
+///     let search_path = matches.get_one::<Simpath>("search-path").unwrap();
+///     assert_eq!(search_path.name(), "search-path");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SimpathParser {
+    separator: char,
+    validate: bool,
+    name: Option<String>,
+}
+
+#[cfg(feature = "clap")]
+impl SimpathParser {
+    /// Create a new `SimpathParser`, using the default separator character for the platform and
+    /// without validation
+    pub fn new() -> Self {
+        SimpathParser { separator: DEFAULT_SEPARATOR_CHAR, validate: false, name: None }
+    }
+
+    /// Set the `separator` character used between the entries of the argument
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Set if the directories of the argument should be validated, as `Simpath::validate()` does
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Set the `name` of the `Simpath`s created, instead of using the name of the argument
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+}
+
+#[cfg(feature = "clap")]
+impl Default for SimpathParser {
+    fn default() -> Self {
+        SimpathParser::new()
+    }
+}
+
+#[cfg(feature = "clap")]
+impl clap::builder::TypedValueParser for SimpathParser {
+    type Value = Simpath;
+
+    fn parse_ref(&self, cmd: &clap::Command, arg: Option<&clap::Arg>, value: &OsStr)
+                 -> Result<Self::Value, clap::Error> {
+        let value = value.to_str()
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidUtf8).with_cmd(cmd))?;
+        let name = self.name.clone()
+            .or_else(|| arg.map(|arg| arg.get_id().to_string()))
+            .unwrap_or_default();
+        let search_path = Simpath::parse(&name, value, self.separator);
+
+        if self.validate {
+            let errors = search_path.validate();
+            if !errors.is_empty() {
+                let arg = arg.map(|arg| arg.to_string()).unwrap_or_else(|| "...".to_string());
+                let reasons: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                return Err(clap::Error::raw(clap::error::ErrorKind::ValueValidation,
+                                            format!("invalid search path '{}' for '{}': {}\n",
+                                                    value, arg, reasons.join(", ")))
+                    .with_cmd(cmd));
+            }
+        }
+
+        Ok(search_path)
+    }
+}
+
+/// Create an empty `Simpath` without a name, to use as a container of search path entries, using
+/// the default separator character for the platform
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::Simpath;
+///
+/// fn main() {
+///     let mut search_path = Simpath::default();
+///     search_path.add_directory("/usr/bin");
+///     assert_eq!(search_path.name(), "");
+/// }
+/// ```
+impl Default for Simpath {
+    fn default() -> Self {
+        Simpath::empty("", DEFAULT_SEPARATOR_CHAR)
+    }
+}
+
+/// A `Simpath` is displayed in a compact form on one line by default. The alternate form
+/// (`{:#}`) lists each entry on its own line, with its index, its kind and whether it is
+/// disabled, for showing to users. Formatting does not access the file system, so it does not
+/// show whether directories are valid, which `write_report()` does.
+///
+/// ```text
+/// Search Path 'PATH':
+///   0: directory /usr/local/bin
+///   1: url https://example.com/tools/
+///   2: directory /snap/bin (disabled)
+/// ```
+impl fmt::Display for Simpath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            if self.name.is_empty() {
+                writeln!(f, "Search Path:")?;
+            } else {
+                writeln!(f, "Search Path '{}':", self.name)?;
+            }
+
+            for (index, entry) in self.entries.iter().enumerate() {
+                let kind = match entry {
+                    Entry::Directory(_) => "directory",
+                    #[cfg(feature = "urls")]
+                    Entry::Url(_) => "url",
+                };
+                write!(f, "  {}: {} {}", index, kind, entry)?;
+                if !entry.is_enabled() {
+                    write!(f, " (disabled)")?;
+                }
+                writeln!(f)?;
+            }
+
+            return Ok(());
+        }
+
+        if self.name.is_empty() {
+            write!(f, "Search Path: Directories: {:?}", self.directories().collect::<Vec<_>>())?;
+        } else {
+            write!(f, "Search Path '{}': Directories: {:?}", self.name, self.directories().collect::<Vec<_>>())?;
+        }
+
+        #[cfg(feature = "urls")]
+        write!(f, ", URLs: {:?}", self.urls().collect::<Vec<_>>())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    #[cfg(feature = "config")]
+    use super::Access;
+    use super::{Canonicalize, EntrySource, FileType, FoundType, IncludeStyle, Limits, NewError, PathError, SecurityWarning,
+                DEFAULT_SEPARATOR_CHAR};
+
+    use super::Simpath;
+
+    // Create a temporary directory for a test. WASI has no default temporary directory, so there
+    // "/tmp" is used, which the test runner must preopen
+    pub(crate) fn temp_dir() -> PathBuf {
+        #[cfg(target_os = "wasi")]
+        return tempdir::TempDir::new_in("/tmp", "simpath").unwrap().into_path();
+        #[cfg(not(target_os = "wasi"))]
+        tempdir::TempDir::new("simpath").unwrap().into_path()
+    }
+
+    #[test]
+    fn can_create() {
+        Simpath::new("PATH");
+    }
+
+    #[test]
+    fn can_create_with_separator() {
+        Simpath::new_with_separator("PATH", ':');
+    }
+
+    #[test]
+    fn name_is_saved() {
+        let path = Simpath::new("MyName");
+        assert_eq!(path.name(), "MyName");
+    }
+
+    #[test]
+    fn find_non_existant_file() {
+        let path = Simpath::new("MyName");
+        assert!(path.find("no_such_file").is_err());
+    }
+
+    #[test]
+    fn display_empty_path() {
+        let path = Simpath::new("MyName");
+        println!("{}", path);
+    }
+
+    #[test]
+    fn directory_is_added() {
+        let mut path = Simpath::new("MyName");
+        assert!(path.directories().next().is_none());
+        path.add_directory(env::current_dir()
+            .expect("Could not get current working directory"));
+        let cwd = env::current_dir()
+            .expect("Could not get current working directory").to_string_lossy().to_string();
+        assert!(path.contains(&cwd));
+    }
+
+    #[test]
+    fn contains_normalized_directory() {
+        let mut path = Simpath::new("MyName");
+        path.set_canonicalize(Canonicalize::Full);
+        path.add_directory(env::current_dir()
+            .expect("Could not get current working directory"));
+        assert!(path.contains("."));
+        assert!(!path.contains_exact("."));
+        assert!(path.contains_exact(env::current_dir()
+            .expect("Could not get current working directory")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn contains_ignores_case_and_separators() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("C:/No/Such/Dir");
+        assert!(path.contains("c:\\no\\such\\dir"));
+        assert!(!path.contains_exact("c:\\no\\such\\dir"));
+    }
+
+    #[test]
+    fn len_and_entry() {
+        let mut path = Simpath::new("MyName");
+        assert_eq!(path.len(), 0);
+        assert!(path.entry(0).is_none());
+
+        path.add_directory("/usr/bin");
+        path.add_directory("/bin");
+        path.disable_entry(0).unwrap();
+        assert_eq!(path.len(), 2);
+        assert!(!path.is_empty());
+        assert_eq!(path.entry(1).and_then(|entry| entry.path()), Some(Path::new("/bin")));
+        assert!(path.entry(2).is_none());
+    }
+
+    #[test]
+    fn search_continues_after_errors_with_handler() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let missing = temp_dir.join("missing");
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&missing);
+        path.add_directory(&temp_dir);
+
+        // without a handler the error ends the search
+        assert!(path.find("tool").is_err());
+        assert!(path.list_with_extension("txt", false).is_err());
+
+        let errors = Arc::new(Mutex::new(vec!()));
+        let handler_errors = errors.clone();
+        path.on_error(move |path, _| handler_errors.lock().unwrap().push(path.to_path_buf()));
+        assert_eq!(path.find("tool").unwrap(), FoundType::File(temp_dir.join("tool")));
+        assert_eq!(path.walk().count(), 1);
+        assert_eq!(*errors.lock().unwrap(), vec!(missing.clone(), missing));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn progress_is_reported() {
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join("sub")).unwrap();
+        fs::File::create(temp_dir.join("sub/tool")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        let progress = Arc::new(Mutex::new(vec!()));
+        let handler_progress = progress.clone();
+        path.on_progress(move |dir, files| handler_progress.lock().unwrap().push((dir.to_path_buf(), files)));
+
+        assert!(path.find_all("tool", FileType::File).is_err());
+        assert_eq!(path.walk().recursive(true).count(), 1);
+        path.freeze();
+        assert_eq!(*progress.lock().unwrap(), vec!((temp_dir.clone(), 1),
+                                                   (temp_dir.clone(), 1), (temp_dir.join("sub"), 2),
+                                                   (temp_dir.clone(), 1)));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn check_entry() {
+        let temp_dir = temp_dir();
+        let missing = temp_dir.join("missing");
+        let mut path = Simpath::new("MyPath");
+        path.add_directory(&temp_dir);
+        path.add_directory(&missing);
+
+        assert!(path.check_entry(0).is_ok());
+        assert_eq!(path.check_entry(1).map_err(|e| e.kind()), Err(std::io::ErrorKind::NotFound));
+        assert!(path.check_entry(2).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn parse_from_str() {
+        let temp_dir = temp_dir();
+        let value = format!("{}{}/", temp_dir.display(), DEFAULT_SEPARATOR_CHAR);
+        let path: Simpath = value.parse().unwrap();
+        assert_eq!(path.name(), "");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone(), PathBuf::from("/")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn parse_with_clap() {
+        use clap::{Arg, Command};
+        use super::SimpathParser;
+
+        let temp_dir = temp_dir();
+        let command = Command::new("tool")
+            .arg(Arg::new("path").long("path")
+                .value_parser(SimpathParser::new().separator(',').validate(true).name("ToolPath")));
+
+        let value = format!("{},/", temp_dir.display());
+        let matches = command.clone().try_get_matches_from(vec!("tool", "--path", &value)).unwrap();
+        let path = matches.get_one::<Simpath>("path").unwrap();
+        assert_eq!(path.name(), "ToolPath");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone(), PathBuf::from("/")));
+
+        let missing = temp_dir.join("missing");
+        let error = command.try_get_matches_from(vec!("tool", "--path", &missing.display().to_string()))
+            .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(error.to_string().contains("does not exist"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn default_is_unnamed() {
+        let path = Simpath::default();
+        assert_eq!(path.name(), "");
+        assert!(path.is_empty());
+        assert!(path.to_string().starts_with("Search Path: Directories: []"));
+        assert_eq!(path.find("no-way-this-exists").unwrap_err().to_string(),
+                   "Could not find type 'Any' called 'no-way-this-exists' in the search path");
+    }
+
+    #[test]
+    fn display_alternate_and_env_string() {
+        let temp_dir = temp_dir();
+        let missing = temp_dir.join("missing");
+        let mut path = Simpath::new_with_separator("DisplayAlternate", ',');
+        path.add_directory(&temp_dir);
+        path.add_directory(&missing);
+        path.add_directory("/");
+        path.disable_entry(2).unwrap();
+
+        assert_eq!(format!("{:#}", path),
+                   format!("Search Path 'DisplayAlternate':\n  0: directory {}\n  1: directory {}\n  \
+                            2: directory / (disabled)\n", temp_dir.display(), missing.display()));
+        assert_eq!(path.to_env_string(), format!("{},{}", temp_dir.display(), missing.display()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "term")]
+    #[test]
+    fn uncolored_report() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        let missing = temp_dir.join("missing");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::File::create(first.join("tool")).unwrap();
+        fs::File::create(second.join("tool")).unwrap();
+        let mut path = Simpath::new("ReportPath");
+        path.add_directory(&first);
+        path.add_directory(&second);
+        path.add_directory(&missing);
+
+        let mut out = termcolor::NoColor::new(Vec::new());
+        path.write_report(&mut out).unwrap();
+        let report = String::from_utf8(out.into_inner()).unwrap();
+        let width = missing.display().to_string().len();
+        assert_eq!(report, format!("Search Path 'ReportPath':\n  ✓  0  {}\n  ✓  1  {:<w$}  \
+                                    1 file shadowed by earlier entries\n  ✗  2  {:<w$}  does not exist\n",
+                                   first.display(), second.display(), missing.display(), w = width));
+
+        // NO_COLOR is read from the environment source of the search path
+        let mut path = Simpath::new_with_env_source("ReportPath", Arc::new(std::collections::HashMap::from(
+            [("NO_COLOR".to_string(), "1".to_string())])));
+        path.add_directory(&first);
+        path.add_directory(&second);
+        path.add_directory(&missing);
+        assert_eq!(path.report(), report);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn parse_with_platform_separator() {
+        let var_name = "PlatformSeparator";
+        let value = env::join_paths(["/usr/bin", "", "/opt/tools/bin"]).unwrap();
+        env::set_var(var_name, &value);
+        let mut path = Simpath::new(var_name);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/bin"), PathBuf::from("/opt/tools/bin")));
+        assert_eq!(path.to_env_string(), env::join_paths(["/usr/bin", "/opt/tools/bin"]).unwrap().to_str().unwrap());
+
+        path.set_posix_empty_entries(true);
+        path.add_from_env_var(var_name);
+        assert!(path.directories().any(|dir| dir == Path::new(".")));
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore)]
+    fn system_path_with_current_dir_first() {
+        let path = Simpath::system_path_with_current_dir();
+        let app_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+        let current_dir = env::current_dir().unwrap();
+        let directories: Vec<&Path> = path.directories().collect();
+
+        assert_eq!(directories[0], app_dir);
+        assert!(directories.contains(&current_dir.as_path()));
+        assert!(directories.iter().position(|dir| dir == &current_dir) <= Some(1));
+        assert!(directories.len() > 2);
+        assert_eq!(path.entries()[0].source(), &EntrySource::Default);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn well_known_variables() {
+        let temp_dir = temp_dir();
+        let man = temp_dir.join("man");
+        let pkgconfig = temp_dir.join("pkgconfig");
+        fs::create_dir(&man).expect("Could not create directory");
+        fs::create_dir(&pkgconfig).expect("Could not create directory");
+
+        env::set_var("MANPATH", format!(":{}", man.display()));
+        assert_eq!(Simpath::manpath().directories().collect::<Vec<_>>(),
+                   vec!(PathBuf::from("/usr/local/share/man"), PathBuf::from("/usr/share/man"),
+                        PathBuf::from("/usr/local/man"), man.clone()));
+        env::set_var("MANPATH", man.display().to_string());
+        assert_eq!(Simpath::manpath().directories().collect::<Vec<_>>(), vec!(man.clone()));
+        env::remove_var("MANPATH");
+        assert_eq!(Simpath::manpath().len(), 3);
+
+        env::set_var("PKG_CONFIG_PATH", pkgconfig.display().to_string());
+        env::set_var("PKG_CONFIG_LIBDIR", "");
+        assert_eq!(Simpath::pkg_config_path().directories().collect::<Vec<_>>(), vec!(pkgconfig.clone()));
+        env::remove_var("PKG_CONFIG_LIBDIR");
+        assert_eq!(Simpath::pkg_config_path().directories().next(), Some(pkgconfig.as_path()));
+        assert!(Simpath::pkg_config_path().contains("/usr/lib/pkgconfig"));
+        env::remove_var("PKG_CONFIG_PATH");
+
+        assert!(Simpath::ld_library_path().contains("/usr/lib"));
+        assert_eq!(Simpath::path().name(), "PATH");
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn defaults_in_place_of_empty_entries() {
+        let temp_dir = temp_dir();
+        let defaults_dir = temp_dir.join("defaults");
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        for dir in [&defaults_dir, &first, &second] {
+            fs::create_dir(dir).expect("Could not create directory");
+        }
+        let mut defaults = Simpath::new("");
+        defaults.add_directory(&defaults_dir);
+        let var_name = "MyDefaultedPath";
+        let join = |dirs: &[&PathBuf]| dirs.iter().map(|dir| dir.display().to_string())
+            .collect::<Vec<String>>().join(&DEFAULT_SEPARATOR_CHAR.to_string());
+
+        env::set_var(var_name, join(&[&first, &PathBuf::new(), &second]));
+        let path = Simpath::new_with_defaults(var_name, &defaults);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(first.clone(), defaults_dir.clone(), second.clone()));
+        assert_eq!(path.entries()[1].source(), &EntrySource::Default);
+
+        env::set_var(var_name, format!("{}{}", DEFAULT_SEPARATOR_CHAR, join(&[&first])));
+        assert_eq!(Simpath::new_with_defaults(var_name, &defaults).directories().collect::<Vec<_>>(),
+                   vec!(defaults_dir.clone(), first.clone()));
+
+        env::set_var(var_name, join(&[&first]));
+        assert_eq!(Simpath::new_with_defaults(var_name, &defaults).directories().collect::<Vec<_>>(), vec!(first.clone()));
+
+        env::remove_var(var_name);
+        assert_eq!(Simpath::new_with_defaults(var_name, &defaults).directories().collect::<Vec<_>>(), vec!(defaults_dir.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn wildcard_entries_expanded() {
+        let temp_dir = temp_dir();
+        let plugins = temp_dir.join("plugins");
+        fs::create_dir_all(plugins.join("b")).unwrap();
+        fs::create_dir_all(plugins.join("a")).unwrap();
+        fs::File::create(plugins.join("README")).unwrap();
+        let var_name = "WildcardPath";
+        env::set_var(var_name, env::join_paths([plugins.join("*"), temp_dir.clone()]).unwrap());
+        let mut path = Simpath::new(var_name);
+
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(plugins.join("a"), plugins.join("b"), temp_dir.clone()));
+        assert_eq!(path.entries()[0].expanded_from(), Some(plugins.as_path()));
+        assert_eq!(path.entries()[2].expanded_from(), None);
+
+        fs::create_dir(plugins.join("c")).unwrap();
+        fs::remove_dir(plugins.join("a")).unwrap();
+        path.refresh_wildcards();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(plugins.join("b"), plugins.join("c"), temp_dir.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
-    /// Check if the path is empty, i.e. has no directories added to it, and if the "urls"
-    /// feature is enabled, that is has no urls added to it either.
-    ///
-    /// ```
-    /// extern crate simpath;
-    /// use simpath::Simpath;
-    /// use std::env;
-    ///
-    /// fn main() {
-    ///     let mut search_path = Simpath::new("Foo");
-    ///     assert!(search_path.is_empty(), "The 'Foo' SearchPath should be empty");
-    /// }
-    /// ```
-    pub fn is_empty(&self) -> bool {
-        #[cfg(not(feature = "urls"))]
-        return self.directories.is_empty();
-        #[cfg(feature = "urls")]
-        return self.directories.is_empty() && self.urls.is_empty();
+    #[test]
+    fn recursive_wildcard_entries_expanded() {
+        let temp_dir = temp_dir();
+        let tools = temp_dir.join("tools");
+        fs::create_dir_all(tools.join("b").join("deep").join("deeper")).unwrap();
+        fs::create_dir_all(tools.join("a")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.set_max_wildcard_depth(2);
+        assert_eq!(path.max_wildcard_depth(), 2);
+        path.add(tools.join("**").display().to_string());
+
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(tools.clone(), tools.join("a"), tools.join("b"),
+                                            tools.join("b").join("deep")));
+        assert!(path.entries().iter().all(|entry| entry.expanded_from() == Some(tools.as_path())));
+
+        path.set_max_wildcard_depth(super::DEFAULT_MAX_WILDCARD_DEPTH);
+        path.refresh_wildcards();
+        assert_eq!(path.directories().last(), Some(tools.join("b").join("deep").join("deeper").as_path()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
-}
 
-impl fmt::Display for Simpath {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Search Path '{}': Directories: {:?}", self.name, self.directories)?;
+    #[test]
+    fn variables_expanded() {
+        let mut path = Simpath::new_with_separator("MyName", ':');
+        path.add("$NOT_EXPANDED/bin");
+        path.set_expand_variables(true);
+        assert!(path.expand_variables());
+        path.set_variables([("JAVA_HOME", "/opt/jdk"), ("HOME", "/home/me"), ("APPDATA", "/data")]);
+        path.add("$JAVA_HOME/bin");
+        path.add("${HOME}/.cargo/bin");
+        path.add("%APPDATA%/tools");
+        path.add("$UNDEFINED/bin");
+        path.add("/usr/lib$/50%");
 
-        #[cfg(feature = "urls")]
-        write!(f, ", URLs: {:?}", self.urls)?;
+        assert_eq!(path.to_env_string(),
+                   "$NOT_EXPANDED/bin:/opt/jdk/bin:/home/me/.cargo/bin:/data/tools:$UNDEFINED/bin:/usr/lib$/50%");
+    }
 
-        Ok(())
+    #[test]
+    fn env_source() {
+        let mut vars = std::collections::HashMap::new();
+        vars.insert("SourcedPath".to_string(), "/usr/bin:${TOOLS}/bin".replace(':', &DEFAULT_SEPARATOR_CHAR.to_string()));
+        vars.insert("TOOLS".to_string(), "/opt/tools".to_string());
+        vars.insert("OtherPath".to_string(), "/sbin".to_string());
+        let source = Arc::new(vars);
+        env::remove_var("SourcedPath");
+
+        let mut path = Simpath::new_with_env_source("SourcedPath", source.clone());
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/bin"), PathBuf::from("${TOOLS}/bin")));
+
+        path.set_expand_variables(true);
+        path.reload_env_entries();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/bin"), PathBuf::from("/opt/tools/bin")));
+
+        let mut path = Simpath::new("SourcedPath");
+        assert!(path.is_empty());
+        path.set_env_source(source);
+        path.add_from_env_var("OtherPath");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/sbin")));
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::env;
-    use std::fs;
-    use std::io::Write;
+    #[test]
+    #[cfg(unix)]
+    fn split_entries_keeps_non_unicode() {
+        use std::ffi::{OsStr, OsString};
+        use std::os::unix::ffi::OsStringExt;
 
-    use super::{DEFAULT_SEPARATOR_CHAR, FileType};
+        let value = OsString::from_vec(b"/tmp/\xff\xfe\xe2\x86\x92/usr/bin".to_vec());
+        assert_eq!(Simpath::split_entries(&value, '→'),
+                   vec!(OsString::from_vec(b"/tmp/\xff\xfe".to_vec()), OsString::from("/usr/bin")));
+        assert_eq!(Simpath::split_entries(OsStr::new(",a,"), ','),
+                   vec!(OsString::new(), OsString::from("a"), OsString::new()));
+    }
 
-    use super::Simpath;
+    #[test]
+    fn env_var_with_separator_parses_entries() {
+        let var_name = "SeparatedPath";
+        env::set_var("SEPARATED_TOOLS", "/opt/separated");
+        env::set_var(var_name, "${SEPARATED_TOOLS}/bin,/usr/bin");
+        let mut path = Simpath::new("MyName");
+        path.set_expand_variables(true);
+        path.add_from_env_var_with_separator(var_name, ',');
+        assert_eq!(path.directories().collect::<Vec<_>>(),
+                   vec!(PathBuf::from("/opt/separated/bin"), PathBuf::from("/usr/bin")));
+        assert_eq!(path.entries()[0].source(), &EntrySource::EnvVar(var_name.into()));
+        assert!(path.original_value().is_none());
+
+        env::set_var(var_name, "/bin,/sbin");
+        path.reload_env_entries();
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/bin"), PathBuf::from("/sbin")));
+    }
 
     #[test]
-    fn can_create() {
-        Simpath::new("PATH");
+    fn command_env_round_trip() {
+        let temp_dir = temp_dir();
+        let one = temp_dir.join("one");
+        let two = temp_dir.join("two");
+        fs::create_dir_all(&one).expect("Could not create dir");
+        fs::create_dir_all(&two).expect("Could not create dir");
+
+        let mut search_path = Simpath::new("SIMPATH_COMMAND_TEST");
+        search_path.add_directory(one.to_str().expect("Invalid path"));
+        search_path.add_directory(two.to_str().expect("Invalid path"));
+
+        let mut command = std::process::Command::new("true");
+        search_path.set_command_env(&mut command);
+
+        let from_command = Simpath::from_command(&command, "SIMPATH_COMMAND_TEST",
+                                                 super::DEFAULT_SEPARATOR_CHAR);
+        assert!(from_command.directories().eq(search_path.directories()));
+        assert_eq!(from_command.to_env_string(), search_path.to_env_string());
+
+        command.env_remove("SIMPATH_COMMAND_TEST");
+        let removed = Simpath::from_command(&command, "SIMPATH_COMMAND_TEST",
+                                            super::DEFAULT_SEPARATOR_CHAR);
+        assert!(removed.is_empty());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
     #[test]
-    fn can_create_with_separator() {
-        Simpath::new_with_separator("PATH", ':');
+    #[cfg(unix)]
+    fn try_new_reports_problems() {
+        let var_name = "TryNewPath";
+        env::remove_var(var_name);
+        assert_eq!(Simpath::try_new(var_name).err(), Some(NewError::Unset(var_name.into())));
+
+        env::set_var(var_name, "::");
+        assert_eq!(Simpath::try_new_with_separator(var_name, ':').err(), Some(NewError::Empty(var_name.into())));
+
+        env::set_var(var_name, "/no/such/dir:/nor/this/one");
+        let error = Simpath::try_new_with_separator(var_name, ':').expect_err("Entries were not rejected");
+        match &error {
+            NewError::Rejected { rejected, .. } => assert_eq!(rejected.len(), 2),
+            _ => panic!("Unexpected error: {}", error),
+        }
+        assert!(error.to_string().starts_with("All 2 entries in environment variable 'TryNewPath' were rejected: \
+            '/no/such/dir' (It does not exist)"));
+
+        let temp_dir = temp_dir();
+        env::set_var(var_name, format!("/no/such/dir:{}", temp_dir.display()));
+        let path = Simpath::try_new_with_separator(var_name, ':').expect("Could not create path");
+        assert_eq!(path.directories().count(), 2);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
     #[test]
-    fn name_is_saved() {
-        let path = Simpath::new("MyName");
-        assert_eq!(path.name(), "MyName");
+    fn limits_bound_entries_and_searches() {
+        let var_name = "LimitedPath";
+        env::set_var(var_name, format!("/first{0}/second{0}/third", DEFAULT_SEPARATOR_CHAR));
+        let limits = Limits { max_entries: 2, ..Default::default() };
+        let error = Simpath::new_with_limits(var_name, limits).expect_err("Limit on entries was not applied");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+        let limits = Limits { max_entry_length: 6, ..Default::default() };
+        assert!(Simpath::new_with_limits(var_name, limits).is_err());
+
+        let temp_dir = temp_dir();
+        for dir in ["a", "b", "c"] {
+            fs::create_dir(temp_dir.join(dir)).expect("Could not create directory");
+        }
+        fs::File::create(temp_dir.join("a").join("found")).expect("Could not create file");
+
+        let limits = Limits { max_entries: 2, max_entry_length: usize::MAX, max_searches: 1 };
+        let mut path = Simpath::new_with_limits("NoSuchVariable", limits).expect("Could not create path");
+        assert_eq!(path.limits(), limits);
+        let rejected = Arc::new(Mutex::new(vec!()));
+        let recorder = rejected.clone();
+        path.on_error(move |path, _| recorder.lock().unwrap().push(path.to_path_buf()));
+        path.add_directory(temp_dir.join("a"));
+        path.add_directory(temp_dir.join("b"));
+        path.add_directory(temp_dir.join("c"));
+        assert_eq!(path.directories().count(), 2);
+        assert_eq!(*rejected.lock().unwrap(), vec!(temp_dir.join("c")));
+
+        assert!(path.find("found").is_ok());
+        let error = path.find("missing").expect_err("Limit on searches was not applied");
+        assert!(error.to_string().contains("limit of 1 entries searched"));
+
+        path.set_limits(Limits { max_entry_length: 2, ..Default::default() });
+        path.add("/usr/bin");
+        assert_eq!(path.directories().count(), 2);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
     #[test]
-    fn find_non_existant_file() {
-        let path = Simpath::new("MyName");
-        assert!(path.find("no_such_file").is_err());
+    fn clone_copies_entries_on_write() {
+        let mut template = Simpath::new("MyName");
+        template.add_directory("/usr/bin");
+        template.add_directory("/bin");
+
+        let mut task = template.clone();
+        assert!(Arc::ptr_eq(&template.entries, &task.entries));
+        task.add_directory("/opt/task/bin");
+        assert!(!Arc::ptr_eq(&template.entries, &task.entries));
+        assert_eq!(template.directories().count(), 2);
+        assert_eq!(task.directories().count(), 3);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn display_empty_path() {
-        let path = Simpath::new("MyName");
-        println!("{}", path);
+    fn mode_filter_rejects_files() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        use super::ModeFilter;
+
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        for (dir, mode) in [(&first, 0o4755), (&second, 0o755)] {
+            fs::create_dir(dir).expect("Could not create directory");
+            let tool = dir.join("tool");
+            fs::File::create(&tool).expect("Could not create file");
+            fs::set_permissions(&tool, fs::Permissions::from_mode(mode)).expect("Could not set permissions");
+        }
+        let uid = fs::metadata(first.join("tool")).expect("Could not get metadata").uid();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+        assert_eq!(path.find("tool").expect("Could not find"), FoundType::File(first.join("tool")));
+
+        path.set_mode_filter(ModeFilter::new().owned_by(uid).exclude_setuid());
+        assert_eq!(path.find("tool").expect("Could not find"), FoundType::File(second.join("tool")));
+        assert_eq!(path.which_all("tool").expect("Could not find"), vec!(second.join("tool")));
+
+        path.set_mode_filter(ModeFilter::new().owned_by(uid.wrapping_add(1)));
+        let error = path.find("tool").expect_err("Found file with wrong owner");
+        assert!(error.to_string().contains("which is not an accepted owner"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
     #[test]
-    fn directory_is_added() {
+    fn find_pc_files() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        for dir in [&first, &second] {
+            fs::create_dir(dir).expect("Could not create directory");
+            fs::File::create(dir.join("zlib.pc")).expect("Could not create file");
+        }
+        fs::File::create(second.join("libpng.pc")).expect("Could not create file");
+        fs::File::create(second.join("README")).expect("Could not create file");
+
         let mut path = Simpath::new("MyName");
-        assert!(path.directories().is_empty());
-        path.add_directory(&env::current_dir()
-            .expect("Could not get current working directory")
-            .to_string_lossy());
-        let cwd = env::current_dir()
-            .expect("Could not get current working directory").to_string_lossy().to_string();
-        assert!(path.contains(&cwd));
+        path.add_directory(&first);
+        path.add_directory(&second);
+
+        let zlib = path.find_pc_file("zlib").expect("Could not find");
+        assert_eq!(zlib.path, first.join("zlib.pc"));
+        assert_eq!(zlib.entry.path(), Some(first.as_path()));
+        assert_eq!(zlib.entry.source(), &EntrySource::Programmatic);
+        assert_eq!(zlib.shadowed, vec!(second.join("zlib.pc")));
+        assert_eq!(path.find_pc_file("libpng").expect("Could not find").entry.path(), Some(second.as_path()));
+        assert_eq!(path.find_pc_file("libjpeg").expect_err("Found missing package").kind(),
+                   std::io::ErrorKind::NotFound);
+        assert_eq!(path.find_pc_file("first/zlib").expect_err("Found a path").kind(),
+                   std::io::ErrorKind::InvalidInput);
+
+        let pc_files = path.pc_files();
+        assert_eq!(pc_files.iter().map(|pc_file| pc_file.package.as_str()).collect::<Vec<_>>(),
+                   vec!("zlib", "libpng"));
+        assert_eq!(pc_files[0], zlib);
+
+        path.add_exclude("libpng*");
+        assert_eq!(path.pc_files().iter().map(|pc_file| pc_file.package.as_str()).collect::<Vec<_>>(),
+                   vec!("zlib"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
     }
 
     #[test]
-    fn cannot_add_same_dir_twice() {
+    fn find_headers() {
+        let temp_dir = temp_dir();
+        let source_dir = temp_dir.join("src");
+        let include_dir = temp_dir.join("include");
+        fs::create_dir_all(include_dir.join("sys")).expect("Could not create directory");
+        fs::create_dir(&source_dir).expect("Could not create directory");
+        fs::File::create(include_dir.join("sys").join("types.h")).expect("Could not create file");
+        fs::File::create(include_dir.join("config.h")).expect("Could not create file");
+        fs::File::create(source_dir.join("config.h")).expect("Could not create file");
+
         let mut path = Simpath::new("MyName");
-        assert!(path.directories().is_empty());
-        path.add_directory(".");
-        path.add_directory(".");
-        assert_eq!(path.directories().len(), 1);
+        path.add_directory(&include_dir);
+
+        assert_eq!(path.find_header("sys/types.h", &IncludeStyle::Angle).expect("Could not find"),
+                   include_dir.join("sys").join("types.h"));
+        assert_eq!(path.find_header("config.h", &IncludeStyle::Angle).expect("Could not find"),
+                   include_dir.join("config.h"));
+        let quote = IncludeStyle::Quote(source_dir.clone());
+        assert_eq!(path.find_header("config.h", &quote).expect("Could not find"), source_dir.join("config.h"));
+        assert_eq!(path.find_header("sys/types.h", &quote).expect("Could not find"),
+                   include_dir.join("sys").join("types.h"));
+        assert!(path.find_header("stdio.h", &quote).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn found_type_conversions() {
+        let temp_dir = temp_dir();
+        let file = temp_dir.join("data.txt");
+        fs::write(&file, "contents").expect("Could not write file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        let found = path.find("data.txt").expect("Could not find");
+
+        assert_eq!(fs::read_to_string(&found).expect("Could not read"), "contents");
+        assert_eq!(found.path(), Some(file.as_path()));
+        assert_eq!(found.to_string(), file.display().to_string());
+        assert_eq!(found.into_path(), Some(file.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn original_value_preserved() {
+        let var_name = "PreservedPath";
+        env::set_var(var_name, "/usr/bin::/bin:");
+        let mut path = Simpath::new_with_separator(var_name, ':');
+        assert_eq!(path.original_value(), Some("/usr/bin::/bin:"));
+        assert_eq!(path.to_env_string(), "/usr/bin:/bin");
+
+        path.set_preserve_original(true);
+        assert_eq!(path.to_env_string(), "/usr/bin::/bin:");
+
+        path.add_directory("/sbin");
+        assert_eq!(path.to_env_string(), "/usr/bin:/bin:/sbin");
+
+        env::set_var(var_name, ":/usr/local/bin");
+        path.reload_env_entries();
+        assert_eq!(path.original_value(), Some(":/usr/local/bin"));
+        assert_eq!(path.to_env_string(), "/usr/local/bin:/sbin");
+    }
+
+    #[cfg(not(feature = "urls"))]
+    #[test]
+    fn urls_reported_without_feature() {
+        let var_name = "UrlsWithoutFeature";
+        env::set_var(var_name, "/usr,https://example.com/tools,HTTP://example.com");
+        let errors = Arc::new(Mutex::new(vec!()));
+        let reported = errors.clone();
+
+        let mut path = Simpath::new_with_separator("Urls", ',');
+        path.on_error(move |entry, error| reported.lock().unwrap().push((entry.to_path_buf(), error.kind())));
+        path.add_from_env_var(var_name);
+
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr")));
+        assert_eq!(*errors.lock().unwrap(), vec!(
+            (PathBuf::from("https://example.com/tools"), std::io::ErrorKind::Unsupported),
+            (PathBuf::from("HTTP://example.com"), std::io::ErrorKind::Unsupported)));
     }
 
     #[test]
     fn find_dir_from_env_variable() {
         // Create a temp dir for test
-        let temp_dir = tempdir::TempDir::new("simpath").unwrap().into_path();
+        let temp_dir = temp_dir();
         let mut parent_dir = temp_dir.clone();
         parent_dir.pop();
 
@@ -578,7 +4134,7 @@ mod test {
     #[test]
     fn find_file_from_env_variable() {
         // Create a temp dir for test
-        let temp_dir = tempdir::TempDir::new("simpath").unwrap().into_path();
+        let temp_dir = temp_dir();
 
         // Create a ENV path that includes the path to the temp dir
         let var_name = "MYPATH";
@@ -605,7 +4161,7 @@ mod test {
     #[test]
     fn find_link_from_env_variable() {
         // Create a temp dir for test
-        let temp_dir = tempdir::TempDir::new("simpath").unwrap().into_path();
+        let temp_dir = temp_dir();
 
         // Create a ENV path that includes the path to the temp dir
         let var_name = "MYPATH";
@@ -636,7 +4192,7 @@ mod test {
     #[test]
     fn find_dir_using_any_from_env_variable() {
         // Create a temp dir for test
-        let temp_dir = tempdir::TempDir::new("simpath").unwrap().into_path();
+        let temp_dir = temp_dir();
 
         // Create a ENV path that includes that dir
         let var_name = "MyPath";
@@ -666,8 +4222,8 @@ mod test {
             .expect("Could not get current working directory")
             .to_string_lossy().to_string());
         let path = Simpath::new(var_name);
-        assert!(path.contains(&env::current_dir()
-            .expect("Could not get current working directory").to_string_lossy().to_string()));
+        assert!(path.contains(env::current_dir()
+            .expect("Could not get current working directory")));
     }
 
     #[test]
@@ -688,6 +4244,98 @@ mod test {
         assert!(path.contains("/"));
     }
 
+    #[test]
+    fn empty_entries_ignored() {
+        let var_name = "EmptyEntriesPath";
+        env::set_var(var_name, format!("/tmp{}{}/{}", DEFAULT_SEPARATOR_CHAR, DEFAULT_SEPARATOR_CHAR,
+                                       DEFAULT_SEPARATOR_CHAR));
+        let path = Simpath::new(var_name);
+        assert_eq!(path.directories().count(), 2);
+        assert!(!path.contains("."));
+        assert!(!path.contains(""));
+    }
+
+    #[test]
+    fn posix_empty_entries_are_current_dir() {
+        let var_name = "PosixEmptyEntriesPath";
+        env::set_var(var_name, format!("/tmp{}{}/{}", DEFAULT_SEPARATOR_CHAR, DEFAULT_SEPARATOR_CHAR,
+                                       DEFAULT_SEPARATOR_CHAR));
+        let mut path = Simpath::new("MyName");
+        path.set_posix_empty_entries(true);
+        path.add_from_env_var(var_name);
+        assert_eq!(path.directories().count(), 3);
+        assert!(path.contains("."));
+    }
+
+    #[test]
+    fn audit_relative_and_temporary_dirs() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory(".");
+        path.add_directory("/tmp/tools");
+        let warnings = path.audit();
+        assert!(warnings.contains(&SecurityWarning::Relative(PathBuf::from("."))));
+        assert!(warnings.contains(&SecurityWarning::Temporary(PathBuf::from("/tmp/tools"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn audit_world_writable_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = temp_dir();
+        fs::set_permissions(&temp_dir, fs::Permissions::from_mode(0o777)).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        assert!(path.audit().contains(&SecurityWarning::WorldWritable(temp_dir.clone())));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn from_env_or_defaults() {
+        let var_name = "DefaultsPath";
+        env::remove_var(var_name);
+        let path = Simpath::from_env_or(var_name, &["/", "/tmp"]);
+        assert_eq!(path.name(), var_name);
+        assert_eq!(path.directories().count(), 2);
+
+        env::set_var(var_name, "/usr");
+        let path = Simpath::from_env_or(var_name, &["/", "/tmp"]);
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr")));
+    }
+
+    #[test]
+    fn entry_sources_recorded() {
+        let var_name = "SourcesPath";
+        env::remove_var(var_name);
+        let mut path = Simpath::from_env_or(var_name, &["/"]);
+        path.add(".");
+        assert_eq!(path.entry_sources(), vec!(("/".to_string(), &EntrySource::Default),
+                                              (".".to_string(), &EntrySource::Programmatic)));
+    }
+
+    #[test]
+    fn system_path_is_not_empty() {
+        assert!(!Simpath::system_path().is_empty());
+    }
+
+    #[test]
+    fn from_multiple_env_vars() {
+        env::set_var("FirstPath", format!("/tmp{}/", DEFAULT_SEPARATOR_CHAR));
+        env::set_var("SecondPath", format!("/{}/usr", DEFAULT_SEPARATOR_CHAR));
+        env::remove_var("ThirdPath");
+        let mut path = Simpath::from_env_vars(&["FirstPath", "SecondPath", "ThirdPath"]);
+        path.add_directory("/var");
+        assert_eq!(path.name(), "FirstPath,SecondPath,ThirdPath");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/tmp"), PathBuf::from("/"),
+                                            PathBuf::from("/usr"), PathBuf::from("/var")));
+        let sources: Vec<_> = path.entries().iter().map(|entry| entry.source().clone()).collect();
+        assert_eq!(sources, vec!(EntrySource::EnvVar("FirstPath".into()), EntrySource::EnvVar("FirstPath".into()),
+                                 EntrySource::EnvVar("SecondPath".into()), EntrySource::Programmatic));
+    }
+
     #[test]
     fn display_a_simpath_with_entries() {
         let var_name = "MyPath";
@@ -696,78 +4344,220 @@ mod test {
         println!("Simpath can be printed: {}", path);
     }
 
-    #[cfg(feature = "urls")]
-    mod url_tests {
-        use std::env;
-        use url::Url;
-        use super::super::FileType;
-        use super::Simpath;
-
-        const BASE_URL: &str = "https://www.ibm.com";
-        const EXISTING_RESOURCE: &str = "es-es";
-
-        #[test]
-        fn create_from_env() {
-            let var_name = "MyPath";
-            env::set_var(var_name, BASE_URL);
-            let path = Simpath::new_with_separator(var_name, ',');
-            assert_eq!(path.urls().len(), 1);
-            assert_eq!(path.directories().len(), 0);
-            assert!(path.urls().contains(&Url::parse(BASE_URL)
-                .expect("Could not parse URL")));
-        }
-
-        #[test]
-        fn add_url_that_exists() {
-            let mut path = Simpath::new_with_separator("test", ',');
-            path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
-            assert_eq!(path.urls().len(), 1);
-            assert_eq!(path.directories().len(), 0);
-            assert!(path.urls().contains(&Url::parse(BASE_URL)
-                .expect("Could not parse URL")));
-        }
-
-        #[test]
-        fn cannot_add_same_url_twice() {
-            let mut path = Simpath::new_with_separator("test", ',');
-            path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
-            path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
-            assert_eq!(path.urls().len(), 1);
-            assert_eq!(path.directories().len(), 0);
-            assert!(path.urls().contains(&Url::parse(BASE_URL)
-                .expect("Could not parse URL")));
-        }
-
-        #[test]
-        fn find_resource_not_exist() {
-            let mut search_path = Simpath::new("TEST");
-            search_path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
-            assert!(search_path.find_type("/no-way-this-exists", FileType::Resource).is_err(),
-                    "should not find the resource");
-        }
-
-        #[test]
-        fn find_existing_resource() {
-            let mut search_path = Simpath::new("TEST");
-            search_path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url")
-                .join(EXISTING_RESOURCE).expect("Could not join to Url"));
-            search_path.find_type(EXISTING_RESOURCE, FileType::Resource).expect("Could not find resource");
-        }
-
-        #[test]
-        fn contains_url_that_exists() {
-            let var_name = "MyPath";
-            env::set_var(var_name, BASE_URL);
-            let path = Simpath::new_with_separator(var_name, ',');
-            assert!(path.contains(BASE_URL));
-        }
-
-        #[test]
-        fn display_path_with_directory_and_url() {
-            let var_name = "MyPath";
-            env::set_var(var_name, format!("~,{}", BASE_URL));
-            let path = Simpath::new_with_separator(var_name, ',');
-            println!("{}", path);
-        }
-    }
-}
\ No newline at end of file
+    #[test]
+    fn walk_all_files() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("shadowing")).unwrap();
+        fs::File::create(second_dir.join("shadowing")).unwrap();
+        fs::File::create(second_dir.join("other")).unwrap();
+        fs::create_dir(second_dir.join("subdir")).unwrap();
+        fs::File::create(second_dir.join("subdir").join("nested")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+
+        let files: Vec<_> = path.walk().map(|file| file.unwrap()).collect();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0], first_dir.join("shadowing"));
+
+        let files: Vec<_> = path.walk().unique(true).map(|file| file.unwrap()).collect();
+        assert_eq!(files.len(), 2);
+        assert!(!files.contains(&second_dir.join("shadowing")));
+
+        let files: Vec<_> = path.walk().recursive(true).map(|file| file.unwrap()).collect();
+        assert_eq!(files.len(), 4);
+        assert!(files.contains(&second_dir.join("subdir").join("nested")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn list_files_with_extension() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("plugin.wasm")).unwrap();
+        fs::File::create(first_dir.join("plugin.so")).unwrap();
+        fs::File::create(second_dir.join("plugin.wasm")).unwrap();
+        fs::File::create(second_dir.join("other.wasm")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+
+        let plugins = path.list_with_extension("wasm", false).expect("Could not list files");
+        assert_eq!(plugins.len(), 3);
+        assert_eq!(plugins[0], first_dir.join("plugin.wasm"));
+
+        let plugins = path.list_with_extension("wasm", true).expect("Could not list files");
+        assert_eq!(plugins.len(), 2);
+        assert!(plugins.contains(&second_dir.join("other.wasm")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn validate_report_lists_invalid() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/");
+        path.add_directory("/no/such/directory");
+        let report = path.validate_report();
+        assert_eq!(report.valid, vec!("/".to_string()));
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.invalid[0].entry, "/no/such/directory");
+    }
+
+    #[test]
+    fn find_in_fallback_chain() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("override")).unwrap();
+        fs::File::create(second_dir.join("override")).unwrap();
+        fs::File::create(second_dir.join("default")).unwrap();
+
+        let mut primary = Simpath::new("Primary");
+        primary.add_directory(&first_dir);
+        let mut fallback = Simpath::new("Fallback");
+        fallback.add_directory(&second_dir);
+        let chain = primary.with_fallback(fallback).with_fallback(Simpath::new("Empty"));
+
+        assert_eq!(chain.levels().len(), 3);
+        assert_eq!(chain.find("override").unwrap(), (0, FoundType::File(first_dir.join("override"))));
+        assert_eq!(chain.find_type("default", FileType::File).unwrap(),
+                   (1, FoundType::File(second_dir.join("default"))));
+        assert_eq!(chain.find("missing").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn validate_missing_directory() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/");
+        path.add_directory("/no/such/directory");
+        assert_eq!(path.validate(), vec!(PathError::DoesNotExist("/no/such/directory".into())));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn create_from_toml_config() {
+        let temp_dir = temp_dir();
+        let config_path = temp_dir.join("path.toml");
+        fs::write(&config_path, format!("name = \"ConfigPath\"\n\
+                                         directories = [\"{}\", \"/\"]\n\
+                                         required_access = \"executable\"\n",
+                                        temp_dir.display())).unwrap();
+
+        let path = Simpath::from_config(&config_path).expect("Could not load config");
+        assert_eq!(path.name(), "ConfigPath");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(temp_dir.clone(), PathBuf::from("/")));
+        assert_eq!(path.required_access(), Access::Executable);
+        assert!(!path.posix_empty_entries());
+        assert_eq!(path.entries()[0].source(), &EntrySource::ConfigFile(config_path.clone()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn create_from_json_config() {
+        let temp_dir = temp_dir();
+        let config_path = temp_dir.join("path.json");
+        fs::write(&config_path, r#"{ "separator": ",", "directories": ["/"], "posix_empty_entries": true }"#)
+            .unwrap();
+
+        let path = Simpath::from_config(&config_path).expect("Could not load config");
+        assert_eq!(path.separator(), ',');
+        assert!(path.contains("/"));
+        assert!(path.posix_empty_entries());
+
+        fs::write(&config_path, r#"{ "directories": "/" }"#).unwrap();
+        assert_eq!(Simpath::from_config(&config_path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(all(feature = "config", feature = "urls"))]
+    #[test]
+    fn resolve_from_config() {
+        let temp_dir = temp_dir();
+        let config_path = temp_dir.join("path.toml");
+        fs::write(&config_path, "resolve = [\"mirror.example.com:443:[::1]\"]\n").unwrap();
+        assert!(Simpath::from_config(&config_path).is_ok());
+
+        for resolve in &["mirror.example.com:443", "mirror.example.com:https:10.0.0.5", ":443:10.0.0.5"] {
+            fs::write(&config_path, format!("resolve = [\"{}\"]\n", resolve)).unwrap();
+            assert_eq!(Simpath::from_config(&config_path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        }
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(all(unix, feature = "xdg"))]
+    #[test]
+    fn xdg_paths_from_env_and_defaults() {
+        env::set_var("XDG_DATA_HOME", "/data/home");
+        env::set_var("XDG_DATA_DIRS", "/data/one:relative:/data/two");
+        let path = Simpath::xdg_data();
+        assert_eq!(path.name(), "XDG_DATA_DIRS");
+        assert_eq!(path.directories().collect::<Vec<_>>(), vec!(PathBuf::from("/data/home"), PathBuf::from("/data/one"),
+                                            PathBuf::from("/data/two")));
+
+        env::remove_var("XDG_CONFIG_DIRS");
+        env::set_var("XDG_CONFIG_HOME", "");
+        let path = Simpath::xdg_config();
+        assert_eq!(path.directories().last(), Some(Path::new("/etc/xdg")));
+        if let Ok(home) = env::var("HOME") {
+            assert_eq!(path.directories().next(), Some(PathBuf::from(home).join(".config").as_path()));
+        }
+
+        env::set_var("XDG_CACHE_HOME", "/cache/home");
+        assert_eq!(Simpath::xdg_cache().directories().collect::<Vec<_>>(), vec!(PathBuf::from("/cache/home")));
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn find_highest_matching_version() {
+        let temp_dir = temp_dir();
+        for filename in &["tool-1.0.0", "tool-1.2.3", "tool-2.0.0.exe", "tool-latest", "other-3.0.0"] {
+            fs::File::create(temp_dir.join(filename)).unwrap();
+        }
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        let (best, candidates) = path.find_versioned("tool",
+                                                     &semver::VersionReq::parse("^1").unwrap())
+            .expect("Could not find a matching version");
+        assert_eq!(best.version, semver::Version::new(1, 2, 3));
+        assert_eq!(best.path, temp_dir.join("tool-1.2.3"));
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].version, semver::Version::new(2, 0, 0));
+
+        assert!(path.find_versioned("tool", &semver::VersionReq::parse(">=3").unwrap()).is_err());
+
+        // A missing directory is passed to the error handler, and excluded files are not candidates
+        let mut path = Simpath::new("MyName");
+        path.add_directory(temp_dir.join("missing"));
+        path.add_directory(&temp_dir);
+        assert!(path.find_versioned("tool", &semver::VersionReq::parse("^1").unwrap()).is_err());
+        path.on_error(|_, _| {});
+        path.add_exclude("tool-1.2.3");
+        let (best, _) = path.find_versioned("tool", &semver::VersionReq::parse("^1").unwrap())
+            .expect("Could not find a matching version");
+        assert_eq!(best.path, temp_dir.join("tool-1.0.0"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+}
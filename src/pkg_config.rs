@@ -0,0 +1,129 @@
+//! Finding `pkg-config` `.pc` files in a `Simpath`, such as one created by
+//! `Simpath::pkg_config_path()`: the file `pkg-config` will use for a package, the entry it came
+//! from, and the files for the same package that it shadows.
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use super::{Entry, FileType, FoundType, PathName, Simpath};
+
+/// `PcFile` is a `pkg-config` `.pc` file for a package found in a `Simpath`, as returned by
+/// `Simpath::find_pc_file()` and `Simpath::pc_files()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcFile {
+    /// The name of the package, e.g. `zlib` for `zlib.pc`
+    pub package: String,
+    /// The `.pc` file that is used for the package, the first in search order
+    pub path: PathBuf,
+    /// The entry of the search path that the file was found in
+    pub entry: Entry,
+    /// The `.pc` files for the same package in later entries, which are shadowed by `path`
+    pub shadowed: Vec<PathBuf>,
+}
+
+impl Simpath {
+    /// Find the `.pc` file for `package` (e.g. `zlib.pc` for `zlib`), as `pkg-config` does, along
+    /// with the entry it was found in and the files for the package in later entries that it
+    /// shadows. If there is none then a `NotFound` error is returned, and if `package` is not a
+    /// plain name an `InvalidInput` error.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::pkg_config_path();
+    ///     match search_path.find_pc_file("zlib") {
+    ///         Ok(pc_file) => println!("'{}' from {} shadows {:?}", pc_file.path.display(),
+    ///                                 pc_file.entry.source(), pc_file.shadowed),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_pc_file(&self, package: &str) -> Result<PcFile, Error> {
+        if package.is_empty() || Path::new(package).components().count() != 1
+            || Path::new(package).file_name() != Some(OsStr::new(package)) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("'{}' is not the name of a package", package)));
+        }
+
+        let mut found = self.find_all(&format!("{}.pc", package), FileType::File)?.into_iter()
+            .filter_map(|found| match found {
+                FoundType::File(path) => Some(path),
+                _ => None,
+            });
+        found.next()
+            .and_then(|path| {
+                let entry = self.entry_containing(&path)?;
+                Some(PcFile { package: package.to_string(), path, entry, shadowed: found.collect() })
+            })
+            .ok_or_else(|| Error::new(ErrorKind::NotFound,
+                                      format!("Could not find '{}.pc' in {}", package, PathName(&self.name))))
+    }
+
+    /// Get the `.pc` files for all the packages in the search path, in search order, each with
+    /// the files for the same package in later entries that it shadows. The entries that cannot
+    /// be read are reported to the error handler and skipped, and files that are excluded from
+    /// searches are left out.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::pkg_config_path();
+    ///     for pc_file in search_path.pc_files().iter().filter(|pc_file| !pc_file.shadowed.is_empty()) {
+    ///         println!("'{}' shadows {:?}", pc_file.path.display(), pc_file.shadowed);
+    ///     }
+    /// }
+    /// ```
+    pub fn pc_files(&self) -> Vec<PcFile> {
+        let mut pc_files: Vec<PcFile> = vec!();
+        let mut index_by_package: HashMap<String, usize> = HashMap::new();
+
+        for entry in self.entries().iter().filter(|entry| entry.is_enabled()) {
+            let dir = match entry.path() {
+                Some(dir) => dir,
+                None => continue,
+            };
+            let dir_exclusions = self.dir_exclusions(dir);
+            let dir_entries = match fs::read_dir(dir) {
+                Ok(dir_entries) => dir_entries,
+                Err(e) => {
+                    self.notify_error(dir, &e);
+                    continue;
+                }
+            };
+
+            let mut paths: Vec<PathBuf> = dir_entries.flatten()
+                .filter(|dir_entry| !self.excluded(&dir_exclusions, dir_entry))
+                .map(|dir_entry| dir_entry.path())
+                .filter(|path| path.extension() == Some(OsStr::new("pc")) && path.is_file())
+                .collect();
+            paths.sort();
+
+            for path in paths {
+                let package = match path.file_stem() {
+                    Some(stem) => stem.to_string_lossy().to_string(),
+                    None => continue,
+                };
+                match index_by_package.get(&package) {
+                    Some(&index) => pc_files[index].shadowed.push(path),
+                    None => {
+                        index_by_package.insert(package.clone(), pc_files.len());
+                        pc_files.push(PcFile { package, path, entry: entry.clone(), shadowed: vec!() });
+                    }
+                }
+            }
+        }
+
+        pc_files
+    }
+
+    // Get the entry that `path` was found in
+    fn entry_containing(&self, path: &Path) -> Option<Entry> {
+        self.entries().iter().find(|entry| entry.path() == path.parent()).cloned()
+    }
+}
@@ -0,0 +1,2429 @@
+//! Searching a `Simpath`: the `SearchEntry` trait implemented by each kind of entry in it, the
+//! finds built on it, the matchers for names, and statistics about searches.
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "digest")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{Access, Entry, FileType, FileTypes, FoundType, PathName, Simpath, SkippedEntry, NO_FILE_SYSTEM};
+use super::directory::EntryKind;
+
+#[cfg(any(all(not(unix), not(target_os = "wasi")), test))]
+// The extensions of executable files when `PATHEXT` is not set, as used by cmd.exe
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+// The number of leading bytes of a file's contents passed to a `find_where_contents()` predicate
+const CONTENTS_PREFIX_LEN: usize = 4096;
+
+// The maximum number of symbolic links followed to find the target of a dangling link, as a
+// chain of links may loop
+const MAX_LINKS_FOLLOWED: usize = 40;
+
+#[cfg(feature = "suggest")]
+// The maximum number of close names suggested when a name cannot be found
+const MAX_SUGGESTIONS: usize = 3;
+
+// A `SearchEntry` is an entry of a search path that can be searched for files or resources by
+// name, such as a directory or (with the "urls" feature) a `Url`. It is implemented by each kind
+// of entry that a `Simpath` can contain.
+pub(crate) trait SearchEntry {
+    // Get the entry as it is written in a search path, e.g. by `Simpath::to_env_string()`
+    fn location(&self) -> String;
+
+    // Search the entry for entries called `file_name` of `file_type`, adding those found to
+    // `context`, which has the `Simpath` being searched (whose settings should be respected)
+    // and records what was searched, compared and skipped
+    fn search(&self, context: &mut SearchContext<'_>, file_name: &str, file_type: &FileType)
+        -> Result<(), Error>;
+
+    // Search the entry for entries of `file_type` whose names are matched by `matcher`, adding
+    // those found to `context`
+    fn search_where(&self, context: &mut SearchContext<'_>, matcher: &dyn NameMatcher, file_type: &FileType)
+        -> Result<(), Error>;
+
+    #[cfg(feature = "urls")]
+    // Return true if `found` was found in this entry
+    fn contains(&self, found: &FoundType) -> bool;
+
+    // Get the subdirectories of this entry that are searched when a search recurses into it,
+    // in name order, including those that are symbolic links if `follow_symlinks` is true.
+    // Entries that have no subdirectories (such as a `Url`) return none.
+    fn subdirectories(&self, _context: &SearchContext<'_>, _follow_symlinks: bool) -> Vec<PathBuf> {
+        vec!()
+    }
+}
+
+// `SearchContext` is passed to each `SearchEntry` searched when a `Simpath` is searched. It
+// gives access to the `Simpath`, collects the entries found and records what was searched.
+pub(crate) struct SearchContext<'a> {
+    pub(crate) simpath: &'a Simpath,
+    trace: Option<&'a mut SearchTrace>,
+    pub(crate) found: Vec<FoundType>,
+    // The first entry that matched but could not be accessed, reported if nothing else is found
+    denied: Option<Error>,
+    pub(crate) files_scanned: usize,
+}
+
+impl<'a> SearchContext<'a> {
+    // Create a context for a search of `simpath`, recording what is searched in `trace` if supplied
+    fn new(simpath: &'a Simpath, trace: Option<&'a mut SearchTrace>) -> Self {
+        SearchContext { simpath, trace, found: vec!(), denied: None, files_scanned: 0 }
+    }
+
+    // Add an entry that was found
+    pub(crate) fn add_found(&mut self, found: FoundType) {
+        self.found.push(found);
+    }
+
+    // Record that an entry matched but that the current user does not have the access required
+    // to it. The first such error is returned if nothing is found.
+    pub(crate) fn denied(&mut self, error: Error) {
+        self.denied.get_or_insert(error);
+    }
+
+    // Record that `entry` was searched
+    pub(crate) fn searched<D: fmt::Display>(&mut self, entry: D) {
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.report.searched.push(entry.to_string());
+        }
+    }
+
+    // Record that `entry` was compared with the name searched for
+    pub(crate) fn compared<D: fmt::Display>(&mut self, entry: D) {
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.compared.push(entry.to_string());
+        }
+    }
+
+    // Record that `entry` was skipped, and why
+    pub(crate) fn skipped<D: fmt::Display>(&mut self, entry: D, reason: String) {
+        trace_event!(debug, entry = %entry, reason = %reason, "skipped entry");
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.report.skipped.push(SkippedEntry { entry: entry.to_string(), reason });
+        }
+    }
+
+    // Finish the search, returning the entries found, or the error to report if none were
+    fn finish<F>(self, not_found: F) -> Result<Vec<FoundType>, Error> where F: FnOnce() -> Error {
+        if !self.found.is_empty() {
+            return Ok(self.found);
+        }
+
+        Err(self.denied.unwrap_or_else(not_found))
+    }
+}
+
+/// `FindReport` describes a search of a `Simpath` for entries of a given name, as returned by
+/// `Simpath::find_report()`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FindReport {
+    /// The name of the `Simpath` that was searched
+    pub search_path: String,
+    /// The name that was searched for
+    pub name: String,
+    /// The type of entry that was searched for
+    pub file_type: Option<FileType>,
+    /// The directories and URLs that were searched, in search order
+    pub searched: Vec<String>,
+    /// The entries that matched, in search order
+    pub matched: Vec<String>,
+    /// The entries that were skipped, and why
+    pub skipped: Vec<SkippedEntry>,
+    /// The error that ended the search early, if there was one
+    pub error: Option<String>,
+}
+
+/// `SearchTrace` is a trace of a search of a `Simpath` for an entry, as returned by
+/// `Simpath::find_traced()`, for diagnosing why an entry was or was not found
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SearchTrace {
+    /// What was searched, what matched and what was skipped (e.g. of the wrong type, not
+    /// executable or not readable), and why
+    pub report: FindReport,
+    /// The entries that were compared with the name searched for, in the order they were visited:
+    /// the files in each directory searched, the paths checked for a relative path, and URLs
+    pub compared: Vec<String>,
+}
+
+/// A `NameMatcher` decides which file names match when searching using `Simpath::find_where()`.
+///
+/// Matchers are provided for exact (`ExactName`), case-insensitive (`CaseInsensitiveName`) and
+/// glob (`GlobName`) matching, and for regular expressions (`regex::Regex`) when the "regex"
+/// feature is enabled. Any closure that takes a `&str` and returns a `bool` is also a
+/// `NameMatcher`, and it can be implemented to provide other matching semantics.
+pub trait NameMatcher {
+    /// Return true if `file_name` matches
+    fn matches(&self, file_name: &str) -> bool;
+}
+
+impl<F> NameMatcher for F where F: Fn(&str) -> bool {
+    fn matches(&self, file_name: &str) -> bool {
+        self(file_name)
+    }
+}
+
+/// `ExactName` is a `NameMatcher` that matches file names equal to a name
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExactName(String);
+
+impl ExactName {
+    /// Create an `ExactName` matcher for `name`
+    pub fn new(name: &str) -> Self {
+        ExactName(name.to_string())
+    }
+}
+
+impl NameMatcher for ExactName {
+    fn matches(&self, file_name: &str) -> bool {
+        file_name == self.0
+    }
+}
+
+/// `CaseInsensitiveName` is a `NameMatcher` that matches file names equal to a name, ignoring case
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseInsensitiveName(String);
+
+impl CaseInsensitiveName {
+    /// Create a `CaseInsensitiveName` matcher for `name`
+    pub fn new(name: &str) -> Self {
+        CaseInsensitiveName(name.to_lowercase())
+    }
+}
+
+impl NameMatcher for CaseInsensitiveName {
+    fn matches(&self, file_name: &str) -> bool {
+        file_name.to_lowercase() == self.0
+    }
+}
+
+/// `GlobName` is a `NameMatcher` that matches file names against a glob pattern, where `*`
+/// matches any number of characters and `?` matches any single character
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobName(Vec<char>);
+
+impl GlobName {
+    /// Create a `GlobName` matcher for `pattern` (e.g. `lib*.so`)
+    pub fn new(pattern: &str) -> Self {
+        GlobName(pattern.chars().collect())
+    }
+}
+
+impl NameMatcher for GlobName {
+    fn matches(&self, file_name: &str) -> bool {
+        glob_match(&self.0, &file_name.chars().collect::<Vec<char>>())
+    }
+}
+
+#[cfg(feature = "regex")]
+impl NameMatcher for regex::Regex {
+    fn matches(&self, file_name: &str) -> bool {
+        self.is_match(file_name)
+    }
+}
+
+// Match `name` against a glob `pattern`, backtracking to the last `*` on a mismatch
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // The position in the pattern after the last `*` seen, and the position in the name it matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        // A `*` is always a wildcard, even where the name has a `*` too
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+        } else if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// `CancellationToken` can be used to cancel the searches of the `Simpath`s it is set on using
+/// `Simpath::set_cancellation_token()`, from another thread. Clones of a token share the same
+/// state, so cancelling one cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new `CancellationToken` that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the searches using this token. Searches in progress stop before searching their
+    /// next entry and return an `Interrupted` error, as do all later searches.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if this token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// `SearchOptions` are the options of a search made with `Simpath::find_with()`. They are built
+/// by chaining the setting of each option onto `SearchOptions::new()`, which has the same
+/// options as `Simpath::find()`: a case-sensitive search for a `FileType::Any` that follows
+/// symbolic links, in the entries themselves and not their subdirectories, without a limit on
+/// the number of results or the time taken.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::{FileType, SearchOptions};
+/// use std::time::Duration;
+///
+/// fn main() {
+///     let options = SearchOptions::new().case_sensitive(false).depth(2)
+///         .file_types(FileType::File | FileType::Directory).max_results(1)
+///         .timeout(Duration::from_secs(5));
+///     println!("{:?}", options);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+    case_sensitive: bool,
+    follow_symlinks: bool,
+    depth: usize,
+    file_types: FileTypes,
+    max_results: usize,
+    timeout: Option<Duration>,
+    reject_dangling_links: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            case_sensitive: true,
+            follow_symlinks: true,
+            depth: 0,
+            file_types: FileTypes::from(FileType::Any),
+            max_results: usize::MAX,
+            timeout: None,
+            reject_dangling_links: false,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Create `SearchOptions` for a search like that of `Simpath::find()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether names must match the name searched for exactly (the default), or may differ
+    /// from it in case. Case-insensitive searches are for names, not for paths.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Set whether symbolic links are followed (the default). If they are not, a link is only
+    /// found as a `FileType::Symlink`, and links to directories are not recursed into.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Set the number of levels of subdirectories of each entry that are also searched, after
+    /// the entry itself. The default of 0 searches only the entries.
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Set the `FileType`, or set of them, to search for
+    pub fn file_types<T: Into<FileTypes>>(mut self, file_types: T) -> Self {
+        self.file_types = file_types.into();
+        self
+    }
+
+    /// Set the maximum number of entries found that are returned. The search stops once it
+    /// has found them.
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Set the time after which the search is given up with a `TimedOut` error, as
+    /// `Simpath::find_with_deadline()` does
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set whether finding a symbolic link whose target does not exist is a `NotFound` error,
+    /// rather than the link being returned (the default)
+    pub fn reject_dangling_links(mut self, reject_dangling_links: bool) -> Self {
+        self.reject_dangling_links = reject_dangling_links;
+        self
+    }
+}
+
+/// `SearchStats` is a snapshot of the statistics collected by a `Simpath` since `enable_stats()`
+/// was called, as returned by `Simpath::stats()`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SearchStats {
+    /// The number of searches performed
+    pub searches: u64,
+    /// The number of directories scanned
+    pub directories_scanned: u64,
+    /// The number of files examined
+    pub files_examined: u64,
+    /// The number of Urls checked
+    pub url_checks: u64,
+    /// The number of lookups of a `FrozenSimpath` that found the name in its index
+    pub cache_hits: u64,
+    /// The number of lookups of a `FrozenSimpath` that did not find the name in its index
+    pub cache_misses: u64,
+    /// The number of searches that found a match in each entry (directory or Url)
+    pub entry_hits: HashMap<String, u64>,
+}
+
+impl SearchStats {
+    /// Get the fraction of `FrozenSimpath` lookups that found the name in its index, or `None`
+    /// if there have been no lookups
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups == 0 {
+            None
+        } else {
+            Some(self.cache_hits as f64 / lookups as f64)
+        }
+    }
+}
+
+// The statistics that can be counted by a `StatsCollector`
+#[derive(Clone, Copy)]
+pub(crate) enum Stat {
+    Search,
+    Directory,
+    File,
+    Url,
+    CacheHit,
+    CacheMiss,
+}
+
+// Collects the statistics of the searches of a `Simpath`. It is shared by clones of the
+// `Simpath` and by `FrozenSimpath`s created from it.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCollector {
+    counters: [AtomicU64; 6],
+    entry_hits: Mutex<HashMap<String, u64>>,
+}
+
+#[cfg(feature = "suggest")]
+// The Levenshtein distance between `from` and `to`: the number of characters that need to be
+// inserted, deleted or substituted to change one into the other
+fn edit_distance(from: &str, to: &str) -> usize {
+    let to: Vec<char> = to.chars().collect();
+    let mut previous: Vec<usize> = (0..=to.len()).collect();
+    for (i, from_char) in from.chars().enumerate() {
+        let mut current = vec!(i + 1);
+        for (j, to_char) in to.iter().enumerate() {
+            let substitution = previous[j] + usize::from(from_char != *to_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[to.len()]
+}
+
+impl Simpath {
+    /// Start collecting statistics about the searches of this `Simpath` (the number of
+    /// searches, directories scanned, files examined, Urls checked, index hits and misses and how
+    /// often each entry had a match), which can be read using `stats()`. Clones of the `Simpath`,
+    /// and `FrozenSimpath`s created from it, add to the same statistics. Calling it again resets
+    /// the statistics.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     search_path.enable_stats();
+    ///     let _ = search_path.find("ls");
+    ///     if let Some(stats) = search_path.stats() {
+    ///         println!("Scanned {} directories", stats.directories_scanned);
+    ///     }
+    /// }
+    /// ```
+    pub fn enable_stats(&mut self) {
+        self.stats = Some(Arc::new(StatsCollector::default()));
+    }
+
+    /// Get the statistics collected since `enable_stats()` was called, or `None` if it has not been
+    pub fn stats(&self) -> Option<SearchStats> {
+        self.stats.as_ref().map(|stats| {
+            let count = |stat: Stat| stats.counters[stat as usize].load(Ordering::Relaxed);
+            SearchStats {
+                searches: count(Stat::Search),
+                directories_scanned: count(Stat::Directory),
+                files_examined: count(Stat::File),
+                url_checks: count(Stat::Url),
+                cache_hits: count(Stat::CacheHit),
+                cache_misses: count(Stat::CacheMiss),
+                entry_hits: stats.entry_hits.lock().unwrap_or_else(PoisonError::into_inner).clone(),
+            }
+        })
+    }
+
+    // Count a statistic, if statistics are being collected
+    pub(crate) fn count(&self, stat: Stat) {
+        if let Some(stats) = &self.stats {
+            stats.counters[stat as usize].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Count a search that found a match in `entry`, if statistics are being collected
+    pub(crate) fn count_hit(&self, entry: String) {
+        if let Some(stats) = &self.stats {
+            *stats.entry_hits.lock().unwrap_or_else(PoisonError::into_inner).entry(entry).or_insert(0) += 1;
+        }
+    }
+
+    // Get the entries to be searched (those enabled), in search order
+    pub(crate) fn search_entries(&self) -> Vec<&dyn SearchEntry> {
+        self.entries.iter().filter(|entry| entry.is_enabled()).filter_map(|entry| match entry {
+            Entry::Directory(_) if NO_FILE_SYSTEM => None,
+            Entry::Directory(directory) => Some(&directory.path as &dyn SearchEntry),
+            #[cfg(feature = "urls")]
+            Entry::Url(url_entry) => Some(&url_entry.url as &dyn SearchEntry),
+        }).collect()
+    }
+
+    /// Try to find a file or resource by name (not full path) on a search path.
+    /// The name can be a relative path with multiple components (e.g. `share/app/config.toml`)
+    /// in which case it is joined to each directory in the search path in turn.
+    /// If the name is an absolute path then it is checked directly, without searching the path.
+    /// Searching for a file could cause errors, so Result<FoundType, io::Error> is returned
+    /// If it is found `Ok(FoundType)` is returned indicating where the resource/file can be found.
+    /// If it is not found then `Err` is returned. If an entry of that name was found, or a directory
+    /// could not be searched, but the current user does not have the required access, the error
+    /// will be of kind `PermissionDenied`, otherwise it will be `NotFound`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find("my-file") {
+    ///         Ok(_found_dir) => println!("Didn't expect that!!"),
+    ///         Err(e)         => println!("{}", e.to_string())
+    ///     }
+    /// }
+    /// ```
+    pub fn find(&self, file_name: &str) -> Result<FoundType, Error> {
+        self.find_type(file_name, FileType::Any)
+    }
+
+    /// Check if an entry called `file_name` can be found, as `find()` would find it, without
+    /// creating the entry found or an error. Where the settings of the search path allow, each
+    /// directory is checked by joining the name to it rather than reading it, so on file systems
+    /// that are not case-sensitive a name that differs in case from the entry is also found.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     if search_path.exists("ls") {
+    ///         println!("'ls' is on the PATH");
+    ///     }
+    /// }
+    /// ```
+    pub fn exists(&self, file_name: &str) -> bool {
+        self.exists_type(file_name, FileType::Any)
+    }
+
+    /// Check if an entry of a specific `FileType` called `file_name` can be found, as
+    /// `find_type()` would find it, without creating the entry found or an error, as `exists()`
+    /// does
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("{}", search_path.exists_type("ls", FileType::File));
+    /// }
+    /// ```
+    pub fn exists_type(&self, file_name: &str, file_type: FileType) -> bool {
+        if Path::new(file_name).is_absolute() {
+            return matches!(self.found_type(PathBuf::from(file_name), &file_type), Ok(Some(_)));
+        }
+
+        let relative_path = match Self::relative_path(file_name) {
+            Ok(Some(relative_path)) => relative_path,
+            Ok(None) if self.can_join_name(file_name, &file_type) => PathBuf::from(file_name),
+            Ok(None) => return self.search_without_suggestions(file_name, &file_type, false).is_ok(),
+            Err(_) => return false,
+        };
+
+        self.count(Stat::Search);
+        for (searches, dir) in self.search_dirs().enumerate() {
+            if self.check_search_limit(searches).is_err() || self.check_cancelled(file_name).is_err() {
+                return false;
+            }
+            if self.may_contain(dir, &relative_path)
+                && matches!(self.found_type(dir.join(&relative_path), &file_type), Ok(Some(_))) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Check if an entry called `file_name` would be found by joining the name to each directory,
+    // rather than comparing it to the names of the files in them, and that no Url could be found
+    fn can_join_name(&self, file_name: &str, file_type: &FileType) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.unicode_normalization {
+            return false;
+        }
+
+        #[cfg(feature = "urls")]
+        if self.has_urls() && (*file_type == FileType::Any || *file_type == FileType::Resource) {
+            return false;
+        }
+
+        *file_type != FileType::Resource && !self.has_exclusions()
+            && Path::new(file_name).file_name() == Some(OsStr::new(file_name))
+    }
+
+    /// find an entry of a specific `FileType` in a `Path`
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     use simpath::FileType;
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_type("my-file", FileType::Directory) {
+    ///         Ok(_found_dir) => println!("Didn't expect that!!"),
+    ///         Err(e)         => println!("{}", e.to_string())
+    ///     }
+    /// }
+    /// ```
+    pub fn find_type(&self, file_name: &str, file_type: FileType) -> Result<FoundType, Error> {
+        self.search(file_name, &file_type, false, None).map(|mut found| found.remove(0))
+    }
+
+    /// Find the first entry called `file_name` of any of a set of `FileTypes`, in search order.
+    /// In each directory the types are tried in the order they are in the set, so that with
+    /// `FileType::Symlink | FileType::File` a symbolic link is found as a `Symlink`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_types("my-tool", FileType::File | FileType::Directory) {
+    ///         Ok(found) => println!("'my-tool' was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_types<T: Into<FileTypes>>(&self, file_name: &str, file_types: T) -> Result<FoundType, Error> {
+        self.search_types(file_name, file_types.into().types(), false, None, true).map(|mut found| found.remove(0))
+    }
+
+    /// Find all the entries called `file_name` of any of a set of `FileTypes`, in search order,
+    /// in one search instead of one for each type. An entry that is of more than one of the types
+    /// is only returned once, as the first of them in the set.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("{:?}", search_path.find_all_types("python3", &[FileType::File, FileType::Directory][..]));
+    /// }
+    /// ```
+    pub fn find_all_types<T: Into<FileTypes>>(&self, file_name: &str, file_types: T) -> Result<Vec<FoundType>, Error> {
+        self.search_types(file_name, file_types.into().types(), true, None, true)
+    }
+
+    /// Find all the entries of a specific `FileType` in a `Path` with the name `file_name`, in
+    /// search order. If none are found then `Err` is returned, as for `find_type()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_all("ls", FileType::File) {
+    ///         Ok(found) => println!("'ls' was found at '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_all(&self, file_name: &str, file_type: FileType) -> Result<Vec<FoundType>, Error> {
+        self.search(file_name, &file_type, true, None)
+    }
+
+    /// Find all the entries of a specific `FileType` with the name `file_name` as `find_all()`
+    /// does, but report each underlying file once: entries that are the same file (the same
+    /// device and inode on unix, or volume and file ID on windows), such as hard links or
+    /// directories reached through symbolic links, are returned as aliases of the first of them
+    /// in search order. Elsewhere the entries found are all distinct.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     if let Ok(found) = search_path.find_all_distinct("ls", FileType::File) {
+    ///         for (found, aliases) in found {
+    ///             println!("'ls' was found at '{:?}', also reachable as {:?}", found, aliases);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn find_all_distinct(&self, file_name: &str, file_type: FileType)
+                             -> Result<Vec<(FoundType, Vec<PathBuf>)>, Error> {
+        let mut distinct: Vec<(FoundType, Vec<PathBuf>)> = vec!();
+        let mut files: HashMap<(u64, u64), usize> = HashMap::new();
+
+        for found in self.search(file_name, &file_type, true, None)? {
+            let file_id = found.path().and_then(Self::file_id);
+            match file_id.and_then(|file_id| files.get(&file_id)) {
+                Some(&index) => {
+                    if let Some(path) = found.path() {
+                        distinct[index].1.push(path.to_path_buf());
+                    }
+                }
+                None => {
+                    if let Some(file_id) = file_id {
+                        files.insert(file_id, distinct.len());
+                    }
+                    distinct.push((found, vec!()));
+                }
+            }
+        }
+
+        Ok(distinct)
+    }
+
+    /// Find all the entries called `file_name`, as `find_all()` does with `FileType::Any`, and
+    /// return the one given the highest score by `score`. This can be used to express policies
+    /// such as preferring the most recently modified file, or files under a certain directory.
+    /// If several entries have the highest score the first of them in search order is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FoundType, Simpath};
+    /// use std::time::SystemTime;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let newest = search_path.find_best("ls", |candidate| match candidate {
+    ///         FoundType::File(path) => path.metadata().and_then(|metadata| metadata.modified()).ok(),
+    ///         _ => None,
+    ///     });
+    ///     match newest {
+    ///         Ok(found) => println!("The newest 'ls' is '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_best<F, S>(&self, file_name: &str, mut score: F) -> Result<FoundType, Error>
+        where F: FnMut(&FoundType) -> S, S: PartialOrd {
+        let mut best: Option<(S, FoundType)> = None;
+        for candidate in self.find_all(file_name, FileType::Any)? {
+            let candidate_score = score(&candidate);
+            let better = match &best {
+                Some((best_score, _)) => candidate_score > *best_score,
+                None => true,
+            };
+            if better {
+                best = Some((candidate_score, candidate));
+            }
+        }
+
+        best.map(|(_, found)| found).ok_or_else(|| Error::new(ErrorKind::NotFound,
+                       format!("Could not find '{}' in {}", file_name, PathName(&self.name))))
+    }
+
+    /// Find all the entries called `file_name` and return the one that was modified most recently,
+    /// e.g. to pick the freshest of several build outputs with the same name. Entries whose
+    /// modification time cannot be read (such as resources) are only returned if none can be.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_newest("ls") {
+    ///         Ok(found) => println!("The newest 'ls' is '{:?}'", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_newest(&self, file_name: &str) -> Result<FoundType, Error> {
+        self.find_best(file_name, |candidate| {
+            fs::metadata(Self::location(candidate)).and_then(|metadata| metadata.modified()).ok()
+        })
+    }
+
+    #[cfg(feature = "suggest")]
+    /// Get the names of the files in the directories of the search path that are close to
+    /// `file_name`, by edit distance or because they start with it, closest first, e.g. to ask
+    /// "did you mean `clang-14`?" when `clang` cannot be found. These are also included in the
+    /// message of the `NotFound` errors returned by `find()` and the other finds by name.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("Did you mean one of {:?}?", search_path.suggestions("pyhton"));
+    /// }
+    /// ```
+    pub fn suggestions(&self, file_name: &str) -> Vec<String> {
+        let mut names = vec!();
+        for search_dir in self.search_dirs() {
+            let dir_exclusions = self.dir_exclusions(search_dir);
+            if let Ok(entries) = fs::read_dir(search_dir) {
+                names.extend(entries.flatten()
+                    .filter(|entry| !self.excluded(&dir_exclusions, entry))
+                    .filter_map(|entry| entry.file_name().into_string().ok()));
+            }
+        }
+
+        Self::close_names(file_name, names.iter().map(String::as_str))
+    }
+
+    /// Find the executable that would be run for the command `name`, as the `which` command
+    /// does. See `which_all()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     match Simpath::system_path().which("ls") {
+    ///         Ok(path) => println!("'ls' is '{}'", path.display()),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn which(&self, name: &str) -> Result<PathBuf, Error> {
+        self.which_all(name).map(|mut found| found.remove(0))
+    }
+
+    /// Find all the executables called `name`, in search order, as `which -a` does. Only files
+    /// (or symbolic links to files) that the current user can execute are returned, regardless
+    /// of the access set with `set_required_access()`, and they are returned as absolute paths,
+    /// so directories such as `.` in the search path are resolved against the current directory.
+    /// If `name` contains a path separator it is not searched for, but checked as it is, relative
+    /// to the current directory. If none are found then a `NotFound` error is returned.
+    ///
+    /// On windows commands are resolved as cmd.exe does: in each directory `name` itself is tried
+    /// if it has an extension, followed by `name` with each of the extensions in `PATHEXT` added,
+    /// in order, before moving on to the next directory. So `tool.cmd` in an earlier directory is
+    /// found before `tool.exe` in a later one.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     match Simpath::system_path().which_all("python3") {
+    ///         Ok(paths) => println!("'python3' found at {:?}", paths),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn which_all(&self, name: &str) -> Result<Vec<PathBuf>, Error> {
+        let candidates: Vec<PathBuf> = if name.is_empty() {
+            vec!()
+        } else if Path::new(name).components().count() > 1 {
+            self.command_names(name).into_iter().map(PathBuf::from).collect()
+        } else {
+            let names = self.command_names(name);
+            self.search_dirs().flat_map(|dir| names.iter().map(move |name| dir.join(name))).collect()
+        };
+
+        let mut found = vec!();
+        for candidate in candidates {
+            if fs::metadata(&candidate).map(|metadata| metadata.is_file()).unwrap_or(false)
+                && self.has_access(&candidate, Access::Executable) && self.mode_accepts(&candidate) {
+                let path = std::path::absolute(&candidate)?;
+                if !found.contains(&path) {
+                    found.push(path);
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return Err(Error::new(ErrorKind::NotFound,
+                                  format!("Could not find executable '{}' in {}", name, PathName(&self.name))));
+        }
+
+        Ok(found)
+    }
+
+    // Check that the mode filter (on unix) accepts the file at `path`
+    fn mode_accepts(&self, _path: &Path) -> bool {
+        #[cfg(unix)]
+        return self.mode_rejects(_path).is_none();
+        #[cfg(not(unix))]
+        true
+    }
+
+    // Get the names to look for in each directory when searching for the command `name`. On
+    // windows these are `name` with the extensions in `PATHEXT`, as cmd.exe uses.
+    fn command_names(&self, name: &str) -> Vec<String> {
+        #[cfg(windows)]
+        return Self::with_extensions(name, self.env_var("PATHEXT").as_deref().and_then(OsStr::to_str));
+        #[cfg(not(windows))]
+        vec!(name.to_string())
+    }
+
+    #[cfg(any(windows, test))]
+    // Get the names cmd.exe tries for the command `name`, in order: `name` itself if it has an
+    // extension, then `name` with each of the extensions in `path_ext` (`PATHEXT`) added
+    pub(crate) fn with_extensions(name: &str, path_ext: Option<&str>) -> Vec<String> {
+        let path_ext = path_ext.filter(|path_ext| !path_ext.is_empty()).unwrap_or(DEFAULT_PATHEXT);
+        let mut names = vec!();
+        if Path::new(name).extension().is_some() {
+            names.push(name.to_string());
+        }
+        for extension in path_ext.split(';').map(str::trim).filter(|extension| !extension.is_empty()) {
+            names.push(format!("{}.{}", name, extension.trim_start_matches('.')));
+        }
+        names
+    }
+
+    /// Search for all the entries of a specific `FileType` in a `Path` with the name `file_name`,
+    /// as `find_all()` does, returning a `FindReport` describing what was searched, what matched
+    /// and what was skipped and why.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let report = search_path.find_report("ls", FileType::File);
+    ///     println!("Searched {:?} and found {:?}", report.searched, report.matched);
+    /// }
+    /// ```
+    pub fn find_report(&self, file_name: &str, file_type: FileType) -> FindReport {
+        let (_, trace) = self.traced_search(file_name, file_type, true);
+        trace.report
+    }
+
+    /// Try to find an entry of a specific `FileType`, as `find_type()` does, also returning a
+    /// `SearchTrace` of every entry visited, what was compared with `file_name`, and why the
+    /// candidates that were rejected (wrong type, not executable, unreadable etc.) were. This can
+    /// be used to answer questions about why an entry was (or was not) found.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let (found, trace) = search_path.find_traced("ls", FileType::File);
+    ///     println!("Found {:?} after comparing {} entries", found, trace.compared.len());
+    ///     for skipped in trace.report.skipped {
+    ///         println!("Rejected '{}': {}", skipped.entry, skipped.reason);
+    ///     }
+    /// }
+    /// ```
+    pub fn find_traced(&self, file_name: &str, file_type: FileType) -> (Result<FoundType, Error>, SearchTrace) {
+        let (result, trace) = self.traced_search(file_name, file_type, false);
+        (result.map(|mut found| found.remove(0)), trace)
+    }
+
+    // Search for `file_name` as `search()` does, recording what was searched in a `SearchTrace`
+    fn traced_search(&self, file_name: &str, file_type: FileType, all: bool)
+        -> (Result<Vec<FoundType>, Error>, SearchTrace) {
+        let mut trace = SearchTrace {
+            report: FindReport {
+                search_path: self.name.clone(),
+                name: file_name.to_string(),
+                file_type: Some(file_type.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = self.search(file_name, &file_type, all, Some(&mut trace));
+        match &result {
+            Ok(found) => trace.report.matched = found.iter().map(Self::location).collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::PermissionDenied => {}
+            Err(e) => trace.report.error = Some(e.to_string()),
+        }
+
+        (result, trace)
+    }
+
+    /// Try to find an entry of a specific `FileType`, as `find_type()` does, but give up if the
+    /// search has not completed within `timeout`, returning a `TimedOut` error.
+    ///
+    /// The search is done on another thread, so that a directory that blocks (such as one on a
+    /// hung network mount) cannot block the caller. That thread is cancelled when the timeout
+    /// expires, so it stops as soon as it gets past whatever it is blocked on. On WebAssembly,
+    /// where threads cannot be started, an `Unsupported` error is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, Simpath};
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_with_deadline("ls", FileType::File, Duration::from_secs(5)) {
+    ///         Ok(found) => println!("Found {:?}", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_with_deadline(&self, file_name: &str, file_type: FileType, timeout: Duration)
+                              -> Result<FoundType, Error> {
+        let name = file_name.to_string();
+        self.with_deadline(file_name, timeout, move |search_path| search_path.find_type(&name, file_type))
+    }
+
+    /// Find the entries called `file_name`, in search order, searching as set in `options`. If
+    /// none are found then `Err` is returned.
+    ///
+    /// Each option of a search can be set in `SearchOptions`, so this can do what the other
+    /// finds do, and combinations of them that they cannot.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{FileType, SearchOptions, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let options = SearchOptions::new().case_sensitive(false).file_types(FileType::File)
+    ///         .max_results(1);
+    ///     match search_path.find_with("LS", &options) {
+    ///         Ok(found) => println!("Found {:?}", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_with(&self, file_name: &str, options: &SearchOptions) -> Result<Vec<FoundType>, Error> {
+        match options.timeout {
+            Some(timeout) => {
+                let name = file_name.to_string();
+                let options = options.clone();
+                self.with_deadline(file_name, timeout,
+                                   move |search_path| search_path.search_with(&name, &options))
+            }
+            None => self.search_with(file_name, options),
+        }
+    }
+
+    /// Find the entries called `file_name` as `find_with()` does, returning with each one found
+    /// that is a symbolic link the final target it resolves to, or for a dangling link the
+    /// target that does not exist
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{SearchOptions, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     if let Ok(found) = search_path.find_with_targets("python", &SearchOptions::new()) {
+    ///         for (found, target) in found {
+    ///             match target {
+    ///                 Some(target) => println!("Found {:?} -> {}", found, target.display()),
+    ///                 None => println!("Found {:?}", found),
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn find_with_targets(&self, file_name: &str, options: &SearchOptions)
+                             -> Result<Vec<(FoundType, Option<PathBuf>)>, Error> {
+        Ok(self.find_with(file_name, options)?.into_iter()
+            .map(|found| {
+                let target = found.path().and_then(Self::link_target);
+                (found, target)
+            })
+            .collect())
+    }
+
+    // Run `search` on a clone of this `Simpath` on another thread, returning a `TimedOut` error
+    // and cancelling it if it has not completed within `timeout`
+    fn with_deadline<T, F>(&self, file_name: &str, timeout: Duration, search: F) -> Result<T, Error>
+        where T: Send + 'static, F: FnOnce(&Simpath) -> Result<T, Error> + Send + 'static {
+        if cfg!(target_arch = "wasm32") {
+            return Err(Error::new(ErrorKind::Unsupported,
+                                  "Finding with a deadline needs threads, which are not supported"));
+        }
+
+        let token = CancellationToken::new();
+        let mut search_path = self.clone();
+        search_path.cancellation.push(token.clone());
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(search(&search_path));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(_) => {
+                token.cancel();
+                Err(Error::new(ErrorKind::TimedOut,
+                               format!("Search for '{}' in {} did not complete within {:?}",
+                                       file_name, PathName(&self.name), timeout)))
+            }
+        }
+    }
+
+    /// Set a `CancellationToken` that can be used to cancel searches of this `Simpath`, and of
+    /// its clones, from another thread
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{CancellationToken, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("PATH");
+    ///     let token = CancellationToken::new();
+    ///     search_path.set_cancellation_token(token.clone());
+    ///     token.cancel();
+    ///     assert!(search_path.find("ls").is_err());
+    /// }
+    /// ```
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = vec!(token);
+    }
+
+    // Return an `Interrupted` error if the search has been cancelled
+    pub(crate) fn check_cancelled(&self, file_name: &str) -> Result<(), Error> {
+        if self.cancellation.iter().any(CancellationToken::is_cancelled) {
+            return Err(Error::new(ErrorKind::Interrupted,
+                                  format!("Search for '{}' in {} was cancelled",
+                                          file_name, PathName(&self.name))));
+        }
+
+        Ok(())
+    }
+
+    // Get a printable representation of where a `FoundType` was found
+    pub(crate) fn location(found: &FoundType) -> String {
+        found.to_string()
+    }
+
+    // Search for entries called `file_name` of `file_type`, returning just the first one found
+    // unless `all` is true. If none are found then `Err` is returned.
+    // If `trace` is supplied the entries searched, compared and skipped are recorded in it.
+    pub(crate) fn search(&self, file_name: &str, file_type: &FileType, all: bool,
+              trace: Option<&mut SearchTrace>) -> Result<Vec<FoundType>, Error> {
+        self.search_types(file_name, std::slice::from_ref(file_type), all, trace, true)
+    }
+
+    // Search for entries called `file_name` of `file_type`, as `search()` does, for searches
+    // whose errors are not shown to the user, so close names are not suggested in them
+    pub(crate) fn search_without_suggestions(&self, file_name: &str, file_type: &FileType, all: bool)
+                                             -> Result<Vec<FoundType>, Error> {
+        self.search_types(file_name, std::slice::from_ref(file_type), all, None, false)
+    }
+
+    // Search for entries called `file_name` of any of `file_types`, as `search()` does for one
+    // type. In each entry of the search path the types are searched for in turn, and anything
+    // found as more than one of them is only returned as the first. If `suggest` is true then
+    // close names are suggested in the error returned if nothing is found.
+    fn search_types(&self, file_name: &str, file_types: &[FileType], all: bool,
+                    trace: Option<&mut SearchTrace>, suggest: bool) -> Result<Vec<FoundType>, Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("search", search_path = %self.name, name = file_name,
+                                         file_types = ?file_types).entered();
+        self.count(Stat::Search);
+
+        let local_types: Vec<&FileType> = file_types.iter().filter(|file_type| **file_type != FileType::Resource).collect();
+        if !local_types.is_empty() {
+            if Path::new(file_name).is_absolute() {
+                for file_type in local_types {
+                    if let Some(found) = self.found_type(PathBuf::from(file_name), file_type)? {
+                        return Ok(vec!(found));
+                    }
+                }
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("Could not find type {} at '{}'",
+                                              Self::type_names(file_types), file_name)));
+            }
+
+            // Reject names that cannot be searched for before searching any entries
+            Self::relative_path(file_name)?;
+        }
+
+        let mut context = SearchContext::new(self, trace);
+        for (searches, search_entry) in self.search_entries().into_iter().enumerate() {
+            self.check_search_limit(searches)?;
+            let found_before = context.found.len();
+            for file_type in file_types {
+                search_entry.search(&mut context, file_name, file_type)?;
+            }
+            if file_types.len() > 1 {
+                let mut locations = HashSet::new();
+                let found: Vec<FoundType> = context.found.drain(found_before..).collect();
+                context.found.extend(found.into_iter().filter(|found| locations.insert(Self::location(found))));
+            }
+            if !all && !context.found.is_empty() {
+                return Ok(context.found);
+            }
+        }
+
+        let message = format!("Could not find type {} called '{}' in {}",
+                              Self::type_names(file_types), file_name, PathName(&self.name));
+        context.finish(|| if suggest {
+            self.not_found(file_name, message)
+        } else {
+            Error::new(ErrorKind::NotFound, message)
+        })
+    }
+
+    // Search for `file_name` as set in `options`
+    fn search_with(&self, file_name: &str, options: &SearchOptions) -> Result<Vec<FoundType>, Error> {
+        let file_types = options.file_types.types();
+        if file_types.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("No file types were set to search for '{}'", file_name)));
+        }
+
+        let matcher = if options.case_sensitive {
+            if Path::new(file_name).is_absolute() {
+                return self.search_types(file_name, file_types, false, None, true)
+                    .and_then(|found| self.checked_links(found, options));
+            }
+            Self::relative_path(file_name)?;
+            None
+        } else {
+            if Path::new(file_name).is_absolute() || Self::relative_path(file_name)?.is_some() {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      format!("Cannot search for the path '{}' ignoring case", file_name)));
+            }
+            Some(CaseInsensitiveName::new(file_name))
+        };
+
+        let mut context = SearchContext::new(self, None);
+        let mut searches = 0;
+        for search_entry in self.search_entries() {
+            if context.found.len() >= options.max_results {
+                break;
+            }
+            self.search_entry_with(&mut context, search_entry, file_name, matcher.as_ref(), options,
+                                   options.depth, &mut searches)?;
+        }
+        context.found.truncate(options.max_results);
+
+        context.finish(|| self.not_found(file_name,
+                       format!("Could not find type {} called '{}' in {}",
+                               Self::type_names(file_types), file_name, PathName(&self.name))))
+            .and_then(|found| self.checked_links(found, options))
+    }
+
+    // Check that none of the entries `found` are dangling symbolic links, if `options` reject them
+    fn checked_links(&self, found: Vec<FoundType>, options: &SearchOptions) -> Result<Vec<FoundType>, Error> {
+        if options.reject_dangling_links {
+            for path in found.iter().filter_map(FoundType::path) {
+                if let Some(target) = Self::link_target(path) {
+                    if !path.exists() {
+                        return Err(Error::new(ErrorKind::NotFound,
+                                              format!("'{}' was found in {} but it is a symbolic link to '{}', which does not exist",
+                                                      path.display(), PathName(&self.name), target.display())));
+                    }
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    #[cfg(unix)]
+    // Identify the file `path` resolves to by its device and inode
+    fn file_id(path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+
+        fs::metadata(path).ok().map(|metadata| (metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(windows)]
+    // Identify the file `path` resolves to by its volume serial number and file index
+    fn file_id(path: &Path) -> Option<(u64, u64)> {
+        use std::os::windows::fs::OpenOptionsExt;
+        use std::os::windows::io::AsRawHandle;
+
+        // Needed to open a directory, rather than a file, to get its information
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct ByHandleFileInformation {
+            file_attributes: u32,
+            creation_time: [u32; 2],
+            last_access_time: [u32; 2],
+            last_write_time: [u32; 2],
+            volume_serial_number: u32,
+            file_size_high: u32,
+            file_size_low: u32,
+            number_of_links: u32,
+            file_index_high: u32,
+            file_index_low: u32,
+        }
+
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn GetFileInformationByHandle(file: *mut std::ffi::c_void,
+                                          information: *mut ByHandleFileInformation) -> i32;
+        }
+
+        let file = fs::OpenOptions::new().access_mode(0).custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path).ok()?;
+        let mut information = ByHandleFileInformation::default();
+        // SAFETY: the handle is open for the duration of the call and `information` is a
+        // correctly laid out BY_HANDLE_FILE_INFORMATION that the call fills in
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle() as *mut std::ffi::c_void, &mut information) } == 0 {
+            return None;
+        }
+
+        Some((information.volume_serial_number as u64,
+              ((information.file_index_high as u64) << 32) | information.file_index_low as u64))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    // Files cannot be identified other than by their paths here
+    fn file_id(_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+
+    // If `path` is a symbolic link get the path it finally resolves to, or if it is dangling the
+    // path that the last link that could be read points to
+    fn link_target(path: &Path) -> Option<PathBuf> {
+        if !fs::symlink_metadata(path).ok()?.file_type().is_symlink() {
+            return None;
+        }
+
+        fs::canonicalize(path).ok().or_else(|| {
+            let mut target = path.to_path_buf();
+            for _ in 0..MAX_LINKS_FOLLOWED {
+                match fs::read_link(&target) {
+                    Ok(next) => target = target.parent().map(|parent| parent.join(&next)).unwrap_or(next),
+                    Err(_) => break,
+                }
+            }
+            Some(target)
+        })
+    }
+
+    // Search `search_entry`, and then its subdirectories down to `depth` levels below it, for
+    // `file_name` (or the names matched by `matcher`) as set in `options`. `searches` counts the
+    // entries searched, which are limited by the limits of the search path.
+    #[allow(clippy::too_many_arguments)]
+    fn search_entry_with(&self, context: &mut SearchContext<'_>, search_entry: &dyn SearchEntry,
+                         file_name: &str, matcher: Option<&CaseInsensitiveName>, options: &SearchOptions,
+                         depth: usize, searches: &mut usize) -> Result<(), Error> {
+        self.check_search_limit(*searches)?;
+        *searches += 1;
+
+        let found_before = context.found.len();
+        for file_type in options.file_types.types() {
+            match matcher {
+                Some(matcher) => search_entry.search_where(context, matcher, file_type)?,
+                None => search_entry.search(context, file_name, file_type)?,
+            }
+        }
+        let mut locations = HashSet::new();
+        let found: Vec<FoundType> = context.found.drain(found_before..).collect();
+        context.found.extend(found.into_iter()
+            .filter(|found| options.follow_symlinks || !Self::found_through_link(found))
+            .filter(|found| locations.insert(Self::location(found))));
+
+        if depth > 0 {
+            for subdirectory in search_entry.subdirectories(context, options.follow_symlinks) {
+                if context.found.len() >= options.max_results {
+                    break;
+                }
+                self.search_entry_with(context, &Arc::<Path>::from(subdirectory), file_name, matcher,
+                                       options, depth - 1, searches)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Check if `found` was found as a file or directory, but is a symbolic link to one
+    fn found_through_link(found: &FoundType) -> bool {
+        match found {
+            FoundType::File(path) | FoundType::Directory(path) => fs::symlink_metadata(path)
+                .map(|metadata| metadata.file_type().is_symlink()).unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    // Describe a set of file types in a message, e.g. "'File' or 'Directory'"
+    fn type_names(file_types: &[FileType]) -> String {
+        file_types.iter().map(|file_type| format!("'{:?}'", file_type)).collect::<Vec<String>>().join(" or ")
+    }
+
+    // Check that a search that has already searched `searches` entries can search another one
+    // without going over the limit of searches of the search path
+    fn check_search_limit(&self, searches: usize) -> Result<(), Error> {
+        if searches >= self.limits.max_searches {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Stopped searching {} after the limit of {} entries searched",
+                                          PathName(&self.name), self.limits.max_searches)));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "suggest")]
+    // Select the names that are close to `file_name`: a small edit distance from it, or starting
+    // with it. The closest are returned first.
+    pub(crate) fn close_names<'a>(file_name: &str, names: impl Iterator<Item = &'a str>) -> Vec<String> {
+        if file_name.is_empty() {
+            return vec!();
+        }
+
+        let max_distance = (file_name.chars().count() / 3).max(1);
+        let mut close: Vec<(usize, &str)> = names.filter(|name| *name != file_name)
+            .filter_map(|name| {
+                let distance = edit_distance(file_name, name);
+                if distance <= max_distance || name.starts_with(file_name) {
+                    Some((distance, name))
+                } else {
+                    None
+                }
+            }).collect();
+        close.sort_unstable();
+        close.dedup();
+        close.into_iter().take(MAX_SUGGESTIONS).map(|(_, name)| name.to_string()).collect()
+    }
+
+    // Compare a file name found with the name being searched for
+    pub(crate) fn names_match(&self, found: &str, wanted: &str) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.unicode_normalization {
+            return found.nfc().eq(wanted.nfc());
+        }
+
+        found == wanted
+    }
+
+    // If `file_name` is a relative path with more than one component, return it as a `PathBuf`
+    // with any `.` components removed. Paths that could escape the directory they are joined to
+    // are rejected.
+    pub(crate) fn relative_path(file_name: &str) -> Result<Option<PathBuf>, Error> {
+        let path = Path::new(file_name);
+        if path.components().count() <= 1 {
+            return Ok(None);
+        }
+
+        let mut relative_path = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => relative_path.push(part),
+                Component::CurDir => {}
+                _ => return Err(Error::new(ErrorKind::InvalidInput,
+                                           format!("Cannot search for '{}' as it is not a relative path within the search path", file_name)))
+            }
+        }
+
+        Ok(Some(relative_path))
+    }
+
+    // Return the `FoundType` for `path` if it exists and is of the `file_type` requested.
+    // If it does but the current user does not have the required access a `PermissionDenied`
+    // error is returned.
+    pub(crate) fn found_type(&self, path: PathBuf, file_type: &FileType) -> Result<Option<FoundType>, Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let found_filetype = fs::symlink_metadata(&path)?.file_type();
+        match Self::matching_type(path.clone(), found_filetype.into(), file_type) {
+            Some(found) => self.check_access(&path).map(|_| Some(found)),
+            None => Ok(None)
+        }
+    }
+
+    // Return a `PermissionDenied` error if the current user does not have the required access,
+    // or (on unix) the mode filter rejects the file
+    pub(crate) fn check_access(&self, path: &Path) -> Result<(), Error> {
+        if !self.accessible(path) {
+            let access = match self.access {
+                Access::Readable => "readable",
+                _ => "executable",
+            };
+
+            return Err(Error::new(ErrorKind::PermissionDenied,
+                                  format!("'{}' was found but is not {} by the current user",
+                                          path.display(), access)));
+        }
+
+        #[cfg(unix)]
+        if let Some(reason) = self.mode_rejects(path) {
+            return Err(Error::new(ErrorKind::PermissionDenied,
+                                  format!("'{}' was found but {}", path.display(), reason)));
+        }
+
+        Ok(())
+    }
+
+    // Return the `FoundType` for `path` if its `found_filetype` matches the `file_type` requested
+    pub(crate) fn matching_type(path: PathBuf, found_kind: EntryKind, file_type: &FileType) -> Option<FoundType> {
+        match (file_type, found_kind) {
+            #[cfg(unix)]
+            (FileType::Any, EntryKind::Fifo) => Some(FoundType::Fifo(path)),
+            #[cfg(unix)]
+            (FileType::Any, EntryKind::Socket) => Some(FoundType::Socket(path)),
+            #[cfg(unix)]
+            (FileType::Any, EntryKind::BlockDevice) => Some(FoundType::BlockDevice(path)),
+            #[cfg(unix)]
+            (FileType::Any, EntryKind::CharDevice) => Some(FoundType::CharDevice(path)),
+            (FileType::Any, _) => Some(FoundType::File(path)),
+            (FileType::Directory, EntryKind::Directory) => Some(FoundType::Directory(path)),
+            (FileType::File, EntryKind::File) | (FileType::File, EntryKind::Symlink) => Some(FoundType::File(path)),
+            #[cfg(unix)]
+            (FileType::Symlink, EntryKind::Symlink) => Some(FoundType::Symlink(path)),
+            #[cfg(unix)]
+            (FileType::Fifo, EntryKind::Fifo) => Some(FoundType::Fifo(path)),
+            #[cfg(unix)]
+            (FileType::Socket, EntryKind::Socket) => Some(FoundType::Socket(path)),
+            #[cfg(unix)]
+            (FileType::BlockDevice, EntryKind::BlockDevice) => Some(FoundType::BlockDevice(path)),
+            #[cfg(unix)]
+            (FileType::CharDevice, EntryKind::CharDevice) => Some(FoundType::CharDevice(path)),
+            _ => None
+        }
+    }
+
+    // Check the current user has the required access to `path`
+    fn accessible(&self, path: &Path) -> bool {
+        self.has_access(path, self.access)
+    }
+
+    // Check the current user has `access` to `path`
+    #[cfg(unix)]
+    fn has_access(&self, path: &Path, access: Access) -> bool {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mode = match access {
+            Access::Any => return true,
+            Access::Readable => libc::R_OK,
+            Access::Executable => libc::X_OK,
+        };
+
+        match CString::new(path.as_os_str().as_bytes()) {
+            // SAFETY: c_path is a valid nul-terminated string that outlives the call
+            Ok(c_path) => unsafe { libc::access(c_path.as_ptr(), mode) == 0 },
+            Err(_) => false,
+        }
+    }
+
+    // Check the current user has `access` to `path`
+    #[cfg(not(unix))]
+    fn has_access(&self, path: &Path, access: Access) -> bool {
+        match access {
+            Access::Any => true,
+            Access::Readable => path.is_dir() || fs::File::open(path).is_ok(),
+            // WASI has no execute permission, so there any file that can be read can be run
+            #[cfg(target_os = "wasi")]
+            Access::Executable => path.is_dir() || fs::File::open(path).is_ok(),
+            #[cfg(not(target_os = "wasi"))]
+            Access::Executable => {
+                let executable_extensions = self.env_var_string("PATHEXT")
+                    .unwrap_or_else(|| DEFAULT_PATHEXT.into()).to_lowercase();
+                path.is_dir() || path.extension()
+                    .map(|ext| executable_extensions.split(';')
+                        .any(|known| known.trim_start_matches('.') == ext.to_string_lossy().to_lowercase()))
+                    .unwrap_or(false)
+            }
+        }
+    }
+
+    /// Find all entries in the directories of the `Simpath` that are accepted by `predicate`.
+    ///
+    /// `predicate` is called with each `DirEntry` found in each directory of the search path, and
+    /// can use it (and its metadata) to apply any criteria it wishes (size, modification time,
+    /// contents etc). All the entries it accepts are returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let large_files = search_path.find_matching(|entry| {
+    ///         entry.metadata().map(|metadata| metadata.len() > 1_000_000).unwrap_or(false)
+    ///     });
+    ///     println!("Large files in search path: {:?}", large_files);
+    /// }
+    /// ```
+    pub fn find_matching<F>(&self, mut predicate: F) -> Result<Vec<FoundType>, Error>
+        where F: FnMut(&fs::DirEntry) -> bool {
+        let mut found = vec!();
+
+        for search_dir in self.search_dirs() {
+            let dir_exclusions = self.dir_exclusions(search_dir);
+            let entries = match fs::read_dir(search_dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.handle_error(search_dir, e)?;
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let file = match entry {
+                    Ok(file) => file,
+                    Err(e) => {
+                        self.handle_error(search_dir, e)?;
+                        continue;
+                    }
+                };
+                if !self.excluded(&dir_exclusions, &file) && predicate(&file) {
+                    match file.metadata() {
+                        Ok(metadata) if metadata.is_dir() => found.push(FoundType::Directory(file.path())),
+                        Ok(_) => found.push(FoundType::File(file.path())),
+                        Err(e) => self.handle_error(&file.path(), e)?,
+                    }
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Find all the entries of a specific `FileType` whose names are matched by `matcher`, in
+    /// search order. If none are found then `Err` is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{CaseInsensitiveName, FileType, GlobName, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     println!("{:?}", search_path.find_where(GlobName::new("python3*"), FileType::File));
+    ///     println!("{:?}", search_path.find_where(CaseInsensitiveName::new("LS"), FileType::File));
+    ///     println!("{:?}", search_path.find_where(|name: &str| name.len() == 2, FileType::File));
+    /// }
+    /// ```
+    pub fn find_where<M: NameMatcher>(&self, matcher: M, file_type: FileType) -> Result<Vec<FoundType>, Error> {
+        let mut context = SearchContext::new(self, None);
+        for (searches, search_entry) in self.search_entries().into_iter().enumerate() {
+            self.check_search_limit(searches)?;
+            search_entry.search_where(&mut context, &matcher, &file_type)?;
+        }
+
+        context.finish(|| Error::new(ErrorKind::NotFound,
+                       format!("Could not find type '{:?}' with a matching name in {}",
+                               file_type, PathName(&self.name))))
+    }
+
+    /// Find the first file or resource, in search order, whose name is matched by `matcher` and
+    /// whose leading bytes (up to the first 4096) are accepted by `predicate`, such as one that
+    /// starts with a particular magic number. Resources are fetched to check them.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{ExactName, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     match search_path.find_where_contents(ExactName::new("ls"), |bytes| bytes.starts_with(b"\x7fELF")) {
+    ///         Ok(found) => println!("Found ELF binary {:?}", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_where_contents<M, F>(&self, matcher: M, mut predicate: F) -> Result<FoundType, Error>
+        where M: NameMatcher, F: FnMut(&[u8]) -> bool {
+        for found in self.find_where(matcher, FileType::Any)? {
+            let contents = match &found {
+                FoundType::File(path) if path.is_file() => {
+                    let mut contents = Vec::with_capacity(CONTENTS_PREFIX_LEN);
+                    let read = fs::File::open(path)
+                        .and_then(|file| file.take(CONTENTS_PREFIX_LEN as u64).read_to_end(&mut contents));
+                    match read {
+                        Ok(_) => contents,
+                        Err(e) => {
+                            self.handle_error(path, e)?;
+                            continue;
+                        }
+                    }
+                }
+                #[cfg(feature = "urls")]
+                FoundType::Resource(url) => match self.fetch(url) {
+                    Ok(mut contents) => {
+                        contents.truncate(CONTENTS_PREFIX_LEN);
+                        contents
+                    }
+                    Err(e) => {
+                        self.handle_error(Path::new(url.as_str()), e)?;
+                        continue;
+                    }
+                },
+                _ => continue,
+            };
+
+            if predicate(&contents) {
+                return Ok(found);
+            }
+        }
+
+        Err(Error::new(ErrorKind::NotFound,
+                       format!("Could not find a file with a matching name and contents in {}",
+                               PathName(&self.name))))
+    }
+
+    #[cfg(feature = "digest")]
+    /// Find a file or resource by name, as `find()` does, and verify that the SHA-256 hash of its
+    /// contents is `sha256` (in hexadecimal). If they do not match an `InvalidData` error is
+    /// returned, so that a file that has been tampered with is not used.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("PATH");
+    ///     let expected = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    ///     match search_path.find_verified("tool", expected) {
+    ///         Ok(found) => println!("Found verified {:?}", found),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_verified(&self, file_name: &str, sha256: &str) -> Result<FoundType, Error> {
+        let found = self.find(file_name)?;
+
+        let mut hasher = Sha256::new();
+        match &found {
+            FoundType::File(path) => {
+                std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+            }
+            #[cfg(feature = "urls")]
+            FoundType::Resource(url) => hasher.update(self.fetch(url)?),
+            _ => return Err(Error::new(ErrorKind::InvalidInput,
+                                       format!("Cannot verify '{}' as it is not a file", Self::location(&found)))),
+        }
+
+        let actual: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+        if actual.eq_ignore_ascii_case(sha256) {
+            Ok(found)
+        } else {
+            Err(Error::new(ErrorKind::InvalidData,
+                           format!("The SHA-256 of '{}' is '{}' but '{}' was expected",
+                                   Self::location(&found), actual, sha256)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+    #[cfg(windows)]
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[cfg(unix)]
+    use crate::Access;
+    use crate::{CancellationToken, CaseInsensitiveName, ExactName, FileType, FoundType, GlobName, NameMatcher,
+                SearchOptions, SearchStats, Simpath};
+    use crate::test::temp_dir;
+
+    #[test]
+    fn collects_stats() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        assert!(path.stats().is_none());
+
+        path.enable_stats();
+        path.find("tool").unwrap();
+        assert!(path.find("missing").is_err());
+        let frozen = path.clone().freeze();
+        frozen.find("tool").unwrap();
+
+        let stats = path.stats().unwrap();
+        assert_eq!(stats.searches, 3);
+        assert_eq!(stats.directories_scanned, 2);
+        assert_eq!(stats.files_examined, 2);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (1, 0));
+        assert_eq!(stats.cache_hit_rate(), Some(1.0));
+        assert_eq!(stats.entry_hits.get(&temp_dir.display().to_string()), Some(&2));
+
+        path.enable_stats();
+        assert_eq!(path.stats(), Some(SearchStats::default()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore)]
+    fn cancelled_search() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/");
+        let token = CancellationToken::new();
+        path.set_cancellation_token(token.clone());
+        assert!(path.find("tmp").is_ok());
+
+        token.cancel();
+        assert_eq!(path.clone().find("tmp").unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        assert_eq!(path.find_with_deadline("tmp", FileType::Any, Duration::from_secs(10))
+                       .unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "wasi", ignore)]
+    fn find_within_deadline() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/");
+        assert_eq!(path.find_with_deadline("tmp", FileType::Directory, Duration::from_secs(10)).unwrap(),
+                   FoundType::Directory(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn find_where_with_matchers() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("libfoo.so")).unwrap();
+        fs::File::create(temp_dir.join("libbar.so.1")).unwrap();
+        fs::File::create(temp_dir.join("README")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert_eq!(path.find_where(ExactName::new("libfoo.so"), FileType::File).unwrap(),
+                   vec!(FoundType::File(temp_dir.join("libfoo.so"))));
+        assert_eq!(path.find_where(CaseInsensitiveName::new("readme"), FileType::File).unwrap(),
+                   vec!(FoundType::File(temp_dir.join("README"))));
+        assert_eq!(path.find_where(GlobName::new("lib*.so*"), FileType::File).unwrap().len(), 2);
+        assert_eq!(path.find_where(GlobName::new("lib???.so"), FileType::File).unwrap().len(), 1);
+        assert!(path.find_where(GlobName::new("*.a"), FileType::File).is_err());
+        assert_eq!(path.find_where(|name: &str| name.starts_with("lib"), FileType::File).unwrap().len(), 2);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn glob_matches_names_containing_stars() {
+        assert!(GlobName::new("a*").matches("a*b"));
+        assert!(GlobName::new("*").matches("*x"));
+        assert!(GlobName::new("*b").matches("a*b"));
+        assert!(!GlobName::new("a*c").matches("a*b"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn find_where_regex() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("tool-1.2")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        let matcher = regex::Regex::new(r"^tool-\d+\.\d+$").unwrap();
+        assert_eq!(path.find_where(matcher, FileType::File).unwrap(),
+                   vec!(FoundType::File(temp_dir.join("tool-1.2"))));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn find_verified_checks_hash() {
+        let temp_dir = temp_dir();
+        fs::write(temp_dir.join("tool"), "hello").unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert_eq!(path.find_verified("tool", "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824")
+                       .unwrap(), FoundType::File(temp_dir.join("tool")));
+        assert_eq!(path.find_verified("tool", "0000").unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_where_contents_checks_leading_bytes() {
+        let temp_dir = temp_dir();
+        let script_dir = temp_dir.join("scripts");
+        let binary_dir = temp_dir.join("binaries");
+        fs::create_dir_all(&script_dir).unwrap();
+        fs::create_dir_all(binary_dir.join("foo")).unwrap();
+        fs::write(script_dir.join("foo"), "#!/bin/sh\n").unwrap();
+        fs::write(binary_dir.join("foo.bin"), b"\x7fELF\x02\x01").unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&script_dir);
+        path.add_directory(&binary_dir);
+
+        let is_elf = |bytes: &[u8]| bytes.starts_with(b"\x7fELF");
+        assert_eq!(path.find_where_contents(GlobName::new("foo*"), is_elf).unwrap(),
+                   FoundType::File(binary_dir.join("foo.bin")));
+        assert!(path.find_where_contents(ExactName::new("foo"), is_elf).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn which_all_finds_executables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = temp_dir();
+        let second = temp_dir();
+        for dir in [&first, &second] {
+            let tool = dir.join("tool");
+            fs::File::create(&tool).unwrap();
+            fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        fs::File::create(first.join("data")).unwrap();
+        fs::set_permissions(first.join("data"), fs::Permissions::from_mode(0o644)).unwrap();
+        fs::create_dir(first.join("subdir")).unwrap();
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+
+        assert_eq!(path.which("tool").unwrap(), first.join("tool"));
+        assert_eq!(path.which_all("tool").unwrap(), vec!(first.join("tool"), second.join("tool")));
+        assert_eq!(path.which(&second.join("tool").display().to_string()).unwrap(), second.join("tool"));
+        assert_eq!(path.which("data").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        assert!(path.which("subdir").is_err());
+        assert!(path.which("").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(first);
+        let _ = fs::remove_dir_all(second);
+    }
+
+    #[test]
+    fn find_best_by_score() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        fs::create_dir_all(&first).expect("Could not create dir");
+        fs::create_dir_all(&second).expect("Could not create dir");
+        fs::write(first.join("config"), "small").expect("Could not write file");
+        fs::write(second.join("config"), "much larger").expect("Could not write file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(first.to_str().expect("Invalid path"));
+        path.add_directory(second.to_str().expect("Invalid path"));
+
+        let size = |found: &FoundType| match found {
+            FoundType::File(file) => fs::metadata(file).map(|metadata| metadata.len()).unwrap_or(0),
+            _ => 0,
+        };
+        assert_eq!(path.find_best("config", size).unwrap(), FoundType::File(second.join("config")));
+
+        // Equal scores return the first in search order
+        assert_eq!(path.find_best("config", |_| 1).unwrap(), FoundType::File(first.join("config")));
+        assert!(path.find_best("no_such_file", |_| 1).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_newest_modified() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        fs::create_dir_all(&first).expect("Could not create dir");
+        fs::create_dir_all(&second).expect("Could not create dir");
+        fs::write(second.join("output"), "old").expect("Could not write file");
+        fs::write(first.join("output"), "new").expect("Could not write file");
+        let old = std::time::SystemTime::now() - Duration::from_secs(60);
+        fs::File::options().write(true).open(second.join("output")).expect("Could not open file")
+            .set_modified(old).expect("Could not set modification time");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(second.to_str().expect("Invalid path"));
+        path.add_directory(first.to_str().expect("Invalid path"));
+        assert_eq!(path.find_newest("output").unwrap(), FoundType::File(first.join("output")));
+        assert!(path.find_newest("no_such_file").is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "suggest")]
+    #[test]
+    fn suggest_close_names() {
+        let temp_dir = temp_dir();
+        for name in ["clang-14", "clang-format", "python3", "ls"] {
+            fs::File::create(temp_dir.join(name)).expect("Could not create file");
+        }
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert_eq!(path.suggestions("pyhton3"), vec!("python3"));
+        assert_eq!(path.suggestions("clang"), vec!("clang-14", "clang-format"));
+        assert!(path.suggestions("gcc").is_empty());
+        let error = path.find("clang").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().ends_with(", did you mean `clang-14` or `clang-format`?"));
+
+        let frozen = path.freeze();
+        assert_eq!(frozen.suggestions("lss"), vec!("ls"));
+        assert!(frozen.find("python").unwrap_err().to_string().ends_with(", did you mean `python3`?"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_set_of_types() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        fs::create_dir_all(first.join("entry")).expect("Could not create directory");
+        fs::create_dir(&second).expect("Could not create directory");
+        fs::File::create(second.join("entry")).expect("Could not create file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+
+        assert_eq!(path.find_types("entry", FileType::File | FileType::Directory).expect("Could not find"),
+                   FoundType::Directory(first.join("entry")));
+        assert_eq!(path.find_all_types("entry", &[FileType::File, FileType::Directory][..]).expect("Could not find"),
+                   vec!(FoundType::Directory(first.join("entry")), FoundType::File(second.join("entry"))));
+        let error = path.find_types("missing", FileType::File | FileType::Directory).expect_err("Found missing");
+        assert!(error.to_string().starts_with("Could not find type 'File' or 'Directory' called 'missing'"));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(second.join("entry"), second.join("link")).expect("Could not create symlink");
+            assert_eq!(path.find_all_types("link", FileType::Symlink | FileType::File).expect("Could not find"),
+                       vec!(FoundType::Symlink(second.join("link"))));
+        }
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn command_names_with_path_extensions() {
+        assert_eq!(Simpath::with_extensions("tool", Some(".COM;.EXE")), vec!("tool.COM", "tool.EXE"));
+        assert_eq!(Simpath::with_extensions("tool.cmd", Some(".COM;;.EXE")),
+                   vec!("tool.cmd", "tool.cmd.COM", "tool.cmd.EXE"));
+        assert_eq!(Simpath::with_extensions("tool", None), vec!("tool.COM", "tool.EXE", "tool.BAT", "tool.CMD"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn which_prefers_earlier_directory_to_extension() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        fs::create_dir(&first).expect("Could not create directory");
+        fs::create_dir(&second).expect("Could not create directory");
+        fs::File::create(first.join("tool.cmd")).expect("Could not create file");
+        fs::File::create(second.join("tool.exe")).expect("Could not create file");
+
+        let mut path = Simpath::new_with_env_source("MyName", Arc::new(std::collections::HashMap::from(
+            [("PATHEXT".to_string(), ".EXE;.CMD".to_string())])));
+        path.add_directory(&first);
+        path.add_directory(&second);
+        let found = path.which("tool").expect("Could not find tool");
+        assert_eq!(found.file_name().map(|name| name.to_ascii_lowercase()), Some("tool.cmd".into()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_with_options() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let nested = first.join("nested");
+        let second = temp_dir.join("second");
+        fs::create_dir_all(&nested).expect("Could not create directory");
+        fs::create_dir(&second).expect("Could not create directory");
+        fs::File::create(nested.join("Tool")).expect("Could not create file");
+        fs::File::create(second.join("tool")).expect("Could not create file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+
+        let options = SearchOptions::new().file_types(FileType::File);
+        assert_eq!(path.find_with("tool", &options).expect("Could not find"),
+                   vec!(FoundType::File(second.join("tool"))));
+
+        let options = options.case_sensitive(false).depth(1);
+        assert_eq!(path.find_with("TOOL", &options).expect("Could not find"),
+                   vec!(FoundType::File(nested.join("Tool")), FoundType::File(second.join("tool"))));
+        assert_eq!(path.find_with("TOOL", &options.clone().max_results(1)).expect("Could not find"),
+                   vec!(FoundType::File(nested.join("Tool"))));
+        assert_eq!(path.find_with("nested/tool", &options).expect_err("Searched for path ignoring case").kind(),
+                   std::io::ErrorKind::InvalidInput);
+
+        let options = SearchOptions::new().file_types(FileType::Directory).timeout(Duration::from_secs(10));
+        assert_eq!(path.find_with("nested", &options).expect("Could not find"),
+                   vec!(FoundType::Directory(nested.clone())));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(second.join("tool"), first.join("link")).expect("Could not create link");
+            let options = SearchOptions::new().file_types(FileType::File | FileType::Symlink);
+            assert_eq!(path.find_with("link", &options).expect("Could not find"),
+                       vec!(FoundType::File(first.join("link"))));
+            assert_eq!(path.find_with("link", &options.follow_symlinks(false)).expect("Could not find"),
+                       vec!(FoundType::Symlink(first.join("link"))));
+        }
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_with_link_targets() {
+        let temp_dir = temp_dir();
+        let tool = temp_dir.join("tool-1.2");
+        fs::File::create(&tool).expect("Could not create file");
+        std::os::unix::fs::symlink(&tool, temp_dir.join("tool")).expect("Could not create link");
+        std::os::unix::fs::symlink(temp_dir.join("missing"), temp_dir.join("dangling")).expect("Could not create link");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        let options = SearchOptions::new().file_types(FileType::File);
+        assert_eq!(path.find_with_targets("tool", &options).expect("Could not find"),
+                   vec!((FoundType::File(temp_dir.join("tool")), Some(fs::canonicalize(&tool).expect("Could not canonicalize")))));
+        assert_eq!(path.find_with_targets("tool-1.2", &options).expect("Could not find"),
+                   vec!((FoundType::File(tool.clone()), None)));
+        assert_eq!(path.find_with_targets("dangling", &options).expect("Could not find"),
+                   vec!((FoundType::File(temp_dir.join("dangling")), Some(temp_dir.join("missing")))));
+
+        let options = options.reject_dangling_links(true);
+        assert!(path.find_with("tool", &options).is_ok());
+        let error = path.find_with("dangling", &options).expect_err("Found dangling link");
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().contains("which does not exist"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn find_all_distinct_files() {
+        let temp_dir = temp_dir();
+        let first = temp_dir.join("first");
+        let second = temp_dir.join("second");
+        let third = temp_dir.join("third");
+        for dir in [&first, &second, &third] {
+            fs::create_dir(dir).expect("Could not create directory");
+        }
+        fs::File::create(first.join("tool")).expect("Could not create file");
+        fs::hard_link(first.join("tool"), second.join("tool")).expect("Could not create hard link");
+        fs::File::create(third.join("tool")).expect("Could not create file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first);
+        path.add_directory(&second);
+        path.add_directory(&third);
+
+        assert_eq!(path.find_all("tool", FileType::File).expect("Could not find").len(), 3);
+        assert_eq!(path.find_all_distinct("tool", FileType::File).expect("Could not find"),
+                   vec!((FoundType::File(first.join("tool")), vec!(second.join("tool"))),
+                        (FoundType::File(third.join("tool")), vec!())));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn exists_without_finding() {
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join("share").join("app")).expect("Could not create directory");
+        fs::File::create(temp_dir.join("tool")).expect("Could not create file");
+        fs::File::create(temp_dir.join(".hidden")).expect("Could not create file");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert!(path.exists("tool"));
+        assert!(path.exists_type("tool", FileType::File));
+        assert!(!path.exists_type("tool", FileType::Directory));
+        assert!(path.exists_type("share/app", FileType::Directory));
+        assert!(path.exists(&temp_dir.join("tool").display().to_string()));
+        assert!(!path.exists("missing"));
+        assert!(!path.exists(".."));
+        assert!(!path.exists("../escape"));
+
+        assert!(path.exists(".hidden"));
+        path.set_skip_hidden(true);
+        assert!(!path.exists(".hidden"));
+        assert!(path.exists("tool"));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_report_records_skipped() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::create_dir(first_dir.join("testfile")).unwrap();
+        fs::File::create(second_dir.join("testfile")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+        let report = path.find_report("testfile", FileType::File);
+        assert_eq!(report.searched.len(), 2);
+        assert_eq!(report.matched, vec!(second_dir.join("testfile").display().to_string()));
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].entry, first_dir.join("testfile").display().to_string());
+        assert!(report.error.is_none());
+
+        let report = path.shadow_report();
+        assert_eq!(report.shadowed.len(), 1);
+        assert_eq!(report.shadowed[0].found, first_dir.join("testfile").display().to_string());
+        assert_eq!(report.shadowed[0].shadowed, vec!(second_dir.join("testfile").display().to_string()));
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn find_traced_records_compared() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::create_dir(first_dir.join("testfile")).unwrap();
+        fs::File::create(first_dir.join("other")).unwrap();
+        fs::File::create(second_dir.join("testfile")).unwrap();
+        fs::File::create(second_dir.join("last")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+        let (found, trace) = path.find_traced("testfile", FileType::File);
+        assert_eq!(found.unwrap(), FoundType::File(second_dir.join("testfile")));
+        assert_eq!(trace.report.searched.len(), 2);
+        assert_eq!(trace.report.matched, vec!(second_dir.join("testfile").display().to_string()));
+        assert_eq!(trace.report.skipped.len(), 1);
+        assert_eq!(trace.compared.len(), 4);
+        assert!(trace.compared.contains(&first_dir.join("other").display().to_string()));
+
+        let (found, trace) = path.find_traced("missing", FileType::File);
+        assert_eq!(found.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        assert!(trace.report.matched.is_empty());
+        assert_eq!(trace.compared.len(), 4);
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn find_all_in_order() {
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("testfile")).unwrap();
+        fs::File::create(second_dir.join("testfile")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+        assert_eq!(path.find_all("testfile", FileType::File).unwrap(),
+                   vec!(FoundType::File(first_dir.join("testfile")),
+                        FoundType::File(second_dir.join("testfile"))));
+        assert!(path.find_all("testfile", FileType::Directory).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[test]
+    fn find_matching_by_size() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("empty")).unwrap();
+        let mut file = fs::File::create(temp_dir.join("full")).unwrap();
+        file.write_all(b"test file contents").unwrap();
+        fs::create_dir(temp_dir.join("subdir")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        let found = path.find_matching(|entry| {
+            entry.metadata().map(|metadata| metadata.is_file() && metadata.len() > 0).unwrap_or(false)
+        }).expect("Could not search path");
+        assert_eq!(found, vec!(FoundType::File(temp_dir.join("full"))));
+
+        let found = path.find_matching(|entry| entry.file_name() == "subdir")
+            .expect("Could not search path");
+        assert_eq!(found, vec!(FoundType::Directory(temp_dir.join("subdir"))));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_multi_component_relative_path() {
+        let temp_dir = temp_dir();
+        fs::create_dir_all(temp_dir.join("share").join("app")).unwrap();
+        fs::File::create(temp_dir.join("share").join("app").join("config.toml")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+
+        assert_eq!(path.find_type("share/app/config.toml", FileType::File).unwrap(),
+                   FoundType::File(temp_dir.join("share").join("app").join("config.toml")));
+        assert_eq!(path.find_type("./share/app", FileType::Directory).unwrap(),
+                   FoundType::Directory(temp_dir.join("share").join("app")));
+        assert!(path.find_type("share/app", FileType::File).is_err());
+        assert!(path.find("share/missing.toml").is_err());
+        assert_eq!(path.find("share/../share/app").unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn find_absolute_path() {
+        let temp_dir = temp_dir();
+        let temp_file = temp_dir.join("testfile");
+        fs::File::create(&temp_file).unwrap();
+
+        let path = Simpath::new("MyName");
+        assert_eq!(path.find(&temp_file.to_string_lossy()).unwrap(), FoundType::File(temp_file.clone()));
+        assert_eq!(path.find_type(&temp_dir.to_string_lossy(), FileType::Directory).unwrap(),
+                   FoundType::Directory(temp_dir.clone()));
+        assert!(path.find_type(&temp_file.to_string_lossy(), FileType::Directory).is_err());
+        assert!(path.find(&temp_dir.join("missing").to_string_lossy()).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_only_executable_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first_dir = temp_dir();
+        let second_dir = temp_dir();
+        fs::File::create(first_dir.join("tool")).unwrap();
+        fs::File::create(second_dir.join("tool")).unwrap();
+        fs::set_permissions(second_dir.join("tool"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&first_dir);
+        path.add_directory(&second_dir);
+        assert_eq!(path.find("tool").unwrap(), FoundType::File(first_dir.join("tool")));
+
+        path.set_required_access(Access::Executable);
+        assert_eq!(path.find("tool").unwrap(), FoundType::File(second_dir.join("tool")));
+
+        fs::remove_file(second_dir.join("tool")).unwrap();
+        assert_eq!(path.find("tool").unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+        assert_eq!(path.find("other").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+        // clean-up
+        let _ = fs::remove_dir_all(first_dir);
+        let _ = fs::remove_dir_all(second_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_special_file_types() {
+        let mut path = Simpath::new("MyName");
+        path.add_directory("/dev");
+        assert_eq!(path.find_type("null", FileType::CharDevice).unwrap(),
+                   FoundType::CharDevice(PathBuf::from("/dev/null")));
+        assert_eq!(path.find("null").unwrap(), FoundType::CharDevice(PathBuf::from("/dev/null")));
+        assert!(path.find_type("null", FileType::BlockDevice).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_symlink_type() {
+        let temp_dir = temp_dir();
+        fs::File::create(temp_dir.join("testfile")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.join("testfile"), temp_dir.join("testlink"))
+            .expect("Could not create symlink");
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        assert_eq!(path.find_type("testlink", FileType::Symlink).unwrap(),
+                   FoundType::Symlink(temp_dir.join("testlink")));
+        assert!(path.find_type("testfile", FileType::Symlink).is_err());
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn find_with_unicode_normalization() {
+        let temp_dir = temp_dir();
+        // NFD form: 'e' followed by a combining acute accent
+        fs::File::create(temp_dir.join("cafe\u{301}")).unwrap();
+
+        let mut path = Simpath::new("MyName");
+        path.add_directory(&temp_dir);
+        // NFC form: a single precomposed 'é'
+        assert!(path.find("caf\u{e9}").is_err());
+
+        path.set_unicode_normalization(true);
+        assert_eq!(path.find("caf\u{e9}").unwrap(), FoundType::File(temp_dir.join("cafe\u{301}")));
+
+        // clean-up
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+}
@@ -0,0 +1,184 @@
+//! Sharing a `Simpath` between threads with a `SharedSimpath`, and changing one temporarily
+//! with a `SimpathGuard`.
+
+use std::io::Error;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::{FileType, FoundType, Simpath};
+
+impl Simpath {
+    /// Get a `SimpathGuard` through which the `Simpath` can be modified temporarily. Any changes
+    /// made through the guard (such as adding or removing entries, or changing the separator)
+    /// are rolled back when it is dropped, restoring the `Simpath` as it was when it was created.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new_with_separator("MyPath", ':');
+    ///     search_path.add_directory("/usr/bin");
+    ///     {
+    ///         let mut scoped = search_path.scoped();
+    ///         scoped.add_directory("/opt/tools/bin");
+    ///         println!("{:?}", scoped.find("tool"));
+    ///     }
+    ///     assert_eq!(search_path.to_env_string(), "/usr/bin");
+    /// }
+    /// ```
+    pub fn scoped(&mut self) -> SimpathGuard<'_> {
+        SimpathGuard { saved: Some(self.clone()), simpath: self }
+    }
+}
+
+/// `SharedSimpath` is a `Simpath` that can be shared between threads and updated while they use
+/// it. Finds take a read lock, so they can run concurrently, and `replace()` and
+/// `refresh_from_env()` update the whole search path at once under a write lock, so a find never
+/// sees a partially updated search path. Clones share the same `Simpath`.
+#[derive(Clone, Debug)]
+pub struct SharedSimpath {
+    inner: Arc<RwLock<Simpath>>,
+}
+
+impl SharedSimpath {
+    /// Create a `SharedSimpath` that shares `search_path`
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{SharedSimpath, Simpath};
+    ///
+    /// fn main() {
+    ///     let search_path = SharedSimpath::new(Simpath::new("PATH"));
+    ///     let worker_path = search_path.clone();
+    ///     let worker = std::thread::spawn(move || println!("{:?}", worker_path.find("ls")));
+    ///     search_path.refresh_from_env();
+    ///     worker.join().unwrap();
+    /// }
+    /// ```
+    pub fn new(search_path: Simpath) -> Self {
+        SharedSimpath { inner: Arc::new(RwLock::new(search_path)) }
+    }
+
+    /// Try to find a file or resource by name (not full path), as `Simpath::find()` does
+    pub fn find(&self, file_name: &str) -> Result<FoundType, Error> {
+        self.read().find(file_name)
+    }
+
+    /// Try to find an entry of a specific `FileType`, as `Simpath::find_type()` does
+    pub fn find_type(&self, file_name: &str, file_type: FileType) -> Result<FoundType, Error> {
+        self.read().find_type(file_name, file_type)
+    }
+
+    /// Find all the entries of a specific `FileType`, in search order, as
+    /// `Simpath::find_all()` does
+    pub fn find_all(&self, file_name: &str, file_type: FileType) -> Result<Vec<FoundType>, Error> {
+        self.read().find_all(file_name, file_type)
+    }
+
+    /// Get a copy of the `Simpath` as it is now
+    pub fn snapshot(&self) -> Simpath {
+        self.read().clone()
+    }
+
+    /// Replace the shared `Simpath` with `search_path`, returning the one it replaced
+    pub fn replace(&self, search_path: Simpath) -> Simpath {
+        std::mem::replace(&mut *self.write(), search_path)
+    }
+
+    /// Re-read the entries of the shared `Simpath` that came from environment variables, so that
+    /// changes to them are seen. Entries that were added in other ways are kept, after them.
+    pub fn refresh_from_env(&self) {
+        self.write().reload_env_entries();
+    }
+
+    // A panic in another thread while it held the lock cannot leave the `Simpath` partially
+    // updated, as updates replace it whole, so a poisoned lock is still used
+    fn read(&self) -> RwLockReadGuard<'_, Simpath> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Simpath> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<Simpath> for SharedSimpath {
+    fn from(search_path: Simpath) -> Self {
+        SharedSimpath::new(search_path)
+    }
+}
+
+/// `SimpathGuard` gives temporary mutable access to a `Simpath`, created using
+/// `Simpath::scoped()`. It dereferences to the `Simpath`, and when it is dropped the `Simpath` is
+/// restored to how it was when the guard was created.
+#[derive(Debug)]
+pub struct SimpathGuard<'a> {
+    simpath: &'a mut Simpath,
+    saved: Option<Simpath>,
+}
+
+impl Deref for SimpathGuard<'_> {
+    type Target = Simpath;
+
+    fn deref(&self) -> &Simpath {
+        self.simpath
+    }
+}
+
+impl DerefMut for SimpathGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Simpath {
+        self.simpath
+    }
+}
+
+impl Drop for SimpathGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            *self.simpath = saved;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::path::{Path, PathBuf};
+
+    use crate::{SharedSimpath, Simpath};
+
+    #[test]
+    fn shared_path_replace_and_refresh() {
+        let var_name = "SharedPath";
+        env::set_var(var_name, "/usr/bin:/bin");
+        let mut path = Simpath::new_with_separator(var_name, ':');
+        path.add_directory("/opt/bin");
+        let shared = SharedSimpath::new(path);
+
+        env::set_var(var_name, "/usr/local/bin");
+        shared.clone().refresh_from_env();
+        assert_eq!(shared.snapshot().directories().collect::<Vec<_>>(), vec!(PathBuf::from("/usr/local/bin"),
+                                                         PathBuf::from("/opt/bin")));
+
+        let old = shared.replace(Simpath::new("Other"));
+        assert_eq!(old.name(), var_name);
+        assert_eq!(shared.snapshot().name(), "Other");
+    }
+
+    #[test]
+    fn scoped_changes_are_rolled_back() {
+        let mut path = Simpath::new_with_separator("MyName", ':');
+        path.add_directory("/usr/bin");
+        {
+            let mut scoped = path.scoped();
+            scoped.add_directory("/opt/tools/bin");
+            scoped.retain(|entry| entry.path() != Some(Path::new("/usr/bin")));
+            scoped.set_separator(',');
+            assert_eq!(scoped.to_env_string(), "/opt/tools/bin");
+            assert_eq!(scoped.separator(), ',');
+        }
+
+        assert_eq!(path.to_env_string(), "/usr/bin");
+        assert_eq!(path.separator(), ':');
+    }
+}
@@ -0,0 +1,1486 @@
+//! The Url entries of a `Simpath`, and the HTTP requests made to find resources at them.
+//! Activated by the "urls" feature.
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use curl::easy::{Easy2, Handler, List, WriteError};
+use url::Url;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{Entry, EntrySource, FileType, FoundType, NameMatcher, PathName, Simpath};
+use super::directory::EntryInfo;
+use super::search::{SearchContext, SearchEntry, Stat};
+
+#[cfg(not(target_arch = "wasm32"))]
+// Collects the headers and body of the last response received, so that those of any redirects
+// are dropped
+#[derive(Default)]
+struct Collector {
+    headers: Vec<String>,
+    body: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Handler for Collector {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        self.body.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        let line = String::from_utf8_lossy(data).trim_end().to_string();
+        if line.starts_with("HTTP/") {
+            self.headers.clear();
+            self.body.clear();
+        }
+        if !line.is_empty() {
+            self.headers.push(line);
+        }
+        true
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+// The `User-Agent` sent with HTTP requests, unless another is set
+const DEFAULT_USER_AGENT: &str = concat!("simpath/", env!("CARGO_PKG_VERSION"));
+
+// The number of resources probed at the same time, unless another limit is set
+pub(crate) const DEFAULT_MAX_CONCURRENT_PROBES: usize = 4;
+
+// The options used to configure the HTTP requests made to probe and fetch resources
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HttpOptions {
+    proxy: Option<String>,
+    ca_bundle: Option<PathBuf>,
+    insecure: bool,
+    user_agent: Option<String>,
+    request_hook: Option<RequestHook>,
+    cache_dir: Option<PathBuf>,
+    // The addresses that hosts are resolved to, as "host:port:address"
+    resolve: Vec<String>,
+}
+
+// A response cached in the directory set with `Simpath::set_http_cache_dir()`, with the
+// validators the server sent for it
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Vec<u8>,
+}
+
+// A callback that can modify each HTTP request before it is sent
+type RequestCallback = dyn Fn(&mut HttpRequest) + Send + Sync;
+
+#[derive(Clone)]
+struct RequestHook(Arc<RequestCallback>);
+
+impl fmt::Debug for RequestHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RequestHook")
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct HttpClientHandle(pub(crate) Arc<dyn HttpClient>);
+
+impl fmt::Debug for HttpClientHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HttpClient")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+// The `HttpClient` used unless another is set, which sends requests using curl, configured
+// with the HTTP options of the search path and the proxy to use for the request, if any
+struct CurlClient<'a>(&'a HttpOptions, Option<String>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpClient for CurlClient<'_> {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let options = self.0;
+        let mut easy = Easy2::new(Collector::default());
+        easy.url(request.url.as_str())?;
+        if request.method == HttpMethod::Head {
+            easy.nobody(true)?;
+        }
+        if !request.headers.is_empty() {
+            let mut headers = List::new();
+            for header in &request.headers {
+                headers.append(header)?;
+            }
+            easy.http_headers(headers)?;
+        }
+        easy.useragent(options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))?;
+        easy.follow_location(request.follow_redirects)?;
+        if let Some(timeout) = request.timeout {
+            easy.timeout(timeout)?;
+        }
+        if let Some(proxy) = &self.1 {
+            easy.proxy(proxy)?;
+        }
+        if let Some(ca_bundle) = &options.ca_bundle {
+            easy.cainfo(ca_bundle)?;
+        }
+        if options.insecure {
+            easy.ssl_verify_peer(false)?;
+            easy.ssl_verify_host(false)?;
+        }
+        if !options.resolve.is_empty() {
+            let mut resolve = List::new();
+            for entry in &options.resolve {
+                resolve.append(entry)?;
+            }
+            easy.resolve(resolve)?;
+        }
+        easy.perform()?;
+
+        let status = easy.response_code()?;
+        let collector = std::mem::take(easy.get_mut());
+        Ok(HttpResponse { status, headers: collector.headers, body: collector.body })
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+// The `HttpClient` used in the browser unless another is set, which sends synchronous
+// `XMLHttpRequest`s, as the browser controls proxies, certificates and the `User-Agent`
+struct BrowserClient;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl HttpClient for BrowserClient {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let js_error = |e| Error::other(format!("{:?}", e));
+        let xhr = web_sys::XmlHttpRequest::new().map_err(js_error)?;
+        let method = match request.method {
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Get => "GET",
+        };
+        xhr.open_with_async(method, request.url.as_str(), false).map_err(js_error)?;
+        for header in &request.headers {
+            if let Some((name, value)) = header.split_once(':') {
+                xhr.set_request_header(name.trim(), value.trim()).map_err(js_error)?;
+            }
+        }
+        // Synchronous requests cannot ask for binary responses, so have each byte of the
+        // response returned as one character
+        xhr.override_mime_type("text/plain; charset=x-user-defined").map_err(js_error)?;
+        xhr.send().map_err(js_error)?;
+
+        let status = u32::from(xhr.status().map_err(js_error)?);
+        let headers = xhr.get_all_response_headers().map_err(js_error)?
+            .lines().map(|line| line.trim_end().to_string()).filter(|line| !line.is_empty())
+            .collect();
+        let body = xhr.response_text().map_err(js_error)?.unwrap_or_default()
+            .chars().map(|c| c as u32 as u8).collect();
+        Ok(HttpResponse { status, headers, body })
+    }
+}
+
+/// `UrlEntry` is a base Url in a `Simpath`, along with information about it
+#[derive(Clone, Debug, PartialEq)]
+pub struct UrlEntry {
+    pub(crate) url: Url,
+    profile: Option<UrlProfile>,
+    pub(crate) info: EntryInfo,
+}
+
+impl UrlEntry {
+    /// Get the Url
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the `UrlProfile` of the requests made for the Url, if it was added with one
+    pub fn profile(&self) -> Option<&UrlProfile> {
+        self.profile.as_ref()
+    }
+}
+
+// A Url is searched by checking if its last path segment is the name searched for, so a Url that
+// ends with '/file_name' is found
+impl SearchEntry for Url {
+    fn location(&self) -> String {
+        self.to_string()
+    }
+
+    fn search(&self, context: &mut SearchContext<'_>, file_name: &str, file_type: &FileType)
+        -> Result<(), Error> {
+        if *file_type != FileType::Resource && *file_type != FileType::Any {
+            return Ok(());
+        }
+
+        let simpath = context.simpath;
+        simpath.check_cancelled(file_name)?;
+        trace_event!(debug, url = %self, "checking url");
+        simpath.count(Stat::Url);
+        context.searched(self);
+        context.compared(self);
+
+        let mut segments = self.path_segments()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "Could not get path segments"))?;
+        if segments.next_back() == Some(file_name) {
+            simpath.count_hit(self.to_string());
+            context.add_found(FoundType::Resource(self.clone()));
+        }
+        Ok(())
+    }
+
+    fn search_where(&self, context: &mut SearchContext<'_>, matcher: &dyn NameMatcher, file_type: &FileType)
+        -> Result<(), Error> {
+        if (*file_type == FileType::Resource || *file_type == FileType::Any)
+            && self.path_segments().and_then(|mut segments| segments.next_back())
+                .map(|name| matcher.matches(name)).unwrap_or(false) {
+            context.add_found(FoundType::Resource(self.clone()));
+        }
+        Ok(())
+    }
+
+    fn contains(&self, found: &FoundType) -> bool {
+        matches!(found, FoundType::Resource(resource) if resource == self)
+    }
+}
+
+/// `ProbeMethod` is the HTTP request used to check a resource exists and get its metadata
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProbeMethod {
+    /// Send a `HEAD` request (the default)
+    Head,
+    /// Send a `GET` request for just the first byte of the resource (`Range: bytes=0-0`),
+    /// for servers that reject `HEAD` requests
+    RangedGet,
+}
+
+/// `ResourceMetadata` is the information about a resource that was returned in the headers of
+/// the response to the request probing it, as returned by `Simpath::find_resource()`.
+/// Each field is `None` if the server did not return the corresponding header.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ResourceMetadata {
+    /// The `Content-Type` of the resource, e.g. "text/html; charset=utf-8"
+    pub content_type: Option<String>,
+    /// The `Content-Length` of the resource, in bytes
+    pub content_length: Option<u64>,
+    /// The `Last-Modified` date of the resource, as sent by the server
+    pub last_modified: Option<String>,
+}
+
+impl ResourceMetadata {
+    // Extract the metadata from the header lines of a response
+    fn from_headers(headers: &[String]) -> Self {
+        let mut metadata = ResourceMetadata::default();
+        for header in headers {
+            if let Some((name, value)) = header.split_once(':') {
+                let value = value.trim();
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-type" => metadata.content_type = Some(value.to_string()),
+                    "content-length" if metadata.content_length.is_none() =>
+                        metadata.content_length = value.parse().ok(),
+                    // The response to a ranged GET gives the full length as "bytes 0-0/LENGTH"
+                    "content-range" => metadata.content_length = value.rsplit('/').next()
+                        .and_then(|length| length.parse().ok()),
+                    "last-modified" => metadata.last_modified = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        metadata
+    }
+}
+
+// Get the value of the header called `name` (in any case) in `headers`
+fn header_value(headers: &[String], name: &str) -> Option<String> {
+    headers.iter().filter_map(|header| header.split_once(':'))
+        .find(|(header_name, _)| header_name.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// `UrlProfile` is the configuration of the HTTP requests made for a Url in a `Simpath`, set when
+/// it is added with `Simpath::add_url_with_profile()`, for Urls that need different credentials,
+/// timeouts or redirect policies from the others. It is applied to the requests for the Url, and
+/// for resources under it, before the hook set with `Simpath::on_request()`.
+///
+/// ```
+/// extern crate simpath;
+/// use simpath::UrlProfile;
+/// use std::time::Duration;
+///
+/// fn main() {
+///     let profile = UrlProfile::new().basic_auth("user", "secret").timeout(Duration::from_secs(10))
+///         .follow_redirects(false);
+///     println!("{:?}", profile);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UrlProfile {
+    headers: Vec<String>,
+    timeout: Option<Duration>,
+    follow_redirects: Option<bool>,
+}
+
+impl UrlProfile {
+    /// Create a `UrlProfile` that makes requests as they are for other Urls
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticate requests using HTTP basic authentication with `user` and `password`
+    pub fn basic_auth(self, user: &str, password: &str) -> Self {
+        let credentials = base64(format!("{}:{}", user, password).as_bytes());
+        self.header(&format!("Authorization: Basic {}", credentials))
+    }
+
+    /// Authenticate requests with the bearer `token`
+    pub fn bearer_token(self, token: &str) -> Self {
+        self.header(&format!("Authorization: Bearer {}", token))
+    }
+
+    /// Send the `header` (in the form "Name: value") with requests
+    pub fn header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_string());
+        self
+    }
+
+    /// Give up requests that have not completed within `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set whether redirects are followed (by default they are)
+    pub fn follow_redirects(mut self, follow_redirects: bool) -> Self {
+        self.follow_redirects = Some(follow_redirects);
+        self
+    }
+}
+
+// Encode `bytes` in base64, with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// `HttpMethod` is the method of an `HttpRequest`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    /// A `HEAD` request, for the headers of the response only
+    Head,
+    /// A `GET` request, for the headers and the body of the response
+    Get,
+}
+
+/// `HttpRequest` is an HTTP request about to be sent to probe or fetch a resource, as passed to
+/// the hook set with `Simpath::on_request()`, which can modify it (e.g. to sign it), and then
+/// to the `HttpClient` that sends it
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpRequest {
+    /// The method of the request
+    pub method: HttpMethod,
+    /// The Url of the resource requested
+    pub url: Url,
+    /// The extra headers to send with the request, each in the form "Name: value"
+    pub headers: Vec<String>,
+    /// The time after which the request should be given up, if there is a limit
+    pub timeout: Option<Duration>,
+    /// Whether redirects should be followed
+    pub follow_redirects: bool,
+}
+
+/// `HttpResponse` is the response to an `HttpRequest`, as returned by an `HttpClient`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpResponse {
+    /// The HTTP status code of the response, e.g. 200
+    pub status: u32,
+    /// The headers of the response, each in the form "Name: value"
+    pub headers: Vec<String>,
+    /// The body of the response (empty for the response to a `HEAD` request)
+    pub body: Vec<u8>,
+}
+
+/// `HttpClient` sends the HTTP requests made to probe and fetch resources. By default they are
+/// sent using curl, but another client can be set with `Simpath::set_http_client()`, e.g. a
+/// `MockHttpClient` to test code that finds resources without accessing the network.
+///
+/// Requests that cannot be sent return an error, but responses with any status are returned
+/// as an `HttpResponse`, to be checked by the `Simpath`.
+pub trait HttpClient: Send + Sync {
+    /// Send `request` and return its response
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// `MockHttpClient` is an `HttpClient` for tests that does not access the network. It returns
+/// the responses that have been set for Urls with `respond()` (and a "404 Not Found" response
+/// for other Urls) and records the requests it is sent, which can be got with `requests()`.
+///
+/// ```
+/// extern crate simpath;
+/// extern crate url;
+/// use std::sync::Arc;
+/// use simpath::{HttpResponse, MockHttpClient, Simpath};
+/// use url::Url;
+///
+/// fn main() {
+///     let url = Url::parse("https://example.com/tool").unwrap();
+///     let client = Arc::new(MockHttpClient::new());
+///     client.respond(&url, HttpResponse { status: 200, headers: vec!("Content-Length: 42".into()),
+///                                         body: vec!() });
+///
+///     let mut search_path = Simpath::new("WEB");
+///     search_path.add_url(&url);
+///     search_path.set_http_client(client.clone());
+///
+///     let (found, metadata) = search_path.find_resource("tool").unwrap();
+///     assert_eq!(found, url);
+///     assert_eq!(metadata.content_length, Some(42));
+///     assert_eq!(client.requests().len(), 1);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MockHttpClient {
+    responses: Mutex<HashMap<Url, HttpResponse>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockHttpClient {
+    /// Create a new `MockHttpClient`, with no responses set
+    pub fn new() -> Self {
+        MockHttpClient::default()
+    }
+
+    /// Set the `response` returned for requests to `url`
+    pub fn respond(&self, url: &Url, response: HttpResponse) {
+        self.responses.lock().unwrap_or_else(PoisonError::into_inner).insert(url.clone(), response);
+    }
+
+    /// Get the requests that have been sent to this client, in the order they were sent
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+impl HttpClient for MockHttpClient {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner).push(request.clone());
+        let responses = self.responses.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut response = responses.get(&request.url).cloned()
+            .unwrap_or(HttpResponse { status: 404, headers: vec!(), body: vec!() });
+        if request.method == HttpMethod::Head {
+            response.body.clear();
+        }
+        Ok(response)
+    }
+}
+
+impl Simpath {
+    // Sort entries found into the search order of the entries of the search path they were in
+    pub(crate) fn sort_by_search_order(&self, found: &mut [FoundType]) {
+        let order = self.search_entries();
+        found.sort_by_key(|found| order.iter().position(|entry| entry.contains(found)).unwrap_or(usize::MAX));
+    }
+
+    /// Get the URLs that are included in the Search Path, in the order they are searched, which
+    /// is interleaved with the directories as shown by `entries()` and `to_env_string()`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    /// use std::env;
+    ///
+    /// fn main() {
+    ///     env::set_var("TEST", "http://ibm.com,https://hp.com");
+    ///     let search_path = Simpath::new("TEST");
+    ///     println!("URLs in Search Path: {:?}", search_path.urls().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn urls(&self) -> impl Iterator<Item = &Url> + '_ {
+        self.url_entries().map(UrlEntry::url)
+    }
+
+    // Iterate over the Url entries, enabled or not, in search order
+    pub(crate) fn url_entries(&self) -> impl Iterator<Item = &UrlEntry> {
+        self.entries.iter().filter_map(Entry::as_url)
+    }
+
+    // Check if there are any Url entries
+    pub(crate) fn has_urls(&self) -> bool {
+        self.url_entries().next().is_some()
+    }
+
+    // Check if `url` is one of the Url entries
+    pub(crate) fn has_url(&self, url: &Url) -> bool {
+        self.url_entries().any(|entry| entry.url == *url)
+    }
+
+    /// Find a resource by name in the URLs of the search path, as `find_all()` does for
+    /// `FileType::Resource`, and probe them (with a `HEAD` request by default, see
+    /// `set_probe_method()`) to confirm they exist and get their `ResourceMetadata` (content
+    /// type, length and last modification date), so that the caller can decide if it should be
+    /// fetched.
+    ///
+    /// The matching URLs are probed concurrently (see `set_max_concurrent_probes()`) and the
+    /// first of them, in the order of the search path, that the server responds to with an
+    /// accepted status (see `set_accepted_status_codes()`) is returned. If none is confirmed
+    /// the error from the last probe is returned.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate url;
+    /// use simpath::Simpath;
+    /// use url::Url;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("TEST");
+    ///     search_path.add_url(&Url::parse("https://www.ibm.com/es-es").unwrap());
+    ///     match search_path.find_resource("es-es") {
+    ///         Ok((url, metadata)) => println!("'{}' has Content-Type {:?}", url, metadata.content_type),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn find_resource(&self, resource_name: &str) -> Result<(Url, ResourceMetadata), Error> {
+        let urls: Vec<Url> = self.find_all(resource_name, FileType::Resource)?.into_iter()
+            .filter_map(|found| match found {
+                FoundType::Resource(url) => Some(url),
+                _ => None,
+            })
+            .collect();
+
+        let mut last_error = Error::new(ErrorKind::NotFound,
+                                        format!("Could not find resource '{}' in {}",
+                                                resource_name, PathName(&self.name)));
+        // Threads cannot be started in the browser, so there resources are probed one at a time
+        let max_concurrent_probes = if cfg!(target_arch = "wasm32") { 1 } else { self.max_concurrent_probes.max(1) };
+        for batch in urls.chunks(max_concurrent_probes) {
+            self.check_cancelled(resource_name)?;
+            let results: Vec<Result<ResourceMetadata, Error>> = if let [url] = batch {
+                vec!(self.probe(url))
+            } else {
+                thread::scope(|scope| {
+                    let probes: Vec<_> = batch.iter()
+                        .map(|url| scope.spawn(move || self.probe(url)))
+                        .collect();
+                    probes.into_iter()
+                        .map(|probe| probe.join()
+                            .unwrap_or_else(|_| Err(Error::other("A resource probe panicked"))))
+                        .collect()
+                })
+            };
+
+            for (url, result) in batch.iter().zip(results) {
+                match result {
+                    Ok(metadata) => return Ok((url.clone(), metadata)),
+                    Err(e) => {
+                        trace_event!(warn, url = %url, error = %e, "probe failed");
+                        last_error = e;
+                    }
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Set the maximum number of resources that are probed at the same time by
+    /// `find_resource()`. The default is 4, and 1 probes them one after the other.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_max_concurrent_probes(8);
+    /// }
+    /// ```
+    pub fn set_max_concurrent_probes(&mut self, max: usize) {
+        self.max_concurrent_probes = max;
+    }
+
+    /// Set the `HttpClient` used to send the HTTP requests made to probe and fetch resources,
+    /// instead of curl. The proxy, CA bundle, insecure mode and `User-Agent` set on this
+    /// `Simpath` only apply to curl, but the hook set with `on_request()` is applied to each
+    /// request before it is passed to `client`. See `MockHttpClient` for an example.
+    pub fn set_http_client<C: HttpClient + 'static>(&mut self, client: Arc<C>) {
+        self.http_client = Some(HttpClientHandle(client));
+    }
+
+    // Probe a resource using the configured method and get its metadata from the response headers
+    fn probe(&self, url: &Url) -> Result<ResourceMetadata, Error> {
+        let response = match self.probe_method {
+            ProbeMethod::Head => self.send(HttpMethod::Head, url, vec!())?,
+            ProbeMethod::RangedGet => self.send(HttpMethod::Get, url, vec!("Range: bytes=0-0".into()))?,
+        };
+
+        if !self.accepts_status(response.status) {
+            return Err(Error::new(ErrorKind::NotFound,
+                                  format!("Could not get '{}', the HTTP status was {}", url, response.status)));
+        }
+
+        Ok(ResourceMetadata::from_headers(&response.headers))
+    }
+
+    // Fetch the contents of a resource. If a copy of it is cached the request is conditional on
+    // it having changed, and the cached copy is returned if the server says it has not.
+    pub(crate) fn fetch(&self, url: &Url) -> Result<Vec<u8>, Error> {
+        let cached = self.cached_response(url);
+        let mut headers = vec!();
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                headers.push(format!("If-None-Match: {}", etag));
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                headers.push(format!("If-Modified-Since: {}", last_modified));
+            }
+        }
+
+        let response = self.send(HttpMethod::Get, url, headers)?;
+        if response.status == 304 {
+            if let Some(cached) = cached {
+                return Ok(cached.body);
+            }
+        }
+        if !(200..300).contains(&response.status) {
+            return Err(Error::new(ErrorKind::NotFound,
+                                  format!("Could not fetch '{}', the HTTP status was {}", url, response.status)));
+        }
+
+        self.cache_response(url, &response);
+        Ok(response.body)
+    }
+
+    /// Set a directory to cache the resources fetched in (e.g. to check their contents), along
+    /// with the `ETag` and `Last-Modified` headers the server sent with them. When a cached
+    /// resource is fetched again the request is conditional, and if the server responds
+    /// "304 Not Modified" the cached copy is used instead of downloading it again. The cache
+    /// can be shared by several search paths, and by runs of a program (e.g. in build loops).
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_http_cache_dir(std::env::temp_dir().join("simpath-cache"));
+    /// }
+    /// ```
+    pub fn set_http_cache_dir<P: AsRef<Path>>(&mut self, cache_dir: P) {
+        self.http.cache_dir = Some(cache_dir.as_ref().to_path_buf());
+    }
+
+    // Get the paths of the files the body and the validators of the response for `url` are
+    // cached in, if there is a cache directory
+    fn cache_files(&self, url: &Url) -> Option<(PathBuf, PathBuf)> {
+        // A 64-bit FNV-1a hash of the Url, which is stable between runs and versions of Rust
+        let hash = url.as_str().bytes().fold(0xcbf2_9ce4_8422_2325_u64,
+                                             |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+        self.http.cache_dir.as_ref().map(|dir| (dir.join(format!("{:016x}.body", hash)),
+                                                dir.join(format!("{:016x}.headers", hash))))
+    }
+
+    // Get the cached response for `url`, if there is one
+    fn cached_response(&self, url: &Url) -> Option<CachedResponse> {
+        let (body_file, headers_file) = self.cache_files(url)?;
+        let headers = fs::read_to_string(headers_file).ok()?;
+        let mut lines = headers.lines();
+        // The Url is recorded, so that another Url with the same hash is not given its response
+        if lines.next() != Some(url.as_str()) {
+            return None;
+        }
+        let headers: Vec<String> = lines.map(String::from).collect();
+
+        Some(CachedResponse {
+            etag: header_value(&headers, "etag"),
+            last_modified: header_value(&headers, "last-modified"),
+            body: fs::read(body_file).ok()?,
+        })
+    }
+
+    // Cache a response for `url`, if there is a cache directory and the response has validators
+    // that it can be revalidated with. Errors caching it are passed to the error handler.
+    fn cache_response(&self, url: &Url, response: &HttpResponse) {
+        let (body_file, headers_file) = match self.cache_files(url) {
+            Some(files) => files,
+            None => return,
+        };
+        let validators: Vec<String> = ["ETag", "Last-Modified"].iter()
+            .filter_map(|name| header_value(&response.headers, name).map(|value| format!("{}: {}", name, value)))
+            .collect();
+        if validators.is_empty() {
+            return;
+        }
+
+        let headers = format!("{}\n{}\n", url, validators.join("\n"));
+        let cached = self.http.cache_dir.as_ref().map(fs::create_dir_all).unwrap_or(Ok(()))
+            .and_then(|_| fs::write(&body_file, &response.body))
+            .and_then(|_| fs::write(&headers_file, headers));
+        if let Err(e) = cached {
+            self.notify_error(&headers_file, &e);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    // Get the proxy to use for a request: the one set with `set_proxy()`, or else the one in
+    // the environment for the scheme of the Url (curl ignores the upper case `HTTP_PROXY` itself)
+    fn proxy_for(&self, url: &Url) -> Option<String> {
+        if self.http.proxy.is_some() {
+            return self.http.proxy.clone();
+        }
+
+        let vars: &[&str] = match url.scheme() {
+            "https" => &["https_proxy", "HTTPS_PROXY"],
+            "http" => &["http_proxy", "HTTP_PROXY"],
+            _ => &[],
+        };
+        vars.iter().filter_map(|var| self.env_var_string(var)).find(|proxy| !proxy.is_empty())
+    }
+
+    // Create a request, pass it to the request hook and send it using the `HttpClient`
+    fn send(&self, method: HttpMethod, url: &Url, headers: Vec<String>) -> Result<HttpResponse, Error> {
+        let mut request = HttpRequest { method, url: url.clone(), headers, timeout: None, follow_redirects: true };
+        if let Some(profile) = self.url_profile(url) {
+            request.headers.splice(0..0, profile.headers.iter().cloned());
+            request.timeout = profile.timeout;
+            request.follow_redirects = profile.follow_redirects.unwrap_or(true);
+        }
+        if let Some(RequestHook(hook)) = &self.http.request_hook {
+            hook(&mut request);
+        }
+
+        match &self.http_client {
+            Some(HttpClientHandle(client)) => client.send(&request),
+            #[cfg(not(target_arch = "wasm32"))]
+            None => CurlClient(&self.http, self.proxy_for(url)).send(&request),
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+            None => BrowserClient.send(&request),
+            #[cfg(all(not(feature = "wasm"), target_arch = "wasm32"))]
+            None => Err(Error::new(ErrorKind::Unsupported,
+                                   "The \"wasm\" feature or an HttpClient is needed to send requests")),
+        }
+    }
+
+    /// Add a Url to the list of Base Urls to be used when searching for resources.
+    ///
+    /// The Url is not checked, so one whose host does not exist only causes failures when
+    /// searching, unless strict mode has been set with `set_strict_urls()`, in which case a Url
+    /// that cannot be reached is not added. Use `add_url_checked()` to know if it was added.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate url;
+    ///
+    /// use simpath::Simpath;
+    /// use url::Url;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.add_url(&Url::parse("http://ibm.com").unwrap());
+    ///     println!("Urls in Search Path: {:?}", search_path.urls().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn add_url(&mut self, url: &Url) {
+        self.push_url(url, EntrySource::Programmatic);
+    }
+
+    /// Add a Url to the list of Base Urls, as `add_url()` does, but only after checking it can
+    /// be reached, by probing it as `check_url()` does. The error from the probe is returned if
+    /// it cannot be reached, and the Url is not added.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate url;
+    ///
+    /// use simpath::Simpath;
+    /// use url::Url;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     if let Err(e) = search_path.add_url_checked(&Url::parse("https://no-way-this-exists.invalid").unwrap()) {
+    ///         println!("Url not added: {}", e);
+    ///     }
+    /// }
+    /// ```
+    pub fn add_url_checked(&mut self, url: &Url) -> Result<(), Error> {
+        self.check_url(url)?;
+        self.insert_url(url, EntrySource::Programmatic);
+        Ok(())
+    }
+
+    /// Set strict mode for Urls: when it is on, each Url added to the search path (with `add()`,
+    /// `add_url()` or from an environment variable) is probed, as `check_url()` does, and is
+    /// not added if it cannot be reached. It is off by default.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_strict_urls(true);
+    ///     search_path.add("https://no-way-this-exists.invalid");
+    ///     assert!(search_path.urls().next().is_none());
+    /// }
+    /// ```
+    pub fn set_strict_urls(&mut self, strict: bool) {
+        self.strict_urls = strict;
+    }
+
+    /// Set the HTTP request used to probe resources, for servers that reject `HEAD` requests
+    /// (e.g. with "405 Method Not Allowed") but accept `GET` requests. The default is
+    /// `ProbeMethod::Head`.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::{ProbeMethod, Simpath};
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_probe_method(ProbeMethod::RangedGet);
+    /// }
+    /// ```
+    pub fn set_probe_method(&mut self, method: ProbeMethod) {
+        self.probe_method = method;
+    }
+
+    /// Set the HTTP status codes of the response to a probe that mean the resource exists.
+    /// By default (or if `codes` is empty) any success (2xx) status is accepted.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     // This server responds "403 Forbidden" to anonymous requests for resources that exist
+    ///     search_path.set_accepted_status_codes(vec!(200, 206, 403));
+    /// }
+    /// ```
+    pub fn set_accepted_status_codes<I: IntoIterator<Item = u32>>(&mut self, codes: I) {
+        self.accepted_statuses = codes.into_iter().collect();
+    }
+
+    /// Set the proxy used for the HTTP requests made to probe and fetch resources, e.g.
+    /// "http://proxy.example.com:8080". If it is not set, the proxy in the `https_proxy` or
+    /// `HTTPS_PROXY` environment variable is used for "https" Urls and the one in `http_proxy` or
+    /// `HTTP_PROXY` for "http" Urls. Hosts in `NO_PROXY` are accessed directly.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_proxy("http://proxy.example.com:8080");
+    /// }
+    /// ```
+    pub fn set_proxy(&mut self, proxy: &str) {
+        self.http.proxy = Some(proxy.to_string());
+    }
+
+    /// Set a file of CA certificates (in PEM format) used to verify the servers of "https" Urls,
+    /// instead of the system's default ones, e.g. for servers using certificates issued by a
+    /// company's own CA.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_ca_bundle("/etc/ssl/certs/company-ca.pem");
+    /// }
+    /// ```
+    pub fn set_ca_bundle<P: AsRef<Path>>(&mut self, ca_bundle: P) {
+        self.http.ca_bundle = Some(ca_bundle.as_ref().to_path_buf());
+    }
+
+    /// Set if the certificates of servers of "https" Urls should NOT be verified. This is
+    /// insecure, as it allows the server to be impersonated, and is off by default.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_insecure(true);
+    /// }
+    /// ```
+    pub fn set_insecure(&mut self, insecure: bool) {
+        self.http.insecure = insecure;
+    }
+
+    /// Set the `User-Agent` sent with the HTTP requests made for resources, for services that
+    /// block unknown agents. The default is "simpath/" followed by the version of this crate.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.set_user_agent("my-tool/1.0");
+    /// }
+    /// ```
+    pub fn set_user_agent(&mut self, user_agent: &str) {
+        self.http.user_agent = Some(user_agent.to_string());
+    }
+
+    /// Resolve `host` to `address` for requests to `port` on it, instead of looking it up in DNS,
+    /// as curl's `--resolve` option does. This redirects the requests for the Urls in the path
+    /// (e.g. to a mirror in an air-gapped network, or a server in a test) without changing them,
+    /// so the `Host` header sent and the certificate expected are still those of `host`.
+    /// `address` is an IP address, with an IPv6 one in brackets (e.g. "[::1]"). It applies to
+    /// the requests sent with curl, not to those sent by an `HttpClient` that has been set.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.add_resolve("plugins.example.com", 443, "10.0.0.5");
+    /// }
+    /// ```
+    pub fn add_resolve(&mut self, host: &str, port: u16, address: &str) {
+        self.http.resolve.push(format!("{}:{}:{}", host, port, address));
+    }
+
+    /// Set a `hook` to be called with every HTTP request made to probe or fetch a resource,
+    /// before it is sent, so it can modify the request: e.g. to sign it by adding headers or
+    /// query parameters to its Url.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// use simpath::Simpath;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     search_path.on_request(|request| request.headers.push("Authorization: Bearer token".into()));
+    /// }
+    /// ```
+    pub fn on_request<F>(&mut self, hook: F) where F: Fn(&mut HttpRequest) + Send + Sync + 'static {
+        self.http.request_hook = Some(RequestHook(Arc::new(hook)));
+    }
+
+    // Check if the HTTP status of the response to a probe means the resource exists
+    fn accepts_status(&self, status: u32) -> bool {
+        if self.accepted_statuses.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            self.accepted_statuses.contains(&status)
+        }
+    }
+
+    // Add a Url to the list of Base Urls, recording where it came from
+    pub(crate) fn push_url(&mut self, url: &Url, source: EntrySource) {
+        if self.strict_urls && !self.has_url(url) {
+            if let Err(error) = self.check_url(url) {
+                return self.reject_entry(url.to_string(), error.to_string(), source);
+            }
+        }
+
+        self.insert_url(url, source);
+    }
+
+    /// Add a Url to the search path, as `add_url()` does, with a `UrlProfile` configuring the
+    /// HTTP requests made for it and the resources under it (e.g. with its own credentials). If
+    /// the Url is already in the search path its profile is replaced.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate url;
+    /// use simpath::{Simpath, UrlProfile};
+    /// use url::Url;
+    ///
+    /// fn main() {
+    ///     let mut search_path = Simpath::new("WEB");
+    ///     let url = Url::parse("https://mirror.example.com/tools/").unwrap();
+    ///     search_path.add_url_with_profile(&url, UrlProfile::new().bearer_token("token"));
+    /// }
+    /// ```
+    pub fn add_url_with_profile(&mut self, url: &Url, profile: UrlProfile) {
+        self.add_url(url);
+        if let Some(entry) = self.entries_mut().iter_mut().find_map(|entry| match entry {
+            Entry::Url(url_entry) if url_entry.url == *url => Some(url_entry),
+            _ => None,
+        }) {
+            entry.profile = Some(profile);
+        }
+    }
+
+    // Get the profile of the requests for `url`: that of the Url entry it is, or else of the
+    // longest Url entry it is under
+    fn url_profile(&self, url: &Url) -> Option<&UrlProfile> {
+        self.url_entries()
+            .filter_map(|entry| Self::segments_under(&entry.url, url).map(|depth| (depth, entry)))
+            .max_by_key(|(depth, _)| *depth)
+            .and_then(|(_, entry)| entry.profile.as_ref())
+    }
+
+    // If `url` is `base` or under it (on the same scheme, host and port, with the path segments
+    // of `base` as the first whole segments of its path), get the number of segments of `base`
+    fn segments_under(base: &Url, url: &Url) -> Option<usize> {
+        if base.scheme() != url.scheme() || base.host_str() != url.host_str()
+            || base.port_or_known_default() != url.port_or_known_default() {
+            return None;
+        }
+
+        let base_segments: Vec<&str> = base.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+        let mut segments = url.path_segments()?;
+        if base_segments.iter().all(|base_segment| segments.next() == Some(base_segment)) {
+            Some(base_segments.len())
+        } else {
+            None
+        }
+    }
+
+    // Add a Url to the end of the list, if it is not already in it, recording where it came from
+    pub(crate) fn insert_url(&mut self, url: &Url, source: EntrySource) {
+        if self.has_url(url) {
+            trace_event!(debug, search_path = %self.name, entry = %url, "ignored duplicate entry");
+        } else if self.len() >= self.limits.max_entries {
+            self.notify_error(Path::new(url.as_str()), &self.entry_limit_error());
+        } else {
+            self.append_entry(Entry::Url(UrlEntry { url: url.clone(), profile: None, info: EntryInfo::new(source) }));
+        }
+    }
+
+    /// Check that the resource at `url` exists, by probing it as `find_resource()` does (using
+    /// the probe method, accepted status codes and HTTP options of this `Simpath`), returning
+    /// its `ResourceMetadata`. `url` does not need to be in the search path.
+    ///
+    /// ```
+    /// extern crate simpath;
+    /// extern crate url;
+    /// use simpath::Simpath;
+    /// use url::Url;
+    ///
+    /// fn main() {
+    ///     let search_path = Simpath::new("WEB");
+    ///     match search_path.check_url(&Url::parse("https://www.ibm.com").unwrap()) {
+    ///         Ok(metadata) => println!("It exists, with Content-Type {:?}", metadata.content_type),
+    ///         Err(e) => println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    pub fn check_url(&self, url: &Url) -> Result<ResourceMetadata, Error> {
+        self.probe(url)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use std::env;
+    use url::Url;
+    use std::sync::{Arc, Mutex};
+    use curl::easy::Handler;
+    use std::time::Duration;
+    use super::{base64, Collector, HttpMethod, HttpResponse, MockHttpClient, ProbeMethod, ResourceMetadata, UrlProfile};
+    use crate::{FileType, FoundType, Simpath};
+
+    const BASE_URL: &str = "https://www.ibm.com";
+    const EXISTING_RESOURCE: &str = "es-es";
+
+    #[test]
+    fn create_from_env() {
+        let var_name = "MyPath";
+        env::set_var(var_name, BASE_URL);
+        let path = Simpath::new_with_separator(var_name, ',');
+        assert_eq!(path.urls().count(), 1);
+        assert_eq!(path.directories().count(), 0);
+        assert!(path.urls().any(|url| *url == Url::parse(BASE_URL)
+            .expect("Could not parse URL")));
+    }
+
+    #[test]
+    fn urls_searched_in_order_with_directories() {
+        let temp_dir = crate::test::temp_dir();
+        std::fs::File::create(temp_dir.join("es-es")).unwrap();
+        let var_name = "UrlsBeforeDirectories";
+        env::set_var(var_name, format!("{}/{},{}", BASE_URL, EXISTING_RESOURCE, temp_dir.display()));
+        let path = Simpath::new_with_separator(var_name, ',');
+        let url = Url::parse(&format!("{}/{}", BASE_URL, EXISTING_RESOURCE)).unwrap();
+
+        assert_eq!(path.to_env_string(), format!("{},{}", url, temp_dir.display()));
+        assert_eq!(path.find(EXISTING_RESOURCE).unwrap(), FoundType::Resource(url.clone()));
+        assert_eq!(path.find_all(EXISTING_RESOURCE, FileType::Any).unwrap(),
+                   vec!(FoundType::Resource(url.clone()), FoundType::File(temp_dir.join("es-es"))));
+        assert_eq!(path.clone().freeze().find(EXISTING_RESOURCE).unwrap(), FoundType::Resource(url.clone()));
+        assert_eq!(path.entries()[0].url(), Some(&url));
+        assert_eq!(path.entries()[1].path(), Some(temp_dir.as_path()));
+        assert_eq!(path.entry_sources()[0].0, url.to_string());
+        assert!(path.check_entry(1).is_ok());
+        assert!(format!("{:#}", path).contains("0: url"));
+
+        let mut path = Simpath::new_with_separator("DirectoriesBeforeUrls", ',');
+        path.add(temp_dir.display().to_string());
+        path.add(format!("{}/{}", BASE_URL, EXISTING_RESOURCE));
+        assert_eq!(path.find(EXISTING_RESOURCE).unwrap(), FoundType::File(temp_dir.join("es-es")));
+        assert_eq!(path.freeze().find(EXISTING_RESOURCE).unwrap(), FoundType::File(temp_dir.join("es-es")));
+
+        // clean-up
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn add_url_that_exists() {
+        let mut path = Simpath::new_with_separator("test", ',');
+        path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
+        assert_eq!(path.urls().count(), 1);
+        assert_eq!(path.directories().count(), 0);
+        assert!(path.urls().any(|url| *url == Url::parse(BASE_URL)
+            .expect("Could not parse URL")));
+    }
+
+    #[test]
+    fn cannot_add_same_url_twice() {
+        let mut path = Simpath::new_with_separator("test", ',');
+        path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
+        path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
+        assert_eq!(path.urls().count(), 1);
+        assert_eq!(path.directories().count(), 0);
+        assert!(path.urls().any(|url| *url == Url::parse(BASE_URL)
+            .expect("Could not parse URL")));
+    }
+
+    #[test]
+    fn find_resource_not_exist() {
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url"));
+        assert!(search_path.find_type("/no-way-this-exists", FileType::Resource).is_err(),
+                "should not find the resource");
+    }
+
+    #[test]
+    fn find_existing_resource() {
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&Url::parse(BASE_URL).expect("Could not parse Url")
+            .join(EXISTING_RESOURCE).expect("Could not join to Url"));
+        search_path.find_type(EXISTING_RESOURCE, FileType::Resource).expect("Could not find resource");
+    }
+
+    #[test]
+    fn find_resource_metadata_not_exist() {
+        // The mock client responds with 404 to the probe of the resource
+        let client = Arc::new(MockHttpClient::new());
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&Url::parse("https://example.com/no-way-this-exists").expect("Could not parse Url"));
+        search_path.set_http_client(client.clone());
+        assert!(search_path.find_resource("no-way-this-exists").is_err(),
+                "should not find the resource");
+        assert_eq!(client.requests().len(), 1);
+    }
+
+    #[test]
+    fn accepted_status_codes() {
+        let mut search_path = Simpath::new("TEST");
+        assert!(search_path.accepts_status(200));
+        assert!(search_path.accepts_status(206));
+        assert!(!search_path.accepts_status(403));
+        search_path.set_accepted_status_codes(vec!(200, 403));
+        assert!(search_path.accepts_status(403));
+        assert!(!search_path.accepts_status(206));
+        search_path.set_accepted_status_codes(vec!());
+        assert!(search_path.accepts_status(204));
+    }
+
+    #[test]
+    fn explicit_proxy_is_used() {
+        let mut search_path = Simpath::new("TEST");
+        search_path.set_proxy("http://proxy.example.com:8080");
+        let url = Url::parse(BASE_URL).expect("Could not parse Url");
+        assert_eq!(search_path.proxy_for(&url), Some("http://proxy.example.com:8080".into()));
+
+        let mut env = std::collections::HashMap::new();
+        env.insert("HTTPS_PROXY".to_string(), "http://sourced.example.com:3128".to_string());
+        let search_path = Simpath::new_with_env_source("TEST", Arc::new(env));
+        assert_eq!(search_path.proxy_for(&url), Some("http://sourced.example.com:3128".into()));
+    }
+
+    #[test]
+    fn request_hook_is_called() {
+        let requested = Arc::new(Mutex::new(vec!()));
+        let recorder = requested.clone();
+        let mut search_path = Simpath::new("TEST");
+        // Nothing listens on port 1, so the request fails without using the network
+        search_path.add_url(&Url::parse("http://127.0.0.1:1/resource").expect("Could not parse Url"));
+        search_path.on_request(move |request| {
+            request.headers.push("Authorization: Bearer token".into());
+            recorder.lock().unwrap().push(request.url.clone());
+        });
+        assert!(search_path.find_resource("resource").is_err());
+        assert_eq!(*requested.lock().unwrap(),
+                   vec!(Url::parse("http://127.0.0.1:1/resource").expect("Could not parse Url")));
+    }
+
+    #[test]
+    fn all_matching_resources_are_probed() {
+        let requested = Arc::new(Mutex::new(vec!()));
+        let recorder = requested.clone();
+        let mut search_path = Simpath::new("TEST");
+        search_path.set_max_concurrent_probes(2);
+        for port in 1..4 {
+            search_path.add_url(&Url::parse(&format!("http://127.0.0.1:{}/resource", port))
+                .expect("Could not parse Url"));
+        }
+        search_path.on_request(move |request| recorder.lock().unwrap().push(request.url.clone()));
+        assert!(search_path.find_resource("resource").is_err());
+        assert_eq!(requested.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn find_first_confirmed_resource_with_mock() {
+        let missing = Url::parse("https://example.com/missing/tool").expect("Could not parse Url");
+        let existing = Url::parse("https://example.org/tool").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&existing, HttpResponse { status: 200,
+            headers: vec!("Content-Type: application/octet-stream".into()), body: vec!() });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&missing);
+        search_path.add_url(&existing);
+        search_path.set_http_client(client.clone());
+
+        let (url, metadata) = search_path.find_resource("tool").expect("Could not find resource");
+        assert_eq!(url, existing);
+        assert_eq!(metadata.content_type, Some("application/octet-stream".into()));
+        assert_eq!(client.requests().len(), 2);
+        assert!(client.requests().iter().all(|request| request.method == HttpMethod::Head));
+    }
+
+    #[test]
+    fn ranged_get_probe_with_mock() {
+        let url = Url::parse("https://example.com/tool").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&url, HttpResponse { status: 206,
+            headers: vec!("Content-Range: bytes 0-0/99".into()), body: vec!(0) });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&url);
+        search_path.set_probe_method(ProbeMethod::RangedGet);
+        search_path.set_http_client(client.clone());
+
+        let (_, metadata) = search_path.find_resource("tool").expect("Could not find resource");
+        assert_eq!(metadata.content_length, Some(99));
+        let requests = client.requests();
+        assert_eq!(requests[0].method, HttpMethod::Get);
+        assert_eq!(requests[0].headers, vec!("Range: bytes=0-0".to_string()));
+    }
+
+    #[test]
+    fn find_resource_by_contents_with_mock() {
+        let url = Url::parse("https://example.com/image").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&url, HttpResponse { status: 200, headers: vec!(), body: b"\x89PNG".to_vec() });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&url);
+        search_path.set_http_client(client);
+
+        let found = search_path.find_where_contents(|name: &str| name == "image",
+                                                    |contents: &[u8]| contents.starts_with(b"\x89PNG"))
+            .expect("Could not find resource");
+        assert_eq!(found, FoundType::Resource(url));
+    }
+
+    #[test]
+    fn check_url_and_entry_with_mock() {
+        let existing = Url::parse("https://example.com/tool").expect("Could not parse Url");
+        let missing = Url::parse("https://example.com/missing").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&existing, HttpResponse { status: 200, headers: vec!(), body: vec!() });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&existing);
+        search_path.set_http_client(client);
+
+        assert!(search_path.check_url(&existing).is_ok());
+        assert!(search_path.check_url(&missing).is_err());
+        assert!(search_path.check_entry(0).is_ok());
+        assert!(search_path.check_entry(1).is_err());
+    }
+
+    #[test]
+    fn checked_and_strict_urls_with_mock() {
+        let existing = Url::parse("https://example.com/tools/").expect("Could not parse Url");
+        let missing = Url::parse("https://example.com/missing/").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&existing, HttpResponse { status: 200, headers: vec!(), body: vec!() });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.set_http_client(client);
+        assert!(search_path.add_url_checked(&missing).is_err());
+        assert!(search_path.add_url_checked(&existing).is_ok());
+        assert_eq!(search_path.urls().count(), 1);
+
+        let mut search_path = Simpath::new("TEST");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&existing, HttpResponse { status: 200, headers: vec!(), body: vec!() });
+        search_path.set_http_client(client);
+        search_path.set_strict_urls(true);
+        search_path.add_url(&missing);
+        search_path.add(existing.as_str());
+        assert_eq!(search_path.urls().collect::<Vec<_>>(), vec!(&existing));
+    }
+
+    #[test]
+    fn fetch_revalidates_cached_resources() {
+        let temp_dir = crate::test::temp_dir();
+        let url = Url::parse("https://example.com/tool").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+        client.respond(&url, HttpResponse { status: 200, headers: vec!("ETag: \"v1\"".into()),
+                                            body: b"contents".to_vec() });
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_url(&url);
+        search_path.set_http_client(client.clone());
+        search_path.set_http_cache_dir(temp_dir.join("cache"));
+        assert_eq!(search_path.fetch(&url).expect("Could not fetch"), b"contents".to_vec());
+
+        client.respond(&url, HttpResponse { status: 304, headers: vec!(), body: vec!() });
+        assert_eq!(search_path.fetch(&url).expect("Could not fetch"), b"contents".to_vec());
+        let requests = client.requests();
+        assert!(requests[0].headers.is_empty());
+        assert_eq!(requests[1].headers, vec!("If-None-Match: \"v1\"".to_string()));
+
+        client.respond(&url, HttpResponse { status: 200, headers: vec!("Last-Modified: today".into()),
+                                            body: b"changed".to_vec() });
+        assert_eq!(search_path.fetch(&url).expect("Could not fetch"), b"changed".to_vec());
+        client.respond(&url, HttpResponse { status: 304, headers: vec!(), body: vec!() });
+        assert_eq!(search_path.fetch(&url).expect("Could not fetch"), b"changed".to_vec());
+        assert_eq!(client.requests()[3].headers, vec!("If-Modified-Since: today".to_string()));
+
+        // clean-up
+        let _ = std::fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn host_resolved_to_address() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Could not listen");
+        let port = listener.local_addr().expect("Could not get address").port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Could not accept");
+            let mut request = vec!();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => request.extend_from_slice(&buffer[..read]),
+                }
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let url = Url::parse(&format!("http://mirror.invalid:{}/tool", port)).expect("Could not parse Url");
+        let mut search_path = Simpath::new("TEST");
+        search_path.add_resolve("mirror.invalid", port, "127.0.0.1");
+        assert!(search_path.check_url(&url).is_ok());
+        let request = server.join().expect("Server failed");
+        assert!(request.contains(&format!("Host: mirror.invalid:{}", port)));
+    }
+
+    #[test]
+    fn requests_use_url_profiles() {
+        let private = Url::parse("https://example.com/private/").expect("Could not parse Url");
+        let resource = private.join("tool").expect("Could not join to Url");
+        let public = Url::parse("https://example.com/public/tool").expect("Could not parse Url");
+        let similar = Url::parse("https://example.com/private-other/tool").expect("Could not parse Url");
+        let client = Arc::new(MockHttpClient::new());
+
+        let mut search_path = Simpath::new("TEST");
+        search_path.set_http_client(client.clone());
+        search_path.on_request(|request| request.headers.push("X-Hook: called".into()));
+        search_path.add_url_with_profile(&private, UrlProfile::new().basic_auth("user", "pass")
+            .timeout(Duration::from_secs(5)).follow_redirects(false));
+        search_path.add_url(&public);
+
+        let _ = search_path.check_url(&resource);
+        let _ = search_path.check_url(&public);
+        let requests = client.requests();
+        assert_eq!(requests[0].headers, vec!("Authorization: Basic dXNlcjpwYXNz".to_string(), "X-Hook: called".into()));
+        assert_eq!(requests[0].timeout, Some(Duration::from_secs(5)));
+        assert!(!requests[0].follow_redirects);
+        assert_eq!(requests[1].headers, vec!("X-Hook: called".to_string()));
+        assert_eq!(requests[1].timeout, None);
+        assert!(requests[1].follow_redirects);
+
+        let _ = search_path.check_url(&similar);
+        let _ = search_path.check_url(&Url::parse("http://example.com/private/tool").expect("Could not parse Url"));
+        let requests = client.requests();
+        assert_eq!(requests[2].headers, vec!("X-Hook: called".to_string()));
+        assert_eq!(requests[3].headers, vec!("X-Hook: called".to_string()));
+    }
+
+    #[test]
+    fn base64_encoding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn resource_length_from_content_range() {
+        let headers: Vec<String> = vec!("HTTP/1.1 206 Partial Content", "Content-Length: 1",
+                                        "Content-Range: bytes 0-0/5678")
+            .into_iter().map(String::from).collect();
+        assert_eq!(ResourceMetadata::from_headers(&headers).content_length, Some(5678));
+    }
+
+    #[test]
+    fn resource_metadata_from_last_response_headers() {
+        let headers: Vec<String> = vec!("HTTP/1.1 301 Moved Permanently", "Content-Type: text/plain",
+                                        "HTTP/2 200", "content-type: text/html", "Content-Length: 1234",
+                                        "Last-Modified: Wed, 21 Oct 2015 07:28:00 GMT", "")
+            .into_iter().map(String::from).collect();
+        let mut collector = Collector::default();
+        for header in &headers {
+            assert!(collector.header(header.as_bytes()));
+        }
+        let metadata = ResourceMetadata::from_headers(&collector.headers);
+        assert_eq!(metadata, ResourceMetadata {
+            content_type: Some("text/html".into()),
+            content_length: Some(1234),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".into()),
+        });
+    }
+
+    #[test]
+    fn contains_url_that_exists() {
+        let var_name = "MyPath";
+        env::set_var(var_name, BASE_URL);
+        let path = Simpath::new_with_separator(var_name, ',');
+        assert!(path.contains(BASE_URL));
+    }
+
+    #[test]
+    fn display_path_with_directory_and_url() {
+        let var_name = "MyPath";
+        env::set_var(var_name, format!("~,{}", BASE_URL));
+        let path = Simpath::new_with_separator(var_name, ',');
+        println!("{}", path);
+    }
+}